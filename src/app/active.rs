@@ -0,0 +1,141 @@
+use egui::{Id, RichText, TextStyle, Vec2};
+
+use super::{
+    meters::{level_meter_ui, meter_frame},
+    time::time_drag,
+    AppState,
+};
+use crate::{
+    cues::{CueRunning, CueTime},
+    Cue, MultitypeCue, Project,
+};
+
+const METER_SIZE: Vec2 = Vec2::new(80., 12.);
+
+#[derive(Debug)]
+pub struct ActiveCuesPanel {
+    pub open: bool,
+    // length of the fade-out buttons
+    fade_length: CueTime,
+}
+
+impl Default for ActiveCuesPanel {
+    fn default() -> Self {
+        Self {
+            open: true,
+            fade_length: CueTime::from_millis(3000),
+        }
+    }
+}
+
+// where a running cue lives in the project
+#[derive(Clone, Copy, Debug)]
+enum ActiveCue {
+    List(usize, usize),
+    Cart(usize, usize),
+}
+
+impl ActiveCue {
+    fn cue_mut(self, project: &mut Project) -> &mut MultitypeCue {
+        match self {
+            ActiveCue::List(l, i) => &mut project.cue_lists[l][i],
+            ActiveCue::Cart(c, i) => &mut project.carts[c].cell_mut(i).cue,
+        }
+    }
+}
+
+// every cue that is running or paused, with the name of its list or cart
+fn active_cues(project: &Project) -> Vec<(ActiveCue, String)> {
+    let mut active = Vec::new();
+    for (l, list) in project.cue_lists.iter().enumerate() {
+        for i in list.active_cues() {
+            active.push((ActiveCue::List(l, i), list.name.clone()));
+        }
+    }
+    for (c, cart) in project.carts.iter().enumerate() {
+        for (i, cell) in cart.cells().enumerate() {
+            if cell.cue.running() != CueRunning::Stopped {
+                active.push((ActiveCue::Cart(c, i), cart.name.clone()));
+            }
+        }
+    }
+    active
+}
+
+pub fn active_cues_panel_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    ui.heading("Active Cues");
+    ui.horizontal(|ui| {
+        ui.label("Fade out over:");
+        ui.add(
+            time_drag(
+                &mut state.active_cues_panel.fade_length,
+                state.project.time_format,
+                CueTime::ZERO..=CueTime::from_millis(60000),
+            )
+            .speed(0.1),
+        );
+    });
+    ui.separator();
+
+    let active = active_cues(&state.project);
+    if active.is_empty() {
+        ui.weak("Nothing is playing");
+        return;
+    }
+    // keep the countdowns and meters moving
+    ui.ctx().request_repaint();
+
+    let frame = meter_frame(ui);
+    let fade_length = state.active_cues_panel.fade_length;
+    let format = state.project.time_format;
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (which, parent) in active {
+            let cue = which.cue_mut(&mut state.project);
+            let key = format!("{}:{}", parent, cue.get_id());
+
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(cue.get_id()).text_style(TextStyle::Monospace));
+                ui.strong(cue.get_name());
+                ui.weak(&parent);
+            });
+
+            ui.horizontal(|ui| {
+                let elapsed = cue.elapsed().unwrap_or_default();
+                let remaining = match cue.remaining() {
+                    Some(rem) => format!("-{}", rem.max(CueTime::ZERO).format(format)),
+                    None => "--".to_string(),
+                };
+                ui.label(
+                    RichText::new(format!("{} {}", elapsed.format(format), remaining))
+                        .text_style(TextStyle::Monospace),
+                );
+
+                let readings = cue.levels(frame).unwrap_or_default();
+                level_meter_ui(
+                    ui,
+                    Id::new(("active_meter", &key)),
+                    &readings,
+                    METER_SIZE,
+                    false,
+                );
+
+                let paused = cue.running() == CueRunning::Paused;
+                if ui.button(if paused { "▶" } else { "⏸" }).clicked() {
+                    cue.set_paused(!paused);
+                }
+                if ui.button("⏹").on_hover_text("Stop").clicked() {
+                    cue.stop();
+                }
+                if ui
+                    .button("Fade")
+                    .on_hover_text("Fade out and stop")
+                    .clicked()
+                {
+                    cue.fade_out(fade_length);
+                }
+            });
+            ui.separator();
+        }
+    });
+}
@@ -0,0 +1,153 @@
+use std::{path::PathBuf, time::Instant};
+
+use egui::RichText;
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+
+use super::CueballApp;
+use crate::project::backup;
+use crate::Project;
+
+// saving preferences, remembered between sessions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SaveSettings {
+    // how many old copies of the project to keep around when saving
+    pub backup_count: usize,
+    // seconds between autosaves to the recovery file, or 0 to disable
+    pub autosave_interval: u64,
+}
+
+impl Default for SaveSettings {
+    fn default() -> Self {
+        Self {
+            backup_count: 5,
+            autosave_interval: 60,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Autosave {
+    last_autosave: Instant,
+    // serialized project as of the last save or autosave, so that unchanged
+    // projects aren't written again
+    last_written: Option<String>,
+    // recovery file found when the project was opened, waiting on the
+    // operator to restore or discard it
+    pub recovery_prompt: Option<PathBuf>,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            last_autosave: Instant::now(),
+            last_written: None,
+            recovery_prompt: None,
+        }
+    }
+}
+
+impl Autosave {
+    // remember `project` as saved, and check for an autosave of it that is
+    // newer than the saved file
+    pub fn project_opened(&mut self, project: &Project) {
+        self.last_written = project.to_json().ok();
+        self.recovery_prompt = backup::recovery_available(project.path.as_deref());
+    }
+
+    // the project was just saved, so its recovery file is stale
+    pub fn project_saved(&mut self, project: &Project) {
+        self.last_written = project.to_json().ok();
+        self.remove_recovery(project);
+    }
+
+    pub fn remove_recovery(&self, project: &Project) {
+        if let Some(recovery) = backup::recovery_path(project.path.as_deref()) {
+            if recovery.exists() {
+                if let Err(err) = std::fs::remove_file(&recovery) {
+                    warn!("Could not remove {}: {}", recovery.display(), err);
+                }
+            }
+        }
+    }
+}
+
+impl CueballApp {
+    pub(super) fn autosave_tick(&mut self, ctx: &egui::Context) {
+        let interval = self.save_settings.autosave_interval;
+        if interval == 0 {
+            return;
+        }
+        let interval = std::time::Duration::from_secs(interval);
+
+        let autosave = &mut self.state.autosave;
+        if autosave.last_autosave.elapsed() >= interval {
+            autosave.last_autosave = Instant::now();
+
+            match self.state.project.to_json() {
+                Ok(json) if autosave.last_written.as_ref() != Some(&json) => {
+                    if let Some(recovery) =
+                        backup::recovery_path(self.state.project.path.as_deref())
+                    {
+                        if let Some(dir) = recovery.parent() {
+                            let _ = std::fs::create_dir_all(dir);
+                        }
+                        match backup::write_atomic(&recovery, json.as_bytes()) {
+                            Ok(()) => {
+                                debug!("Autosaved to {}", recovery.display());
+                                autosave.last_written = Some(json);
+                            }
+                            Err(err) => error!("Autosave failed: {}", err),
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => error!("Autosave failed: {}", err),
+            }
+        }
+
+        // make sure we wake up for the next autosave even if nothing happens
+        ctx.request_repaint_after(interval);
+    }
+
+    pub(super) fn recovery_prompt_ui(&mut self, ctx: &egui::Context) {
+        let recovery = match &self.state.autosave.recovery_prompt {
+            Some(r) => r.clone(),
+            None => return,
+        };
+
+        egui::Modal::new(egui::Id::new("recovery_prompt")).show(ctx, |ui| {
+            ui.heading("Recover unsaved changes?");
+            ui.label(format!(
+                "{} has changes that were autosaved but never saved.",
+                self.state.project.name
+            ));
+            ui.label(RichText::new(recovery.display().to_string()).weak());
+
+            ui.horizontal(|ui| {
+                if ui.button("Restore").clicked() {
+                    let project_path = self.state.project.path.clone();
+                    match Project::open(&recovery) {
+                        Ok(mut restored) => {
+                            // keep saving to the real project file
+                            restored.path = project_path;
+                            self.load_project(restored);
+                            // the restored changes still need saving
+                            self.state.autosave.last_written = None;
+                            self.state.modified.mark_unsaved();
+                        }
+                        Err(err) => error!("Failed to restore {}: {}", recovery.display(), err),
+                    }
+                    self.state.autosave.recovery_prompt = None;
+                }
+                if ui.button("Discard").clicked() {
+                    if let Err(err) = std::fs::remove_file(&recovery) {
+                        warn!("Could not remove {}: {}", recovery.display(), err);
+                    }
+                    self.state.autosave.recovery_prompt = None;
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,199 @@
+use egui::{Align2, Color32, FontId, Rect, RichText, Sense, Stroke, Vec2};
+
+use super::{
+    shortcuts::{hotkey_edit_ui, KeyBinding, KeyBindings},
+    AppState,
+};
+use crate::{
+    cues::{AudioCue, BonkCue, ControlCue, CueRunning, CueTime, RemarkCue},
+    Cue, MultitypeCue,
+};
+
+const MIN_CELL_SIZE: Vec2 = Vec2::new(96., 48.);
+
+#[derive(Debug, Default)]
+pub struct CartView {
+    // cart shown in place of the cue lists
+    pub active: Option<usize>,
+    // cell whose cue is shown in the inspector
+    pub selected_cell: Option<usize>,
+}
+
+pub fn cart_ui(ui: &mut egui::Ui, state: &mut AppState, c: usize) {
+    let cart = &state.project.carts[c];
+    let (rows, columns) = (cart.rows.max(1), cart.columns.max(1));
+
+    let hidden = cart.hidden_cells();
+    if hidden != 0 {
+        ui.label(
+            RichText::new(format!("{} cues are outside the grid", hidden)).color(Color32::YELLOW),
+        );
+    }
+
+    // share the available space between the cells
+    let spacing = ui.spacing().item_spacing;
+    let available = ui.available_size();
+    let cell_size = Vec2::new(
+        (available.x - spacing.x * (columns - 1) as f32) / columns as f32,
+        (available.y - spacing.y * (rows - 1) as f32) / rows as f32,
+    )
+    .max(MIN_CELL_SIZE);
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new(("cart", c))
+            .spacing(spacing)
+            .show(ui, |ui| {
+                for row in 0..rows {
+                    for column in 0..columns {
+                        cart_cell_ui(ui, state, c, row, column, cell_size);
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+fn cart_cell_ui(
+    ui: &mut egui::Ui,
+    state: &mut AppState,
+    c: usize,
+    row: usize,
+    column: usize,
+    size: Vec2,
+) {
+    let (rect, resp) = ui.allocate_exact_size(size, Sense::click());
+    let visuals = ui.visuals().clone();
+    let painter = ui.painter();
+
+    let cell = state.project.carts[c].cell_index(row, column);
+    let Some(i) = cell else {
+        painter.rect_stroke(
+            rect,
+            4.,
+            Stroke::new(1., visuals.weak_text_color()),
+            egui::StrokeKind::Inside,
+        );
+        if !state.show_mode {
+            resp.context_menu(|ui| add_cue_menu_ui(ui, state, c, row, column));
+        }
+        return;
+    };
+
+    let cell = state.project.carts[c].cell(i);
+    let cue = &cell.cue;
+    let fill = match cue.running() {
+        CueRunning::Running => Color32::from_rgb(0, 96, 0),
+        CueRunning::Paused => Color32::from_rgb(96, 96, 0),
+        CueRunning::Stopped => visuals.widgets.inactive.bg_fill,
+    };
+    painter.rect_filled(rect, 4., fill);
+
+    // progress through the cue
+    if let (Some(el), Some(len)) = (cue.elapsed(), cue.length()) {
+        if len > CueTime::ZERO {
+            let mut done = rect;
+            let progress = (el.as_secs() / len.as_secs()).clamp(0., 1.) as f32;
+            done.set_width(rect.width() * progress);
+            painter.rect_filled(done, 4., Color32::from_rgba_unmultiplied(0, 200, 0, 64));
+        }
+        ui.ctx().request_repaint();
+    }
+
+    let selected = state.cart_view.active == Some(c) && state.cart_view.selected_cell == Some(i);
+    let stroke = if selected {
+        visuals.selection.stroke
+    } else if resp.hovered() {
+        visuals.widgets.hovered.fg_stroke
+    } else {
+        Stroke::new(1., visuals.weak_text_color())
+    };
+    painter.rect_stroke(rect, 4., stroke, egui::StrokeKind::Inside);
+
+    let text_color = visuals.strong_text_color();
+    let inner = Rect::from_min_max(rect.min + Vec2::splat(4.), rect.max - Vec2::splat(4.));
+    painter.text(
+        inner.left_top(),
+        Align2::LEFT_TOP,
+        cue.get_id(),
+        FontId::monospace(12.),
+        text_color,
+    );
+    if let Some(key) = cue.get_hotkey() {
+        painter.text(
+            inner.right_top(),
+            Align2::RIGHT_TOP,
+            key,
+            FontId::monospace(12.),
+            text_color,
+        );
+    }
+    painter.text(
+        inner.center(),
+        Align2::CENTER_CENTER,
+        cue.get_name(),
+        FontId::proportional(14.),
+        text_color,
+    );
+    let time = cue.remaining().or(cue.length());
+    if let Some(time) = time {
+        painter.text(
+            inner.right_bottom(),
+            Align2::RIGHT_BOTTOM,
+            time.format(state.project.time_format),
+            FontId::monospace(12.),
+            text_color,
+        );
+    }
+
+    if resp.clicked() {
+        state.project.toggle_cart_cell(c, i);
+    }
+    if !state.show_mode {
+        resp.context_menu(|ui| {
+            let bindings = KeyBindings::new(&state.project, &state.shortcut_keys);
+            let cart = &mut state.project.carts[c];
+            ui.horizontal(|ui| {
+                ui.label("Hotkey:");
+                let cue = &mut cart.cell_mut(i).cue;
+                let mut key = cue.get_hotkey().cloned();
+                let binding = KeyBinding::Cart(c, i);
+                hotkey_edit_ui(ui, &mut key, 80., &bindings, binding)
+                    .on_hover_text("Key name, e.g. A, F1 or Num1. Leave empty for none");
+                cue.set_hotkey(key);
+            });
+            if ui.button("Edit Cue").clicked() {
+                state.cart_view.selected_cell = Some(i);
+                ui.close();
+            }
+            ui.menu_button("Replace With", |ui| {
+                add_cue_menu_ui(ui, state, c, row, column)
+            });
+            if ui.button("Clear Cell").clicked() {
+                state.project.carts[c].remove_cell(i);
+                state.cart_view.selected_cell = None;
+                ui.close();
+            }
+        });
+    }
+}
+
+// buttons for putting a new cue in a cell
+fn add_cue_menu_ui(ui: &mut egui::Ui, state: &mut AppState, c: usize, row: usize, column: usize) {
+    let id = state.project.carts[c].get_new_cue_id().to_string();
+    let cue = if ui.button("Audio").clicked() {
+        Some(MultitypeCue::Audio(AudioCue::with_id(id)))
+    } else if ui.button("Remark").clicked() {
+        Some(MultitypeCue::Remark(RemarkCue::with_id(id)))
+    } else if ui.button("Bonk").clicked() {
+        Some(MultitypeCue::Bonk(BonkCue::with_id(id)))
+    } else if ui.button("Control").clicked() {
+        Some(MultitypeCue::Control(ControlCue::with_id(id)))
+    } else {
+        None
+    };
+    if let Some(cue) = cue {
+        let i = state.project.carts[c].set_cell(row, column, cue);
+        state.cart_view.selected_cell = Some(i);
+        ui.close();
+    }
+}
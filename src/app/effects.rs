@@ -0,0 +1,201 @@
+use egui::{DragValue, RichText};
+
+use super::AppState;
+use crate::{
+    audio::{Effect, EffectChain, EffectSlot, MAX_DELAY},
+    cues::CueTime,
+};
+
+#[derive(Debug, Default)]
+pub struct OutputEffectsWindow {
+    pub open: bool,
+}
+
+pub fn output_effects_ui(ctx: &egui::Context, state: &mut AppState) {
+    let mut open = state.output_effects_window.open;
+    egui::Window::new("Output Effects")
+        .open(&mut open)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.add_enabled_ui(!state.show_mode, |ui| {
+                let mut chain = state.project.output_effects.clone();
+                if effect_chain_ui(ui, "output_effects", &mut chain) {
+                    state.project.output_effects = chain;
+                    state.project.apply_output_settings();
+                }
+            });
+        });
+    state.output_effects_window.open = open;
+}
+
+// a chain as a list of effects, each with its settings, a bypass toggle and
+// buttons to move or remove it. returns whether anything changed
+pub(super) fn effect_chain_ui(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    chain: &mut EffectChain,
+) -> bool {
+    let before = chain.clone();
+    let id = ui.make_persistent_id(id_salt);
+
+    // (from, to) index of a slot being moved, and a slot being removed
+    let mut moved = None;
+    let mut removed = None;
+    let count = chain.0.len();
+    for (i, slot) in chain.0.iter_mut().enumerate() {
+        ui.push_id(id.with(i), |ui| {
+            ui.horizontal(|ui| {
+                ui.toggle_value(&mut slot.bypass, "Bypass");
+                let name = RichText::new(slot.effect.name()).strong();
+                ui.label(if slot.bypass { name.weak() } else { name });
+                effect_settings_ui(ui, &mut slot.effect);
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                    if ui
+                        .add_enabled(i + 1 < count, egui::Button::new("⏷").small())
+                        .clicked()
+                    {
+                        moved = Some((i, i + 1));
+                    }
+                    if ui
+                        .add_enabled(i > 0, egui::Button::new("⏶").small())
+                        .clicked()
+                    {
+                        moved = Some((i, i - 1));
+                    }
+                });
+            });
+        });
+    }
+    if chain.0.is_empty() {
+        ui.weak("No effects");
+    }
+    if let Some((from, to)) = moved {
+        chain.0.swap(from, to);
+    }
+    if let Some(i) = removed {
+        chain.0.remove(i);
+    }
+
+    ui.menu_button("Add Effect", |ui| {
+        for effect in Effect::ITER {
+            if ui.button(effect.name()).clicked() {
+                chain.0.push(EffectSlot::new(effect));
+                ui.close();
+            }
+        }
+    });
+
+    *chain != before
+}
+
+fn freq(value: &mut f32) -> DragValue<'_> {
+    DragValue::new(value)
+        .range(10.0..=20000.0)
+        .speed(5.)
+        .max_decimals(0)
+        .suffix(" Hz")
+}
+
+fn q(value: &mut f32) -> DragValue<'_> {
+    DragValue::new(value)
+        .range(0.1..=20.0)
+        .speed(0.01)
+        .prefix("Q ")
+}
+
+fn db(value: &mut f32) -> DragValue<'_> {
+    DragValue::new(value)
+        .range(-60.0..=24.0)
+        .speed(0.1)
+        .max_decimals(1)
+        .suffix(" dB")
+}
+
+// a time stored in seconds, shown in milliseconds
+fn ms(value: &mut f32, max: f32) -> DragValue<'_> {
+    DragValue::from_get_set(move |set| {
+        if let Some(v) = set {
+            *value = (v / 1000.) as f32;
+        }
+        *value as f64 * 1000.
+    })
+    .range(0.0..=max as f64 * 1000.)
+    .speed(0.1)
+    .max_decimals(2)
+    .suffix(" ms")
+}
+
+// a delay time, shown in milliseconds
+fn delay_ms(value: &mut CueTime) -> DragValue<'_> {
+    DragValue::from_get_set(move |set| {
+        if let Some(v) = set {
+            *value = CueTime::from_secs(v / 1000.);
+        }
+        value.as_secs() * 1000.
+    })
+    .range(0.0..=MAX_DELAY.as_secs() * 1000.)
+    .speed(0.1)
+    .max_decimals(2)
+    .suffix(" ms")
+}
+
+fn effect_settings_ui(ui: &mut egui::Ui, effect: &mut Effect) {
+    match effect {
+        Effect::Eq {
+            freq: f,
+            gain,
+            q: quality,
+        } => {
+            ui.add(freq(f));
+            ui.add(db(gain));
+            ui.add(q(quality));
+        }
+        Effect::HighPass {
+            freq: f,
+            q: quality,
+        }
+        | Effect::LowPass {
+            freq: f,
+            q: quality,
+        } => {
+            ui.add(freq(f));
+            ui.add(q(quality));
+        }
+        Effect::Compressor {
+            threshold,
+            ratio,
+            attack,
+            release,
+            makeup,
+        } => {
+            ui.label("Threshold");
+            ui.add(db(threshold));
+            ui.add(
+                DragValue::new(ratio)
+                    .range(1.0..=100.0)
+                    .speed(0.1)
+                    .max_decimals(1)
+                    .suffix(":1"),
+            );
+            ui.label("Attack");
+            ui.add(ms(attack, 1.));
+            ui.label("Release");
+            ui.add(ms(release, 5.));
+            ui.label("Makeup");
+            ui.add(db(makeup));
+        }
+        Effect::Limiter { ceiling, release } => {
+            ui.label("Ceiling");
+            ui.add(db(ceiling));
+            ui.label("Release");
+            ui.add(ms(release, 5.));
+        }
+        Effect::Delay { time } => {
+            ui.add(delay_ms(time));
+        }
+    }
+}
@@ -1,28 +1,34 @@
-use std::{
-    fs::File,
-    io::BufReader,
-    sync::{Arc, Mutex},
-    time::Duration,
-};
+use std::{path::Path, time::Duration};
 
-use anyhow::anyhow;
-use egui::{Color32, Id, Pos2, Rect, RichText, Sense, Stroke, TextEdit, TextStyle};
+use egui::{Color32, Id, RichText, Shape, Stroke, TextEdit, TextStyle, Vec2};
 use log::{error, warn};
 use rfd::FileDialog;
-use rodio::{Decoder, Source};
 
-use crate::cues::AudioCue;
+use crate::{
+    app::{
+        effects::effect_chain_ui,
+        meters::{level_meter_ui, meter_frame},
+        stage::StagePlot,
+        time::time_drag,
+    },
+    audio::{analyze, gain_to_db, position_at, Fade, FadeCurve, Keyframe, MediaStatus, Placement},
+    cues::{AudioCue, CueTime, TimeFormat},
+    Cue,
+};
+
+use super::{waveform::waveform_ui, CueInspector, InspectorContext};
 
-use super::CueInspector;
+const KEYFRAME_COLOR: Color32 = Color32::from_rgb(255, 160, 0);
 
 #[derive(Debug)]
 pub struct AudioCueInspector<'a> {
     pub cue: &'a mut AudioCue,
+    context: InspectorContext,
 }
 
 impl<'a> AudioCueInspector<'a> {
-    pub fn new(cue: &'a mut AudioCue) -> Self {
-        Self { cue }
+    pub fn new(cue: &'a mut AudioCue, context: InspectorContext) -> Self {
+        Self { cue, context }
     }
 
     fn basics(&mut self, ui: &mut egui::Ui) -> () {
@@ -32,7 +38,7 @@ impl<'a> AudioCueInspector<'a> {
             if ui.button("Pick").clicked() {
                 match FileDialog::new().pick_file() {
                     Some(path) => match path.to_str() {
-                        Some(p) => self.cue.file_path = p.into(),
+                        Some(p) => self.cue.set_file_path(p),
                         None => error!("Selected invalid path!"),
                     },
                     None => warn!("Did not select an audio file!"),
@@ -42,18 +48,13 @@ impl<'a> AudioCueInspector<'a> {
     }
 
     fn time_and_loops(&mut self, ui: &mut egui::Ui) -> () {
-        let audio_data = match decode_source(&self.cue, ui) {
-            Err(err) => {
-                if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
-                    ui.colored_label(egui::Color32::RED, "Invalid audio file: ");
-                    ui.colored_label(egui::Color32::RED, io_err.to_string());
-                } else {
-                    error!("Could not decode audio for cue {}: {}", self.cue.id, err);
-                }
-                return;
-            }
-            Ok(d) => d,
-        };
+        // peaks come from the cache, or are worked out in the background
+        let media = analyze(Path::new(&self.cue.file_path));
+        if let MediaStatus::Failed(err) = &media {
+            ui.colored_label(egui::Color32::RED, "Invalid audio file: ");
+            ui.colored_label(egui::Color32::RED, err);
+            return;
+        }
 
         // ui.horizontal(|ui| {
         ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
@@ -63,22 +64,63 @@ impl<'a> AudioCueInspector<'a> {
             ui.vertical(|ui| {
                 ui.set_width(total_width * 0.3);
 
-                // start and end offsets
+                // start and end offsets, in the project's time format
                 if let Some(duration) = self.cue.duration {
+                    let length = self.cue.start + duration + self.cue.end;
+                    let format = self.context.time_format;
                     ui.horizontal(|ui| {
                         ui.label("Start time: ");
-                        ui.label(
-                            RichText::new(format!("{:.3}", self.cue.start))
-                                .text_style(TextStyle::Monospace),
+                        let mut start = self.cue.start;
+                        ui.add(
+                            time_drag(&mut start, format, CueTime::ZERO..=length - self.cue.end)
+                                .speed(0.001),
                         );
+                        if start != self.cue.start {
+                            if let Err(err) = self.cue.set_start(start) {
+                                error!("Could not set start for cue {}: {}", self.cue.id, err);
+                            }
+                        }
                     });
                     ui.horizontal(|ui| {
                         ui.label("End time: ");
+                        let mut end = length - self.cue.end;
+                        ui.add(time_drag(&mut end, format, self.cue.start..=length).speed(0.001));
+                        if end != length - self.cue.end {
+                            if let Err(err) = self.cue.set_end(length - end) {
+                                error!("Could not set end for cue {}: {}", self.cue.id, err);
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Duration: ");
                         ui.label(
-                            RichText::new(format!("{:.3}", self.cue.start + duration))
-                                .text_style(TextStyle::Monospace),
+                            RichText::new(duration.format(format)).text_style(TextStyle::Monospace),
                         );
                     });
+
+                    // fades, within the trimmed region
+                    let mut fade_in = self.cue.get_fade_in();
+                    if fade_ui(
+                        ui,
+                        "Fade in: ",
+                        &self.cue.id,
+                        &mut fade_in,
+                        duration,
+                        format,
+                    ) {
+                        self.cue.set_fade_in(fade_in);
+                    }
+                    let mut fade_out = self.cue.get_fade_out();
+                    if fade_ui(
+                        ui,
+                        "Fade out: ",
+                        &self.cue.id,
+                        &mut fade_out,
+                        duration,
+                        format,
+                    ) {
+                        self.cue.set_fade_out(fade_out);
+                    }
                 } else {
                     ui.label(RichText::new("No audio duration").color(Color32::RED));
                 }
@@ -87,35 +129,298 @@ impl<'a> AudioCueInspector<'a> {
             // right column
             ui.vertical(|ui| {
                 // sample views
-                draw_waveform_view(self.cue, &audio_data, ui, total_width)
-                // .unwrap_or_else(|err| error!("Error drawing audio waveform: {}", err))
+                match &media {
+                    MediaStatus::Ready(analysis) => {
+                        waveform_ui(
+                            ui,
+                            self.cue,
+                            &analysis.peaks,
+                            total_width * 0.7,
+                            self.context.time_format,
+                        );
+                    }
+                    MediaStatus::Queued | MediaStatus::Analyzing(_) => {
+                        let bar = match media.progress() {
+                            Some(done) => egui::ProgressBar::new(done).show_percentage(),
+                            None => egui::ProgressBar::new(0.).animate(true),
+                        };
+                        ui.add(
+                            bar.desired_width(total_width * 0.7)
+                                .text("Analyzing waveform"),
+                        );
+                        ui.ctx().request_repaint_after(Duration::from_millis(100));
+                    }
+                    MediaStatus::Failed(_) => {}
+                }
             });
         });
     }
 
     fn levels(&mut self, ui: &mut egui::Ui) -> () {
-        let mut v = self.cue.get_volume();
-        ui.add(
-            egui::Slider::new(&mut v, 0.0..=2.0)
-                .logarithmic(true)
-                .smallest_positive(0.005)
-                .vertical()
-                .text("Volume"),
-        );
-        if v != self.cue.get_volume() {
-            if let Err(err) = self.cue.set_volume(v) {
-                error!("Could not set volume for cue {}: {}", self.cue.id, err);
+        ui.horizontal(|ui| {
+            let mut v = self.cue.get_volume();
+            ui.add(
+                egui::Slider::new(&mut v, 0.0..=2.0)
+                    .logarithmic(true)
+                    .smallest_positive(0.005)
+                    .vertical()
+                    .text("Volume"),
+            );
+            if v != self.cue.get_volume() {
+                if let Err(err) = self.cue.set_volume(v) {
+                    error!("Could not set volume for cue {}: {}", self.cue.id, err);
+                }
+            }
+
+            // what the cue is putting out right now
+            let readings = self.cue.levels(meter_frame(ui)).unwrap_or_default();
+            level_meter_ui(
+                ui,
+                Id::new(("inspector_meter", &self.cue.id)),
+                &readings,
+                Vec2::new(24., ui.spacing().slider_width),
+                true,
+            );
+
+            ui.vertical(|ui| self.loudness(ui));
+            ui.separator();
+            ui.vertical(|ui| self.placement(ui));
+        });
+    }
+
+    // where the cue comes out: channel for channel, panned, or at a point on
+    // the stage plot
+    fn placement(&mut self, ui: &mut egui::Ui) -> () {
+        let mut placement = self.cue.get_placement().clone();
+        egui::ComboBox::from_id_salt(("audio_placement", &self.cue.id))
+            .selected_text(placement.name())
+            .show_ui(ui, |ui| {
+                for kind in Placement::kinds() {
+                    let name = kind.name();
+                    if ui
+                        .selectable_label(placement.name() == name, name)
+                        .clicked()
+                        && placement.name() != name
+                    {
+                        placement = kind;
+                    }
+                }
+            });
+
+        match &mut placement {
+            Placement::Direct => {
+                ui.weak("Channels go straight to the outputs");
+            }
+            Placement::Pan(pan) => {
+                ui.add(egui::Slider::new(pan, -1.0..=1.0).text("Pan"));
+            }
+            Placement::Object(keyframes) => self.position_ui(ui, keyframes),
+        }
+        if placement != *self.cue.get_placement() {
+            self.cue.set_placement(placement);
+        }
+    }
+
+    // the stage plot with the cue's path across it, and its keyframes
+    fn position_ui(&mut self, ui: &mut egui::Ui, keyframes: &mut Vec<Keyframe>) -> () {
+        ui.horizontal_top(|ui| {
+            let plot = StagePlot::new(ui, 160.);
+            plot.speakers(ui, &self.context.speakers);
+            if keyframes.len() > 1 {
+                let path = keyframes.iter().map(|k| plot.to_screen(k.x, k.y)).collect();
+                plot.painter()
+                    .add(Shape::line(path, Stroke::new(1., KEYFRAME_COLOR)));
+            }
+            for (i, keyframe) in keyframes.iter_mut().enumerate() {
+                let id = Id::new(("audio_keyframe", &self.cue.id, i));
+                if let Some((x, y)) = plot.handle(ui, id, (keyframe.x, keyframe.y), KEYFRAME_COLOR)
+                {
+                    (keyframe.x, keyframe.y) = (x, y);
+                }
+            }
+            // where it has got to
+            if let Some((x, y)) = self.cue.elapsed().and_then(|at| position_at(keyframes, at)) {
+                plot.painter()
+                    .circle_filled(plot.to_screen(x, y), 4., Color32::YELLOW);
+                ui.ctx().request_repaint();
+            }
+
+            ui.vertical(|ui| {
+                let mut removed = None;
+                egui::Grid::new(("audio_keyframes", &self.cue.id))
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        let count = keyframes.len();
+                        let format = self.context.time_format;
+                        for (i, keyframe) in keyframes.iter_mut().enumerate() {
+                            ui.add(time_drag(
+                                &mut keyframe.time,
+                                format,
+                                CueTime::ZERO..=CueTime::from_nanos(i64::MAX),
+                            ));
+                            ui.add(
+                                egui::DragValue::new(&mut keyframe.x)
+                                    .range(-1.0..=1.0)
+                                    .speed(0.01)
+                                    .prefix("x "),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut keyframe.y)
+                                    .range(-1.0..=1.0)
+                                    .speed(0.01)
+                                    .prefix("y "),
+                            );
+                            if ui
+                                .add_enabled(count > 1, egui::Button::new("✖").small())
+                                .clicked()
+                            {
+                                removed = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = removed {
+                    keyframes.remove(i);
+                }
+                if ui.button("Add Keyframe").clicked() {
+                    if let Some(last) = keyframes.last().copied() {
+                        keyframes.push(Keyframe {
+                            time: last.time + CueTime::from_millis(1000),
+                            ..last
+                        });
+                    }
+                }
+            });
+        });
+        keyframes.sort_by_key(|k| k.time);
+    }
+
+    fn effects(&mut self, ui: &mut egui::Ui) -> () {
+        let mut chain = self.cue.get_effects().clone();
+        if effect_chain_ui(ui, ("audio_effects", &self.cue.id), &mut chain) {
+            self.cue.set_effects(chain);
+        }
+    }
+
+    // the file's measured loudness, and the trim that brings it to the
+    // project's target
+    fn loudness(&mut self, ui: &mut egui::Ui) -> () {
+        let media = analyze(Path::new(&self.cue.file_path));
+        let info = match &media {
+            MediaStatus::Ready(analysis) => &analysis.info,
+            MediaStatus::Failed(_) => {
+                ui.label(RichText::new("No loudness for invalid audio").color(Color32::RED));
+                return;
+            }
+            MediaStatus::Queued | MediaStatus::Analyzing(_) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Measuring loudness");
+                });
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+        };
+
+        let true_peak = gain_to_db(info.true_peak);
+        egui::Grid::new(("audio_loudness", &self.cue.id))
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Integrated: ");
+                ui.label(
+                    RichText::new(match info.loudness {
+                        Some(lufs) => format!("{:.1} LUFS", lufs),
+                        None => "silent".to_string(),
+                    })
+                    .text_style(TextStyle::Monospace),
+                );
+                ui.end_row();
+
+                ui.label("True peak: ");
+                ui.label(
+                    RichText::new(format!("{:.1} dBTP", true_peak))
+                        .text_style(TextStyle::Monospace),
+                );
+                ui.end_row();
+
+                ui.label("Trim: ");
+                let mut trim = self.cue.get_trim();
+                ui.add(
+                    egui::DragValue::new(&mut trim)
+                        .range(-60.0..=24.0)
+                        .speed(0.1)
+                        .max_decimals(1)
+                        .suffix(" dB"),
+                );
+                if trim != self.cue.get_trim() {
+                    self.cue.set_trim(trim);
+                }
+                ui.end_row();
+            });
+
+        let target = self.context.loudness_target;
+        if ui
+            .add_enabled(
+                info.loudness.is_some(),
+                egui::Button::new(format!("Normalize to {:.1} LUFS", target)),
+            )
+            .clicked()
+        {
+            if let Err(err) = self.cue.normalize(target) {
+                error!("Could not normalize cue {}: {}", self.cue.id, err);
             }
         }
+
+        // how loud the peaks come out at unity volume
+        let peak_out = true_peak + self.cue.get_trim();
+        if peak_out > 0. {
+            ui.label(
+                RichText::new(format!("Trim clips by {:.1} dB", peak_out)).color(Color32::YELLOW),
+            );
+        }
     }
 }
 
+// length and curve of one of a cue's fades. returns whether it changed
+fn fade_ui(
+    ui: &mut egui::Ui,
+    label: &str,
+    cue_id: &str,
+    fade: &mut Fade,
+    max: CueTime,
+    format: TimeFormat,
+) -> bool {
+    let before = *fade;
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(time_drag(
+            &mut fade.length,
+            format,
+            CueTime::ZERO..=max.max(CueTime::ZERO),
+        ));
+        egui::ComboBox::from_id_salt((label, cue_id))
+            .selected_text(
+                FadeCurve::ITER
+                    .iter()
+                    .find(|(c, _)| *c == fade.curve)
+                    .map_or("", |(_, name)| *name),
+            )
+            .show_ui(ui, |ui| {
+                for (curve, name) in FadeCurve::ITER {
+                    ui.selectable_value(&mut fade.curve, curve, name);
+                }
+            });
+    });
+    *fade != before
+}
+
 impl CueInspector for AudioCueInspector<'_> {
     fn has_tab(&self, tab: &super::InspectorPanelTabs) -> bool {
         match tab {
             super::InspectorPanelTabs::Basics => true,
             super::InspectorPanelTabs::TimeLoops => true,
             super::InspectorPanelTabs::Levels => true,
+            super::InspectorPanelTabs::Effects => true,
             _ => false,
         }
     }
@@ -125,224 +430,8 @@ impl CueInspector for AudioCueInspector<'_> {
             super::InspectorPanelTabs::Basics => self.basics(ui),
             super::InspectorPanelTabs::TimeLoops => self.time_and_loops(ui),
             super::InspectorPanelTabs::Levels => self.levels(ui),
+            super::InspectorPanelTabs::Effects => self.effects(ui),
             _ => {}
         }
     }
 }
-
-#[derive(Debug, Clone)]
-struct AudioData {
-    samples: Arc<Mutex<Vec<f32>>>,
-    rate: u32,
-    duration: Option<Duration>,
-}
-
-fn draw_waveform_view(
-    cue: &mut AudioCue,
-    audio_data: &AudioData,
-    ui: &mut egui::Ui,
-    total_width: f32,
-) -> Result<(), anyhow::Error> {
-    let samples = match audio_data.samples.lock() {
-        Err(err) => return Err(anyhow!(err.to_string())),
-        Ok(ok) => ok,
-    };
-
-    let sink = match &cue.sink {
-        None => return Err(anyhow!("Cue had no sync!")),
-        Some(s) => s,
-    };
-
-    // if audio has known duration, use that
-    // if not, try to calculate from number of samples
-    let sample_len = match audio_data.duration {
-        Some(d) => d.as_secs_f32(),
-        None => (samples.len() as f32) / (2. * audio_data.rate as f32),
-    };
-
-    // only take every nth point
-    const N: usize = 128;
-    let displayed_waveform: Vec<&f32> = samples.iter().step_by(N).collect();
-
-    let mut waveform_rect = ui.available_rect_before_wrap();
-    waveform_rect.set_width(total_width * 0.7);
-    let painter = ui.painter_at(waveform_rect);
-    painter.rect_filled(waveform_rect, 4., Color32::BLACK);
-
-    let horiz_scale = waveform_rect.width() / sample_len;
-
-    if !displayed_waveform.is_empty() {
-        let wave_height = waveform_rect.height() / (2. * 2.);
-        let wave_width = waveform_rect.width() / displayed_waveform.len().max(1) as f32;
-        let center_y = waveform_rect.center().y;
-
-        let points: Vec<Pos2> = displayed_waveform
-            .iter()
-            .enumerate()
-            .map(|(i, sample)| {
-                let x = waveform_rect.left_top().x + (i as f32 * wave_width);
-                let y = center_y - (**sample * wave_height);
-                Pos2 { x, y }
-            })
-            .collect();
-
-        painter.add(egui::Shape::line(
-            points,
-            Stroke::new(1.5, Color32::LIGHT_BLUE),
-        ));
-
-        if !sink.empty() {
-            let time = sink.get_pos().as_secs_f32() + cue.start;
-            let playhead_pos = time * horiz_scale;
-            painter.add(egui::Shape::line_segment(
-                [
-                    Pos2 {
-                        x: waveform_rect.left_top().x + playhead_pos,
-                        y: waveform_rect.left_top().y,
-                    },
-                    Pos2 {
-                        x: waveform_rect.left_top().x + playhead_pos,
-                        y: waveform_rect.left_bottom().y,
-                    },
-                ],
-                Stroke::new(1.5, Color32::YELLOW),
-            ));
-
-            ui.ctx().request_repaint();
-        }
-    }
-
-    // start and end cutoffs
-    let top = waveform_rect.left_top().y;
-    let bottom = waveform_rect.left_bottom().y;
-
-    let start_pos = cue.start * horiz_scale + waveform_rect.left_top().x;
-    let end_pos = waveform_rect.right_top().x - cue.end * waveform_rect.width() / sample_len;
-
-    painter.add(egui::Shape::line_segment(
-        [
-            Pos2 { x: end_pos, y: top },
-            Pos2 {
-                x: end_pos,
-                y: bottom,
-            },
-        ],
-        Stroke::new(1.5, Color32::BLUE),
-    ));
-    painter.rect_filled(
-        egui::Rect {
-            min: Pos2 { x: end_pos, y: top },
-            max: waveform_rect.right_bottom(),
-        },
-        0.,
-        Color32::from_rgba_unmultiplied(0, 0, 200, 32),
-    );
-
-    painter.add(egui::Shape::line_segment(
-        [
-            Pos2 {
-                x: start_pos,
-                y: top,
-            },
-            Pos2 {
-                x: start_pos,
-                y: bottom,
-            },
-        ],
-        Stroke::new(1.5, Color32::BLUE),
-    ));
-    painter.rect_filled(
-        egui::Rect {
-            min: waveform_rect.left_top(),
-            max: Pos2 {
-                x: start_pos,
-                y: bottom,
-            },
-        },
-        0.,
-        Color32::from_rgba_unmultiplied(0, 0, 200, 32),
-    );
-
-    let start_icon_size = 16.;
-    let start_icon_rect = Rect {
-        min: Pos2 {
-            x: start_pos - start_icon_size / 2.,
-            y: top,
-        },
-        max: Pos2 {
-            x: start_pos + start_icon_size / 2.,
-            y: top + start_icon_size,
-        },
-    };
-
-    let start_icon_resp = ui.put(
-        start_icon_rect,
-        egui::Image::new(egui::include_image!("../../../assets/left-and-right.png"))
-            .sense(Sense::drag()),
-    );
-
-    if start_icon_resp.dragged() {
-        let delta = start_icon_resp.drag_delta().x / horiz_scale;
-        let new_pos = (cue.start + delta).clamp(0., sample_len - cue.end);
-        let _ = cue.set_start(new_pos);
-    }
-
-    let end_icon_size = 16.;
-    let end_icon_rect = Rect {
-        min: Pos2 {
-            x: end_pos - end_icon_size / 2.,
-            y: top,
-        },
-        max: Pos2 {
-            x: end_pos + end_icon_size / 2.,
-            y: top + end_icon_size,
-        },
-    };
-
-    let end_icon_resp = ui.put(
-        end_icon_rect,
-        egui::Image::new(egui::include_image!("../../../assets/left-and-right.png"))
-            .sense(Sense::drag()),
-    );
-
-    if end_icon_resp.dragged() {
-        let delta = end_icon_resp.drag_delta().x / horiz_scale;
-        let new_pos = (cue.end - delta).clamp(0., sample_len - cue.start);
-        let _ = cue.set_end(new_pos);
-    }
-
-    Ok(())
-}
-
-fn decode_source(cue: &AudioCue, ui: &mut egui::Ui) -> Result<AudioData, anyhow::Error> {
-    let id = Id::new(format!("audio_cue_{}_data", cue.id));
-
-    let mut stored: Option<AudioData> = None;
-    ui.memory(|mem| {
-        stored = mem.data.get_temp(id);
-    });
-    if let Some(out) = stored {
-        return Ok(out);
-    }
-
-    let file = BufReader::new(File::open(cue.file_path.clone())?);
-
-    let source = Decoder::new(file)?;
-
-    let rate = source.sample_rate();
-    let duration = source.total_duration();
-
-    let out = Arc::new(Mutex::new(source.collect()));
-
-    let data = AudioData {
-        samples: out,
-        rate,
-        duration,
-    };
-
-    ui.memory_mut(|mem| {
-        mem.data.insert_temp(id, data.clone());
-    });
-
-    Ok(data)
-}
@@ -1,29 +1,51 @@
 use crate::{
-    cues::{BonkCue, RemarkCue},
-    MultitypeCue,
+    audio::Speaker,
+    cues::{BonkCue, ControlAction, ControlCue, RemarkCue, TimeFormat},
+    MultitypeCue, Project,
 };
 
 mod audio;
+mod waveform;
 
 pub use audio::AudioCueInspector;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum InspectorPanelTabs {
     Basics,
     TimeLoops,
     Levels,
+    Effects,
     Extra,
 }
 
 impl InspectorPanelTabs {
-    pub const ITER: [(InspectorPanelTabs, &str); 4] = [
+    pub const ITER: [(InspectorPanelTabs, &str); 5] = [
         (InspectorPanelTabs::Basics, "Basics"),
         (InspectorPanelTabs::TimeLoops, "Time & Loops"),
         (InspectorPanelTabs::Levels, "Levels"),
+        (InspectorPanelTabs::Effects, "Effects"),
         (InspectorPanelTabs::Extra, "Extra"),
     ];
 }
 
+// project settings that inspectors need, taken before the cue is borrowed
+#[derive(Clone, Debug)]
+pub struct InspectorContext {
+    pub loudness_target: f32,
+    pub speakers: Vec<Speaker>,
+    pub time_format: TimeFormat,
+}
+
+impl InspectorContext {
+    pub fn new(project: &Project) -> Self {
+        Self {
+            loudness_target: project.loudness_target,
+            speakers: project.speaker_layout(),
+            time_format: project.time_format,
+        }
+    }
+}
+
 pub trait CueInspector {
     fn has_tab(&self, tab: &InspectorPanelTabs) -> bool {
         match tab {
@@ -35,11 +57,15 @@ pub trait CueInspector {
     fn draw_tab(&mut self, _ui: &mut egui::Ui, _tab: &InspectorPanelTabs) {}
 }
 
-pub fn get_cue_inspector(cue: &mut MultitypeCue) -> Option<Box<dyn CueInspector + '_>> {
+pub fn get_cue_inspector(
+    cue: &mut MultitypeCue,
+    context: InspectorContext,
+) -> Option<Box<dyn CueInspector + '_>> {
     match cue {
         MultitypeCue::Remark(ref mut q) => Some(Box::new(RemarkCueInspector::new(q))),
         MultitypeCue::Bonk(ref mut q) => Some(Box::new(BonkCueInspector::new(q))),
-        MultitypeCue::Audio(ref mut q) => Some(Box::new(AudioCueInspector::new(q))),
+        MultitypeCue::Audio(ref mut q) => Some(Box::new(AudioCueInspector::new(q, context))),
+        MultitypeCue::Control(ref mut q) => Some(Box::new(ControlCueInspector::new(q))),
     }
 }
 
@@ -54,13 +80,27 @@ impl<'a> RemarkCueInspector<'a> {
     }
 }
 
-impl CueInspector for RemarkCueInspector<'_> {
+// notes are common to every cue, so remarks have nothing else to show
+impl CueInspector for RemarkCueInspector<'_> {}
+
+#[derive(Debug)]
+pub struct BonkCueInspector<'a> {
+    pub cue: &'a mut BonkCue,
+}
+
+impl<'a> BonkCueInspector<'a> {
+    fn new(cue: &'a mut BonkCue) -> Self {
+        Self { cue }
+    }
+}
+
+impl CueInspector for BonkCueInspector<'_> {
     fn draw_tab(&mut self, ui: &mut egui::Ui, tab: &InspectorPanelTabs) {
         match tab {
             InspectorPanelTabs::Basics => {
                 ui.horizontal(|ui| {
-                    ui.label("Notes: ");
-                    ui.text_edit_singleline(&mut self.cue.notes);
+                    ui.label("Bonk count: ");
+                    ui.label(self.cue.ctr.to_string());
                 });
             }
             _ => {}
@@ -69,23 +109,35 @@ impl CueInspector for RemarkCueInspector<'_> {
 }
 
 #[derive(Debug)]
-pub struct BonkCueInspector<'a> {
-    pub cue: &'a mut BonkCue,
+pub struct ControlCueInspector<'a> {
+    pub cue: &'a mut ControlCue,
 }
 
-impl<'a> BonkCueInspector<'a> {
-    fn new(cue: &'a mut BonkCue) -> Self {
+impl<'a> ControlCueInspector<'a> {
+    fn new(cue: &'a mut ControlCue) -> Self {
         Self { cue }
     }
 }
 
-impl CueInspector for BonkCueInspector<'_> {
+impl CueInspector for ControlCueInspector<'_> {
     fn draw_tab(&mut self, ui: &mut egui::Ui, tab: &InspectorPanelTabs) {
         match tab {
             InspectorPanelTabs::Basics => {
                 ui.horizontal(|ui| {
-                    ui.label("Bonk count: ");
-                    ui.label(self.cue.ctr.to_string());
+                    egui::ComboBox::from_label("Action")
+                        .selected_text(self.cue.action.to_string())
+                        .show_ui(ui, |ui| {
+                            for action in ControlAction::ITER {
+                                ui.selectable_value(
+                                    &mut self.cue.action,
+                                    action,
+                                    action.to_string(),
+                                );
+                            }
+                        });
+                    ui.label("Target: ");
+                    ui.text_edit_singleline(&mut self.cue.target)
+                        .on_hover_text("A cue ID, or list:ID for a cue in another list");
                 });
             }
             _ => {}
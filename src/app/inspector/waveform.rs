@@ -0,0 +1,380 @@
+use std::{fs::File, io::BufReader, sync::Arc};
+
+use egui::{
+    pos2, vec2, Color32, CursorIcon, Id, Pos2, Rect, RichText, Sense, Shape, Stroke, TextStyle,
+    Vec2,
+};
+use log::{error, warn};
+use rodio::{Decoder, Sink, Source};
+
+use crate::{
+    audio::{self, Fade, Peaks},
+    cues::{AudioCue, CueTime, TimeFormat},
+};
+
+// narrowest stretch of audio the view will zoom in to, in seconds
+const MIN_SPAN: f32 = 0.01;
+// how far one notch of the mouse wheel zooms
+const ZOOM_SPEED: f32 = 0.003;
+const LANE_GAP: f32 = 2.;
+// points between the vertices of a drawn fade curve
+const CURVE_STEP: f32 = 2.;
+const FADE_COLOR: Color32 = Color32::from_rgb(255, 160, 0);
+
+// the file playing from the preview playhead
+#[derive(Clone)]
+struct Audition {
+    sink: Arc<Sink>,
+    from: CueTime,
+}
+
+// how much of the file is in view and where the preview playhead is, kept
+// for each cue between frames
+#[derive(Clone, Default)]
+struct WaveformState {
+    // seconds at the left edge
+    offset: f32,
+    // seconds across the view, where zero fits the whole file
+    span: f32,
+    preview: Option<CueTime>,
+    audition: Option<Audition>,
+}
+
+impl WaveformState {
+    fn clamp(&mut self, length: f32) {
+        if self.span <= 0. || self.span > length {
+            self.span = length;
+        }
+        self.span = self.span.max(MIN_SPAN.min(length));
+        self.offset = self.offset.clamp(0., (length - self.span).max(0.));
+    }
+
+    // play the file from `from`, straight from disk
+    fn audition(&mut self, cue: &AudioCue, length: CueTime, from: CueTime) {
+        self.stop_audition();
+        let Some(sink) = audio::new_sink() else {
+            return;
+        };
+        let mut source = match File::open(&cue.file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(Decoder::new(BufReader::new(file))?))
+        {
+            Ok(source) => source,
+            Err(err) => {
+                error!("Could not audition cue {}: {}", cue.id, err);
+                return;
+            }
+        };
+
+        // play up to the end trim, or to the end of the file when starting
+        // after it
+        let mut to = length - cue.end;
+        if from >= to {
+            to = length;
+        }
+        let start = from.to_duration();
+        let take = (to - from).to_duration();
+        sink.set_volume(cue.gain());
+        match source.try_seek(start) {
+            Ok(()) => sink.append(source.take_duration(take)),
+            Err(err) => {
+                warn!(
+                    "Could not seek in {}, skipping instead: {}",
+                    cue.file_path, err
+                );
+                sink.append(source.skip_duration(start).take_duration(take));
+            }
+        }
+        sink.play();
+        self.audition = Some(Audition {
+            sink: Arc::new(sink),
+            from,
+        });
+    }
+
+    fn stop_audition(&mut self) {
+        if let Some(audition) = self.audition.take() {
+            audition.sink.stop();
+        }
+    }
+
+    // where the audition has got to, if it is still playing
+    fn audition_position(&self) -> Option<CueTime> {
+        let audition = self.audition.as_ref()?;
+        (!audition.sink.empty()).then(|| audition.from + audition.sink.get_pos().into())
+    }
+}
+
+// the cue's file as a min/max envelope for each channel, with trim handles.
+// the mouse wheel zooms, shift and the wheel (or a horizontal scroll) pans,
+// and clicking or dragging places the preview playhead and auditions from it
+pub(super) fn waveform_ui(
+    ui: &mut egui::Ui,
+    cue: &mut AudioCue,
+    peaks: &Peaks,
+    width: f32,
+    format: TimeFormat,
+) {
+    let id = Id::new(("waveform_view", &cue.id));
+    let mut state: WaveformState = ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+    // the view works in float seconds, and the trims and fades in exact times
+    let file_length = CueTime::from_frames(peaks.frames() as i64, peaks.rate());
+    let length = peaks.length();
+    state.clamp(length);
+
+    // toolbar
+    ui.horizontal(|ui| {
+        let playing = state.audition_position().is_some();
+        if ui
+            .button(if playing { "⏹ Stop" } else { "▶ Audition" })
+            .on_hover_text("Play from the preview playhead, or the start trim")
+            .clicked()
+        {
+            if playing {
+                state.stop_audition();
+            } else {
+                let from = state.preview.unwrap_or(cue.start);
+                state.audition(cue, file_length, from);
+            }
+        }
+        if ui.button("Fit").clicked() {
+            state.span = length;
+            state.offset = 0.;
+        }
+        ui.label(
+            RichText::new(format!(
+                "{} - {}",
+                CueTime::from_secs_f32(state.offset).format(format),
+                CueTime::from_secs_f32(state.offset + state.span).format(format)
+            ))
+            .text_style(TextStyle::Monospace),
+        );
+        if let Some(preview) = state.preview {
+            ui.label(
+                RichText::new(format!("Preview: {}", preview.format(format)))
+                    .text_style(TextStyle::Monospace)
+                    .color(Color32::WHITE),
+            );
+        }
+    });
+
+    let height = ui.available_height().max(64.);
+    let (rect, resp) = ui.allocate_exact_size(vec2(width, height), Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4., Color32::BLACK);
+
+    // zoom around the pointer, and pan
+    if resp.hovered() {
+        let (scroll, zoom) = ui.input(|i| (i.smooth_scroll_delta, i.zoom_delta()));
+        let factor = zoom * (scroll.y * ZOOM_SPEED).exp();
+        if let Some(pointer) = resp.hover_pos().filter(|_| factor != 1.) {
+            let along = (pointer.x - rect.left()) / rect.width();
+            let at = state.offset + along * state.span;
+            state.span /= factor;
+            state.clamp(length);
+            state.offset = at - along * state.span;
+        }
+        state.offset -= scroll.x / rect.width() * state.span;
+        state.clamp(length);
+        // keep the inspector from scrolling too
+        ui.input_mut(|i| i.smooth_scroll_delta = Vec2::ZERO);
+    }
+
+    let (offset, horiz_scale) = (state.offset, rect.width() / state.span.max(f32::EPSILON));
+    let to_x = |t: f32| rect.left() + (t - offset) * horiz_scale;
+    let to_t = |x: f32| offset + (x - rect.left()) / horiz_scale;
+
+    // scrub the preview playhead, auditioning from wherever it ends up
+    if let Some(pointer) = resp.interact_pointer_pos() {
+        state.preview =
+            Some(CueTime::from_secs_f32(to_t(pointer.x)).clamp(CueTime::ZERO, file_length));
+    }
+    if resp.drag_started() {
+        state.stop_audition();
+    }
+    if resp.clicked() || resp.drag_stopped() {
+        if let Some(preview) = state.preview {
+            state.audition(cue, file_length, preview);
+        }
+    }
+
+    // the envelope, one lane per channel
+    let frames = peaks.frames();
+    let lane_height = rect.height() / peaks.channels() as f32;
+    let frame_at = |x: f32| ((to_t(x) * peaks.rate() as f32).max(0.) as usize).min(frames);
+    for channel in 0..peaks.channels() {
+        let top = rect.top() + channel as f32 * lane_height;
+        let center = top + lane_height / 2.;
+        let half = (lane_height - LANE_GAP) / 2.;
+        painter.hline(
+            rect.x_range(),
+            center,
+            Stroke::new(1., Color32::from_gray(40)),
+        );
+        for px in 0..rect.width() as usize {
+            let x = rect.left() + px as f32;
+            let from = frame_at(x);
+            let to = frame_at(x + 1.).max(from + 1);
+            if from >= frames {
+                break;
+            }
+            let (min, max) = peaks.envelope(channel, from, to);
+            painter.vline(
+                x,
+                center - max * half - 0.5..=center - min * half + 0.5,
+                Stroke::new(1., Color32::LIGHT_BLUE),
+            );
+        }
+    }
+
+    let top = rect.top();
+    let bottom = rect.bottom();
+
+    // playheads: the cue's in yellow, the audition's and the preview's in white
+    if let Some(sink) = &cue.sink {
+        if !sink.empty() {
+            let x = to_x((CueTime::from(sink.get_pos()) + cue.start).as_secs_f32());
+            painter.vline(x, top..=bottom, Stroke::new(1.5, Color32::YELLOW));
+            ui.ctx().request_repaint();
+        }
+    }
+    match state.audition_position() {
+        Some(t) => {
+            painter.vline(
+                to_x(t.as_secs_f32()),
+                top..=bottom,
+                Stroke::new(1.5, Color32::WHITE),
+            );
+            ui.ctx().request_repaint();
+        }
+        None => state.audition = None,
+    }
+    if let Some(preview) = state.preview {
+        painter.vline(
+            to_x(preview.as_secs_f32()),
+            top..=bottom,
+            Stroke::new(1., Color32::from_white_alpha(128)),
+        );
+    }
+
+    // start and end cutoffs
+    let (start, end) = (cue.start, cue.end);
+    let start_pos = to_x(start.as_secs_f32());
+    let end_pos = to_x((file_length - end).as_secs_f32());
+    let trimmed = Color32::from_rgba_unmultiplied(0, 0, 200, 32);
+    painter.vline(end_pos, top..=bottom, Stroke::new(1.5, Color32::BLUE));
+    painter.rect_filled(
+        Rect::from_min_max(pos2(end_pos, top), rect.right_bottom()),
+        0.,
+        trimmed,
+    );
+    painter.vline(start_pos, top..=bottom, Stroke::new(1.5, Color32::BLUE));
+    painter.rect_filled(
+        Rect::from_min_max(rect.left_top(), pos2(start_pos, bottom)),
+        0.,
+        trimmed,
+    );
+
+    // handles for dragging the cutoffs, when they are in view
+    if let Some(delta) = trim_handle_ui(ui, rect, start_pos) {
+        let new_pos = start + CueTime::from_secs_f32(delta / horiz_scale);
+        let _ = cue.set_start(new_pos.clamp(CueTime::ZERO, file_length - end));
+    }
+    if let Some(delta) = trim_handle_ui(ui, rect, end_pos) {
+        let new_pos = end - CueTime::from_secs_f32(delta / horiz_scale);
+        let _ = cue.set_end(new_pos.clamp(CueTime::ZERO, file_length - start));
+    }
+
+    // fades, drawn over the trimmed region with handles where they end
+    let region = (file_length - start - end).max(CueTime::ZERO);
+    let fade_in = cue.get_fade_in();
+    let fade_out = cue.get_fade_out();
+    draw_fade(&painter, rect, &fade_in, start_pos, horiz_scale, false);
+    draw_fade(&painter, rect, &fade_out, end_pos, horiz_scale, true);
+    let handle = to_x((start + fade_in.length).as_secs_f32());
+    if let Some(delta) = fade_handle_ui(ui, rect, id.with("fade_in"), handle) {
+        cue.set_fade_in(Fade {
+            length: (fade_in.length + CueTime::from_secs_f32(delta / horiz_scale))
+                .clamp(CueTime::ZERO, region),
+            ..fade_in
+        });
+    }
+    let handle = to_x((file_length - end - fade_out.length).as_secs_f32());
+    if let Some(delta) = fade_handle_ui(ui, rect, id.with("fade_out"), handle) {
+        cue.set_fade_out(Fade {
+            length: (fade_out.length - CueTime::from_secs_f32(delta / horiz_scale))
+                .clamp(CueTime::ZERO, region),
+            ..fade_out
+        });
+    }
+
+    ui.data_mut(|d| d.insert_temp(id, state));
+}
+
+// the gain of a fade from full height down to the bottom of the view. a fade
+// in starts at `edge`, and a fade out (`out`) ends there
+fn draw_fade(
+    painter: &egui::Painter,
+    rect: Rect,
+    fade: &Fade,
+    edge: f32,
+    horiz_scale: f32,
+    out: bool,
+) {
+    let width = fade.length.as_secs_f32() * horiz_scale;
+    if width <= 0. {
+        return;
+    }
+    let steps = (width / CURVE_STEP).ceil().max(1.) as usize;
+    let points = (0..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            let (x, gain) = if out {
+                (edge - width + t * width, fade.curve.gain(1. - t))
+            } else {
+                (edge + t * width, fade.curve.gain(t))
+            };
+            pos2(x, rect.bottom() - gain * rect.height())
+        })
+        .collect();
+    painter.add(Shape::line(points, Stroke::new(1.5, FADE_COLOR)));
+}
+
+// how far the fade handle at `x` was dragged this frame, in points
+fn fade_handle_ui(ui: &mut egui::Ui, rect: Rect, id: Id, x: f32) -> Option<f32> {
+    if !rect.x_range().contains(x) {
+        return None;
+    }
+    let handle = Rect::from_center_size(pos2(x, rect.top() + 24.), Vec2::splat(10.));
+    let resp = ui
+        .interact(handle, id, Sense::drag())
+        .on_hover_cursor(CursorIcon::ResizeHorizontal);
+    let color = if resp.hovered() || resp.dragged() {
+        Color32::WHITE
+    } else {
+        FADE_COLOR
+    };
+    ui.painter().rect_filled(handle, 2., color);
+    resp.dragged().then(|| resp.drag_delta().x)
+}
+
+// how far the handle at `x` was dragged this frame, in points
+fn trim_handle_ui(ui: &mut egui::Ui, rect: Rect, x: f32) -> Option<f32> {
+    if !rect.x_range().contains(x) {
+        return None;
+    }
+    let icon_size = 16.;
+    let icon_rect = Rect::from_min_size(
+        Pos2 {
+            x: x - icon_size / 2.,
+            y: rect.top(),
+        },
+        Vec2::splat(icon_size),
+    );
+    let resp = ui.put(
+        icon_rect,
+        egui::Image::new(egui::include_image!("../../../assets/left-and-right.png"))
+            .sense(Sense::drag()),
+    );
+    resp.dragged().then(|| resp.drag_delta().x)
+}
@@ -0,0 +1,249 @@
+use egui::{Color32, RichText};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cart::cart_ui,
+    cue_list_ui,
+    shortcuts::{hotkey_edit_ui, KeyBinding, KeyBindings},
+    AppState,
+};
+use crate::cues::{Cart, CueList};
+
+const DEFAULT_CART_SIZE: (usize, usize) = (4, 4);
+
+// how multiple cue lists are laid out in the central panel
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ListLayout {
+    #[default]
+    Tabs,
+    SideBySide,
+}
+
+// columns of the cue lists that can be hidden, remembered between sessions.
+// the ID and name are always shown
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ListColumns {
+    pub kind: bool,
+    pub duration: bool,
+    pub level: bool,
+    pub state: bool,
+    pub file: bool,
+    pub referents: bool,
+    pub notes: bool,
+}
+
+impl Default for ListColumns {
+    fn default() -> Self {
+        Self {
+            kind: true,
+            duration: true,
+            level: true,
+            state: false,
+            file: false,
+            referents: false,
+            notes: false,
+        }
+    }
+}
+
+impl ListColumns {
+    pub const STORAGE_KEY: &'static str = "list_columns";
+
+    pub fn menu_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.kind, "Type");
+        ui.checkbox(&mut self.duration, "Duration");
+        ui.checkbox(&mut self.level, "Level");
+        ui.checkbox(&mut self.state, "Enabled / Armed");
+        ui.checkbox(&mut self.file, "File");
+        ui.checkbox(&mut self.referents, "Targets");
+        ui.checkbox(&mut self.notes, "Notes");
+    }
+}
+
+pub fn cue_lists_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    list_tabs_ui(ui, state);
+    ui.separator();
+
+    if let Some(c) = state.cart_view.active {
+        cart_ui(ui, state, c);
+        return;
+    }
+
+    match state.list_layout {
+        ListLayout::Tabs => cue_list_ui(ui, state, state.active_list),
+        ListLayout::SideBySide => {
+            let count = state.project.cue_lists.len();
+            ui.columns(count, |columns| {
+                for (l, ui) in columns.iter_mut().enumerate() {
+                    let active = l == state.active_list;
+                    let heading = RichText::new(&state.project.cue_lists[l].name).strong();
+                    if ui.selectable_label(active, heading).clicked() {
+                        state.active_list = l;
+                    }
+                    cue_list_ui(ui, state, l);
+                }
+            });
+        }
+    }
+}
+
+// one tab per list and cart, plus controls for adding them and switching
+// layout
+fn list_tabs_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    ui.horizontal(|ui| {
+        let mut removed = None;
+        for l in 0..state.project.cue_lists.len() {
+            let list = &state.project.cue_lists[l];
+            let label = match &list.go_key {
+                Some(key) => format!("{} [{}]", list.name, key),
+                None => list.name.clone(),
+            };
+            let active = l == state.active_list && state.cart_view.active.is_none();
+            let resp = ui.selectable_label(active, label);
+            if resp.clicked() {
+                state.active_list = l;
+                state.cart_view.active = None;
+            }
+            if !state.show_mode {
+                resp.context_menu(|ui| {
+                    if list_menu_ui(ui, state, l) {
+                        removed = Some(l);
+                    }
+                });
+            }
+        }
+
+        if let Some(l) = removed {
+            state.project.remove_list(l);
+            if state.active_list >= l && state.active_list != 0 {
+                state.active_list -= 1;
+            }
+            state.dragged_cue = None;
+            state.hovered_cue = None;
+            state.readiness_panel.invalidate();
+        }
+
+        ui.separator();
+        let mut removed = None;
+        for c in 0..state.project.carts.len() {
+            let label = format!("▦ {}", state.project.carts[c].name);
+            let resp = ui.selectable_label(state.cart_view.active == Some(c), label);
+            if resp.clicked() {
+                state.cart_view.active = Some(c);
+                state.cart_view.selected_cell = None;
+            }
+            if !state.show_mode {
+                resp.context_menu(|ui| {
+                    if cart_menu_ui(ui, state, c) {
+                        removed = Some(c);
+                    }
+                });
+            }
+        }
+
+        if let Some(c) = removed {
+            let mut cart = state.project.carts.remove(c);
+            cart.stop_all();
+            state.cart_view.active = None;
+            state.cart_view.selected_cell = None;
+            state.readiness_panel.invalidate();
+        }
+
+        if !state.show_mode {
+            ui.menu_button("+", |ui| {
+                if ui.button("Cue List").clicked() {
+                    let mut n = state.project.cue_lists.len() + 1;
+                    while state.project.list_index(&format!("List {}", n)).is_some() {
+                        n += 1;
+                    }
+                    state
+                        .project
+                        .cue_lists
+                        .push(CueList::new(format!("List {}", n)));
+                    state.active_list = state.project.cue_lists.len() - 1;
+                    state.cart_view.active = None;
+                }
+                if ui.button("Cart").clicked() {
+                    let (rows, columns) = DEFAULT_CART_SIZE;
+                    let name = format!("Cart {}", state.project.carts.len() + 1);
+                    state.project.carts.push(Cart::new(name, rows, columns));
+                    state.cart_view.active = Some(state.project.carts.len() - 1);
+                    state.cart_view.selected_cell = None;
+                }
+            });
+        }
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.selectable_value(
+                &mut state.list_layout,
+                ListLayout::SideBySide,
+                "Side by Side",
+            );
+            ui.selectable_value(&mut state.list_layout, ListLayout::Tabs, "Tabs");
+        });
+    });
+}
+
+// right-click menu for a list tab. returns true if the list should be deleted
+fn list_menu_ui(ui: &mut egui::Ui, state: &mut AppState, l: usize) -> bool {
+    let bindings = KeyBindings::new(&state.project, &state.shortcut_keys);
+
+    // the name is only taken once the field loses focus, so references to
+    // the list are rewritten once rather than on every key typed
+    ui.horizontal(|ui| {
+        ui.label("Name:");
+        let id = egui::Id::new(("list_name", l));
+        let mut name = ui
+            .data(|d| d.get_temp::<String>(id))
+            .unwrap_or_else(|| state.project.cue_lists[l].name.clone());
+        let resp = ui.text_edit_singleline(&mut name);
+        let problem = state.project.check_list_name(l, &name).err();
+        if let Some(problem) = &problem {
+            ui.label(RichText::new(problem.to_string()).color(Color32::RED));
+        }
+        if resp.has_focus() {
+            ui.data_mut(|d| d.insert_temp(id, name));
+        } else {
+            if resp.lost_focus() && problem.is_none() {
+                if let Err(err) = state.project.rename_list(l, &name) {
+                    error!("Could not rename list: {}", err);
+                }
+            }
+            ui.data_mut(|d| d.remove::<String>(id));
+        }
+    });
+    let list = &mut state.project.cue_lists[l];
+
+    ui.horizontal(|ui| {
+        ui.label("GO key:");
+        hotkey_edit_ui(ui, &mut list.go_key, 80., &bindings, KeyBinding::Go(l))
+            .on_hover_text("Key name, e.g. Space, Enter or F1. Leave empty for none");
+    });
+
+    ui.separator();
+    let can_delete = state.project.cue_lists.len() > 1;
+    ui.add_enabled(can_delete, egui::Button::new("Delete List"))
+        .on_disabled_hover_text("A project needs at least one cue list")
+        .clicked()
+}
+
+// right-click menu for a cart tab. returns true if the cart should be deleted
+fn cart_menu_ui(ui: &mut egui::Ui, state: &mut AppState, c: usize) -> bool {
+    let cart = &mut state.project.carts[c];
+
+    ui.horizontal(|ui| {
+        ui.label("Name:");
+        ui.text_edit_singleline(&mut cart.name);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Rows:");
+        ui.add(egui::DragValue::new(&mut cart.rows).range(1..=16));
+        ui.label("Columns:");
+        ui.add(egui::DragValue::new(&mut cart.columns).range(1..=16));
+    });
+
+    ui.separator();
+    ui.button("Delete Cart").clicked()
+}
@@ -0,0 +1,189 @@
+use egui::{pos2, Color32, Id, Rect, Response, Sense, Stroke, Vec2};
+
+use crate::audio::{self, MeterReading};
+
+// lowest level a meter shows, in dBFS
+const FLOOR_DB: f32 = -60.;
+// how fast a meter falls back after a peak, in dB per second
+const FALLOFF_DB: f32 = 20.;
+// how long the peak hold line stays put, in seconds
+const HOLD_TIME: f32 = 1.5;
+// width of the clip indicator at the top end of each meter
+const CLIP_SIZE: f32 = 6.;
+const GAP: f32 = 1.;
+
+// what a meter is showing, kept between frames so it can fall off smoothly
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelState {
+    peak: f32,
+    rms: f32,
+    hold: f32,
+    hold_age: f32,
+    clipped: bool,
+}
+
+impl ChannelState {
+    fn update(&mut self, reading: MeterReading, dt: f32) {
+        let falloff = 10f32.powf(-FALLOFF_DB * dt / 20.);
+        self.peak = reading.peak.max(self.peak * falloff);
+        self.rms = reading.rms.max(self.rms * falloff);
+
+        self.hold_age += dt;
+        if reading.peak >= self.hold || self.hold_age > HOLD_TIME {
+            self.hold = reading.peak.max(self.peak);
+            self.hold_age = 0.;
+        }
+
+        if reading.peak >= 1. {
+            self.clipped = true;
+        }
+    }
+}
+
+// position of a level along a meter, from 0 at the floor to 1 at full scale
+fn meter_fraction(level: f32) -> f32 {
+    let db = 20. * level.max(1e-6).log10();
+    ((db - FLOOR_DB) / -FLOOR_DB).clamp(0., 1.)
+}
+
+fn meter_color(level: f32) -> Color32 {
+    let db = 20. * level.max(1e-6).log10();
+    if db > -3. {
+        Color32::RED
+    } else if db > -12. {
+        Color32::YELLOW
+    } else {
+        Color32::GREEN
+    }
+}
+
+// the part of `bar` filled up to `fraction`, growing rightwards or upwards
+fn filled(bar: Rect, fraction: f32, vertical: bool) -> Rect {
+    if vertical {
+        Rect::from_min_max(
+            pos2(bar.min.x, bar.max.y - bar.height() * fraction),
+            bar.max,
+        )
+    } else {
+        Rect::from_min_max(bar.min, pos2(bar.min.x + bar.width() * fraction, bar.max.y))
+    }
+}
+
+// the frame number meters should be read with, so that every meter drawn
+// this frame sees the same readings
+pub fn meter_frame(ui: &egui::Ui) -> u64 {
+    ui.ctx().cumulative_frame_nr()
+}
+
+// draw one bar per channel, showing RMS solid, peak lighter, a peak hold line
+// and a clip indicator. falloff, hold and clip state live in egui's memory
+// under `id`. clicking the meter clears the clip indicator
+pub fn level_meter_ui(
+    ui: &mut egui::Ui,
+    id: Id,
+    readings: &[MeterReading],
+    size: Vec2,
+    vertical: bool,
+) -> Response {
+    let (rect, resp) = ui.allocate_exact_size(size, Sense::click());
+    let dt = ui.input(|i| i.stable_dt);
+
+    let mut channels: Vec<ChannelState> = ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+    if !readings.is_empty() {
+        channels.resize(readings.len(), ChannelState::default());
+    }
+    for (i, channel) in channels.iter_mut().enumerate() {
+        channel.update(readings.get(i).copied().unwrap_or_default(), dt);
+        if resp.clicked() {
+            channel.clipped = false;
+        }
+    }
+
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 0., visuals.extreme_bg_color);
+
+    let count = channels.len().max(1) as f32;
+    for (i, channel) in channels.iter().enumerate() {
+        // split the meter between the channels, leaving room for the clip
+        // indicator at the top end
+        let (bar, clip) = if vertical {
+            let width = (rect.width() - GAP * (count - 1.)) / count;
+            let x = rect.min.x + i as f32 * (width + GAP);
+            (
+                Rect::from_min_max(
+                    pos2(x, rect.min.y + CLIP_SIZE + GAP),
+                    pos2(x + width, rect.max.y),
+                ),
+                Rect::from_min_max(pos2(x, rect.min.y), pos2(x + width, rect.min.y + CLIP_SIZE)),
+            )
+        } else {
+            let height = (rect.height() - GAP * (count - 1.)) / count;
+            let y = rect.min.y + i as f32 * (height + GAP);
+            (
+                Rect::from_min_max(
+                    pos2(rect.min.x, y),
+                    pos2(rect.max.x - CLIP_SIZE - GAP, y + height),
+                ),
+                Rect::from_min_max(
+                    pos2(rect.max.x - CLIP_SIZE, y),
+                    pos2(rect.max.x, y + height),
+                ),
+            )
+        };
+
+        let peak = filled(bar, meter_fraction(channel.peak), vertical);
+        painter.rect_filled(peak, 0., meter_color(channel.peak).gamma_multiply(0.4));
+        let rms = filled(bar, meter_fraction(channel.rms), vertical);
+        painter.rect_filled(rms, 0., meter_color(channel.rms));
+
+        if channel.hold > 0. {
+            let hold = filled(bar, meter_fraction(channel.hold), vertical);
+            let stroke = Stroke::new(1., visuals.strong_text_color());
+            if vertical {
+                painter.hline(bar.x_range(), hold.min.y, stroke);
+            } else {
+                painter.vline(hold.max.x, bar.y_range(), stroke);
+            }
+        }
+
+        let clip_color = if channel.clipped {
+            Color32::RED
+        } else {
+            visuals.faint_bg_color
+        };
+        painter.rect_filled(clip, 0., clip_color);
+    }
+
+    // keep falling off until everything has settled
+    if channels.iter().any(|c| c.peak > 1e-4 || c.hold > 1e-4) {
+        ui.ctx().request_repaint();
+    }
+    ui.data_mut(|d| d.insert_temp(id, channels));
+
+    resp.on_hover_text("Click to clear the clip indicator")
+}
+
+// meters for every output channel
+pub fn master_strip_ui(ui: &mut egui::Ui) {
+    ui.vertical_centered(|ui| {
+        ui.strong("Master");
+        match audio::master_levels(meter_frame(ui)) {
+            Some(readings) => {
+                let width = (readings.len().max(1) as f32 * 10.).max(24.);
+                let height = ui.available_height() - 24.;
+                level_meter_ui(
+                    ui,
+                    Id::new("master_meter"),
+                    &readings,
+                    Vec2::new(width, height),
+                    true,
+                );
+                ui.weak(format!("{} ch", readings.len()));
+            }
+            None => {
+                ui.weak("No output");
+            }
+        }
+    });
+}
@@ -1,21 +1,60 @@
+mod active;
+mod autosave;
+mod cart;
+mod effects;
 pub mod inspector;
+mod lists;
+mod meters;
+mod readiness;
+mod relink;
+mod render;
+pub mod shortcuts;
+mod show_mode;
+mod stage;
+mod time;
+mod timeline;
+mod unsaved;
 
 pub use inspector::AudioCueInspector;
-use inspector::{get_cue_inspector, InspectorPanelTabs};
+
+use active::{active_cues_panel_ui, ActiveCuesPanel};
+use autosave::{Autosave, SaveSettings};
+use cart::CartView;
+use effects::{output_effects_ui, OutputEffectsWindow};
+use inspector::{get_cue_inspector, InspectorContext, InspectorPanelTabs};
+use lists::{cue_lists_ui, ListColumns, ListLayout};
+use meters::{level_meter_ui, master_strip_ui, meter_frame};
+use readiness::{readiness_panel_ui, ReadinessPanel};
+use relink::{relink_dialog_ui, RelinkDialog};
+use render::{render_window_ui, RenderWindow};
+use shortcuts::{shortcut_editor_ui, Action, KeyBinding, KeyBindings, ShortcutEditor, Shortcuts};
+use show_mode::{show_mode_settings_ui, ShowModeLock, ShowModeSettings};
+use stage::{stage_window_ui, StageWindow};
+use time::{time_drag, time_format_ui};
+use timeline::{timeline_panel_ui, TimelinePanel};
+use unsaved::{ModifiedTracker, PendingAction};
 
 use crate::{
-    cues::{AudioCue, BonkCue, RemarkCue},
+    cues::{AudioCue, BonkCue, ContinueMode, ControlCue, CueTime, RemarkCue},
+    project::backup,
     Cue, MultitypeCue, Project,
 };
 
 use anyhow::anyhow;
 use egui::{Color32, Rect, RichText, Stroke, TextStyle};
 use egui_extras::{Column, TableBuilder};
-use log::{debug, error};
+use log::{debug, error, warn};
 use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::BufReader, path::PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 const CUE_ID_WIDTH_PX: f32 = 50.;
+const METER_WIDTH_PX: f32 = 64.;
+const MARKER_WIDTH_PX: f32 = 16.;
+// color picked when a cue is first given one
+const DEFAULT_CUE_COLOR: [u8; 3] = [200, 60, 60];
 
 #[derive(Serialize, Deserialize)]
 pub struct CueballApp {
@@ -23,6 +62,15 @@ pub struct CueballApp {
     state: AppState,
 
     project_path: Option<PathBuf>,
+
+    #[serde(default)]
+    save_settings: SaveSettings,
+
+    #[serde(default)]
+    shortcuts: Shortcuts,
+
+    #[serde(default)]
+    show_mode_settings: ShowModeSettings,
 }
 
 impl Default for CueballApp {
@@ -30,48 +78,172 @@ impl Default for CueballApp {
         CueballApp {
             state: AppState::default(),
             project_path: None,
+            save_settings: SaveSettings::default(),
+            shortcuts: Shortcuts::default(),
+            show_mode_settings: ShowModeSettings::default(),
         }
     }
 }
 
 impl CueballApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    // `project_path` overrides the project remembered from the last session
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        project_path: Option<PathBuf>,
+        show_mode: bool,
+    ) -> Self {
         egui_extras::install_image_loaders(&cc.egui_ctx);
 
         // persistence
-        if let Some(storage) = cc.storage {
-            let stored: CueballApp =
-                eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-            if let Some(ref project_path) = stored.project_path {
-                match open_project(project_path.clone()) {
-                    Ok(new_project) => Self {
-                        state: AppState {
-                            project: new_project,
-                            ..Default::default()
-                        },
-                        project_path: Some(project_path.clone()),
-                    },
-                    Err(err) => {
-                        error!(
-                            "Failed to open project at {:?}: {}",
-                            project_path.clone(),
-                            err
-                        );
-                        Default::default()
-                    }
+        let mut app: CueballApp = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        if let Some(columns) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, ListColumns::STORAGE_KEY))
+        {
+            app.state.list_columns = columns;
+        }
+        let project_path = project_path.or(app.project_path.take());
+
+        if let Some(ref project_path) = project_path {
+            match open_project(project_path.clone()) {
+                Ok(new_project) => app.load_project(new_project),
+                Err(err) => {
+                    error!(
+                        "Failed to open project at {:?}: {}",
+                        project_path.clone(),
+                        err
+                    );
                 }
-            } else {
-                Default::default()
             }
         } else {
-            Default::default()
+            // an untitled project may have been autosaved before a crash
+            app.state.autosave.project_opened(&app.state.project);
+            app.state.modified.mark_saved(&app.state.project);
         }
+        app.state.show_mode = show_mode;
+        app
     }
 
     fn set_project_path(&mut self, path: Option<PathBuf>) -> () {
         self.project_path = path.clone();
         self.state.project.path = path.clone();
     }
+
+    // replace the current project with a freshly opened one
+    fn load_project(&mut self, project: Project) {
+        self.project_path = project.path.clone();
+        self.state.project = project;
+        self.state.project.apply_output_settings();
+        self.state.active_list = 0;
+        self.state.readiness_panel.invalidate();
+        self.state.relink_dialog.invalidate();
+        self.state.autosave.project_opened(&self.state.project);
+        self.state.modified.mark_saved(&self.state.project);
+    }
+
+    // save the project, asking for a path first if it doesn't have one yet
+    // or if `save_as` is set
+    fn save(&mut self, save_as: bool) {
+        if save_as {
+            self.set_project_path(None);
+        }
+        match save_project(&mut self.state.project, self.save_settings.backup_count) {
+            Ok(path) => {
+                self.set_project_path(Some(path));
+                self.state.autosave.project_saved(&self.state.project);
+                self.state.modified.mark_saved(&self.state.project);
+                self.state.relink_dialog.invalidate();
+            }
+            Err(err) => {
+                error!("Failed to save project: {}", err)
+            }
+        }
+    }
+
+    // fire list GO keys, cue and cart hotkeys and the shortcuts in the
+    // registry. GO keys and hotkeys are plain keys, so they're left alone
+    // while a text field is being typed into
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // the hotkey editors need to know which plain keys the shortcuts use
+        self.state.shortcut_keys = self.shortcuts.plain_keys();
+        if self.state.shortcut_editor.is_recording() {
+            return;
+        }
+        let typing = ctx.wants_keyboard_input();
+
+        // hotkeys take their key presses before the shortcuts see them, so a
+        // list's own GO key wins over the GO shortcut
+        let bindings = KeyBindings::new(&self.state.project, &self.state.shortcut_keys);
+        let pressed = ctx.input_mut(|inp| bindings.pressed_hotkeys(inp, typing));
+        for binding in pressed {
+            match binding {
+                KeyBinding::Go(l) => handle_go(&mut self.state, l),
+                KeyBinding::Cue(l, i) => {
+                    self.state.project.go_cue(l, i);
+                }
+                KeyBinding::Cart(c, i) => self.state.project.toggle_cart_cell(c, i),
+                KeyBinding::Action(_) => {}
+            }
+        }
+
+        let actions = ctx.input_mut(|inp| self.shortcuts.fired(inp, typing));
+        for action in actions {
+            self.run_action(ctx, action);
+        }
+    }
+
+    fn run_action(&mut self, ctx: &egui::Context, action: Action) {
+        let state = &mut self.state;
+        let l = state.active_list;
+        // selection only moves in the list being shown
+        let playhead = match state.cart_view.active {
+            Some(_) => None,
+            None => Some(state.project.cue_lists[l].playhead),
+        };
+        match action {
+            Action::Go => handle_go(state, l),
+            Action::Panic => state.project.stop_all(),
+            Action::SelectNext => match playhead {
+                Some(Some(i)) => {
+                    state.select_cue(l, i + 1);
+                }
+                Some(None) => {
+                    state.select_cue(l, 0);
+                }
+                None => {}
+            },
+            Action::SelectPrevious => {
+                if let Some(Some(i)) = playhead {
+                    state.select_cue(l, i.saturating_sub(1));
+                }
+            }
+            Action::SelectFirst => {
+                if playhead.is_some() {
+                    state.select_cue(l, 0);
+                }
+            }
+            Action::SelectLast => {
+                let len = state.project.cue_lists[l].len();
+                if playhead.is_some() && len != 0 {
+                    state.select_cue(l, len - 1);
+                }
+            }
+            Action::EditId | Action::EditName => {
+                if !state.show_mode {
+                    state.edit_request = Some(action);
+                }
+            }
+            Action::Save => self.save(false),
+            Action::SaveAs => self.save(true),
+            // loading another project mid-show is never what was meant
+            Action::Open | Action::New if state.show_mode => {}
+            Action::Open => self.request(ctx, PendingAction::Open),
+            Action::New => self.request(ctx, PendingAction::New),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -90,11 +262,37 @@ impl Default for DebugSettings {
 pub struct AppState {
     pub project: Project,
 
-    selected_cue: Option<usize>,
+    // list whose selected cue is shown in the inspector. each list's
+    // selection is its playhead
+    active_list: usize,
+    list_layout: ListLayout,
+    list_columns: ListColumns,
+    cart_view: CartView,
 
-    dragged_cue: Option<usize>,
-    hovered_cue: Option<usize>,
+    // (list, cue) indices
+    dragged_cue: Option<(usize, usize)>,
+    hovered_cue: Option<(usize, usize)>,
     inspector_panel: InspectorPanel,
+    active_cues_panel: ActiveCuesPanel,
+    show_master_strip: bool,
+    readiness_panel: ReadinessPanel,
+    timeline_panel: TimelinePanel,
+    relink_dialog: RelinkDialog,
+    output_effects_window: OutputEffectsWindow,
+    stage_window: StageWindow,
+    render_window: RenderWindow,
+    autosave: Autosave,
+    modified: ModifiedTracker,
+
+    // editing is locked while in show mode
+    show_mode: bool,
+    show_mode_lock: ShowModeLock,
+
+    shortcut_editor: ShortcutEditor,
+    // plain keys bound to shortcuts, which hotkeys can't also use
+    shortcut_keys: Vec<(Action, egui::Key)>,
+    // field of the selected cue to start editing, asked for from the keyboard
+    edit_request: Option<Action>,
 
     debug_settings: DebugSettings,
 }
@@ -103,25 +301,78 @@ impl Default for AppState {
     fn default() -> Self {
         AppState {
             project: Project::default(),
-            selected_cue: None,
+            active_list: 0,
+            list_layout: ListLayout::default(),
+            list_columns: ListColumns::default(),
+            cart_view: CartView::default(),
             hovered_cue: None,
             dragged_cue: None,
             inspector_panel: InspectorPanel::default(),
+            active_cues_panel: ActiveCuesPanel::default(),
+            show_master_strip: true,
+            readiness_panel: ReadinessPanel::default(),
+            timeline_panel: TimelinePanel::default(),
+            relink_dialog: RelinkDialog::default(),
+            output_effects_window: OutputEffectsWindow::default(),
+            stage_window: StageWindow::default(),
+            render_window: RenderWindow::default(),
+            autosave: Autosave::default(),
+            modified: ModifiedTracker::default(),
+            show_mode: false,
+            show_mode_lock: ShowModeLock::default(),
+            shortcut_editor: ShortcutEditor::default(),
+            shortcut_keys: Vec::new(),
+            edit_request: None,
             debug_settings: DebugSettings::default(),
         }
     }
 }
 
 impl AppState {
-    fn select_cue(&mut self, new_cue_index: usize) -> Option<&MultitypeCue> {
-        if new_cue_index < self.project.cues.len() {
-            let new_cue = &self.project.cues[new_cue_index];
-            self.selected_cue = Some(new_cue_index);
-            Some(new_cue)
+    fn select_cue(&mut self, list: usize, new_cue_index: usize) -> Option<&MultitypeCue> {
+        let cue_list = self.project.cue_lists.get_mut(list)?;
+        if new_cue_index < cue_list.len() {
+            cue_list.set_playhead(Some(new_cue_index));
+            self.active_list = list;
+            self.cart_view.active = None;
+            Some(&cue_list[new_cue_index])
         } else {
             None
         }
     }
+
+    // the cue shown in the inspector: the selected cart cell when a cart is
+    // shown, otherwise the active list's selected cue
+    fn inspected_cue_mut(&mut self) -> Option<&mut MultitypeCue> {
+        if let Some(c) = self.cart_view.active {
+            let i = self.cart_view.selected_cell?;
+            return Some(&mut self.project.carts[c].cell_mut(i).cue);
+        }
+        let list = &mut self.project.cue_lists[self.active_list];
+        let i = list.playhead?;
+        Some(&mut list[i])
+    }
+
+    // add a cue to the end of the active list and select it, or to the first
+    // free cell of the cart being shown
+    fn add_cue(&mut self, make_cue: impl FnOnce(String) -> MultitypeCue) {
+        if let Some(c) = self.cart_view.active {
+            let cart = &mut self.project.carts[c];
+            match cart.first_empty_cell() {
+                Some((row, column)) => {
+                    let cue = make_cue(cart.get_new_cue_id().to_string());
+                    self.cart_view.selected_cell = Some(cart.set_cell(row, column, cue));
+                }
+                None => error!("Cart {} is full!", cart.name),
+            }
+            return;
+        }
+
+        let list = &mut self.project.cue_lists[self.active_list];
+        if let Ok(i) = list.add(make_cue(list.get_new_cue_id().to_string())) {
+            self.select_cue(self.active_list, i);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -141,49 +392,29 @@ impl eframe::App for CueballApp {
     // save state on shutdown
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
+        eframe::set_value(storage, ListColumns::STORAGE_KEY, &self.state.list_columns);
     }
 
     // paint frame
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // program-wide keyboard shortcuts
-        ctx.input(|inp| {
-            if inp.modifiers.command {
-                // control-s for save(s)
-                if inp.key_pressed(egui::Key::S) {
-                    if inp.modifiers.shift {
-                        // save-as
-                        self.set_project_path(None);
-                    }
-                    match save_project(&self.state.project) {
-                        Ok(path) => {
-                            self.set_project_path(Some(path));
-                        }
-                        Err(err) => {
-                            error!("Failed to save project: {}", err)
-                        }
-                    }
-                }
+        self.track_modified(ctx);
+        self.autosave_tick(ctx);
+        self.recovery_prompt_ui(ctx);
+        self.unlock_prompt_ui(ctx);
+        self.unsaved_changes_ui(ctx);
 
-                if inp.key_pressed(egui::Key::O) {
-                    match FileDialog::new()
-                        .add_filter("cueball", &["cueball", "cbp"])
-                        .pick_file()
-                    {
-                        None => {
-                            error!("No file path selected!");
-                        }
-                        Some(path) => match open_project(path) {
-                            Ok(new_project) => {
-                                self.state.project = new_project;
-                            }
-                            Err(err) => {
-                                error!("Failed to open project: {}", err);
-                            }
-                        },
-                    };
-                }
-            }
-        });
+        // fire cues whose pre-wait is over and follow on from finished ones
+        self.state.project.update(Instant::now());
+        if self.state.project.sequence_pending() {
+            ctx.request_repaint_after(Duration::from_millis(10));
+        }
+        // and keep checking on media being analyzed
+        if self.state.project.update_media() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        // keyboard shortcuts, before any widget gets to see the keys
+        self.handle_shortcuts(ctx);
 
         // top bar
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -194,95 +425,153 @@ impl eframe::App for CueballApp {
                     // theme widget
                     egui::widgets::global_theme_preference_buttons(ui);
 
+                    let show_mode = self.state.show_mode;
+                    if ui
+                        .add_enabled(!show_mode, egui::Button::new("New Project"))
+                        .clicked()
+                    {
+                        self.request(ctx, PendingAction::New);
+                    }
+
                     // save & save as buttons
                     if ui.button("Save").clicked() {
-                        match save_project(&self.state.project) {
-                            Ok(path) => {
-                                self.set_project_path(Some(path));
-                            }
-                            Err(err) => {
-                                error!("Failed to save project: {}", err)
-                            }
-                        }
+                        self.save(false);
                     }
 
                     // save as the same as save, but reset project path
                     if ui.button("Save As").clicked() {
-                        self.set_project_path(None);
-                        match save_project(&self.state.project) {
-                            Ok(path) => {
-                                self.set_project_path(Some(path));
-                            }
-                            Err(err) => {
-                                error!("Failed to save project: {}", err)
-                            }
-                        }
+                        self.save(true);
                     }
 
                     // open button
-                    if ui.button("Open").clicked() {
-                        match FileDialog::new()
-                            .add_filter("cueball", &["cueball", "cbp"])
-                            .pick_file()
-                        {
+                    if ui
+                        .add_enabled(!show_mode, egui::Button::new("Open"))
+                        .clicked()
+                    {
+                        self.request(ctx, PendingAction::Open);
+                    }
+
+                    // copy all media next to the project and save it there
+                    if ui
+                        .add_enabled(!show_mode, egui::Button::new("Collect and Save"))
+                        .clicked()
+                    {
+                        match FileDialog::new().pick_folder() {
                             None => {
-                                error!("No file path selected!");
+                                error!("No folder selected!");
                             }
-                            Some(path) => match open_project(path) {
-                                Ok(new_project) => {
-                                    self.state.project = new_project;
-                                }
-                                Err(err) => {
-                                    error!("Failed to open project: {}", err);
+                            Some(dir) => {
+                                let bundle_dir = dir.join(self.state.project.file_stem());
+                                match self.state.project.collect(&bundle_dir) {
+                                    Ok(path) => {
+                                        self.set_project_path(Some(path));
+                                        self.state.autosave.project_saved(&self.state.project);
+                                        self.state.modified.mark_saved(&self.state.project);
+                                        self.state.relink_dialog.invalidate();
+                                    }
+                                    Err(err) => error!("Failed to collect project: {}", err),
                                 }
-                            },
-                        };
+                            }
+                        }
+                    }
+
+                    if ui.button("Keyboard Shortcuts").clicked() {
+                        self.state.shortcut_editor.open = true;
                     }
 
+                    ui.menu_button("Show Mode", |ui| {
+                        show_mode_settings_ui(ui, &mut self.show_mode_settings, show_mode);
+                    });
+
+                    ui.menu_button("Backups", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Backups to keep:");
+                            ui.add(egui::DragValue::new(&mut self.save_settings.backup_count));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Autosave every:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.save_settings.autosave_interval)
+                                    .suffix(" s"),
+                            )
+                            .on_hover_text("0 disables autosave");
+                        });
+                    });
+
                     // quit button
                     if ui.button("Quit").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        self.request(ctx, PendingAction::Quit);
                     }
                 });
 
                 // cues menu
                 ui.menu_button("Cues", |ui| {
+                    if self.state.show_mode {
+                        ui.disable();
+                    }
                     if ui.button("Audio").clicked() {
-                        if let Ok(i) =
-                            self.state
-                                .project
-                                .cues
-                                .add(MultitypeCue::Audio(AudioCue::with_id(
-                                    self.state.project.cues.get_new_cue_id().to_string(),
-                                )))
-                        {
-                            self.state.select_cue(i);
-                        }
+                        self.state
+                            .add_cue(|id| MultitypeCue::Audio(AudioCue::with_id(id)));
                     }
                     if ui.button("Remark").clicked() {
-                        if let Ok(i) =
-                            self.state
-                                .project
-                                .cues
-                                .add(MultitypeCue::Remark(RemarkCue::with_id(
-                                    self.state.project.cues.get_new_cue_id().to_string(),
-                                )))
-                        {
-                            self.state.select_cue(i);
-                        }
+                        self.state
+                            .add_cue(|id| MultitypeCue::Remark(RemarkCue::with_id(id)));
                     }
                     if ui.button("Bonk").clicked() {
-                        if let Ok(i) =
-                            self.state
-                                .project
-                                .cues
-                                .add(MultitypeCue::Bonk(BonkCue::with_id(
-                                    self.state.project.cues.get_new_cue_id().to_string(),
-                                )))
+                        self.state
+                            .add_cue(|id| MultitypeCue::Bonk(BonkCue::with_id(id)));
+                    }
+                    if ui.button("Control").clicked() {
+                        self.state
+                            .add_cue(|id| MultitypeCue::Control(ControlCue::with_id(id)));
+                    }
+                });
+
+                // tools menu
+                ui.menu_button("Tools", |ui| {
+                    ui.checkbox(&mut self.state.active_cues_panel.open, "Active Cues");
+                    ui.checkbox(&mut self.state.show_master_strip, "Master Meters");
+                    ui.checkbox(&mut self.state.timeline_panel.open, "Timeline");
+                    ui.checkbox(&mut self.state.output_effects_window.open, "Output Effects");
+                    ui.checkbox(&mut self.state.stage_window.open, "Stage Plot");
+                    ui.menu_button("Columns", |ui| self.state.list_columns.menu_ui(ui));
+                    if ui
+                        .checkbox(&mut self.state.readiness_panel.open, "Show Readiness")
+                        .clicked()
+                    {
+                        self.state.readiness_panel.invalidate();
+                    }
+                    ui.add_enabled(
+                        !self.state.show_mode,
+                        egui::Checkbox::new(
+                            &mut self.state.relink_dialog.open,
+                            "Relink Missing Media",
+                        ),
+                    );
+                    ui.checkbox(&mut self.state.render_window.open, "Render to WAV");
+                    ui.separator();
+                    ui.add_enabled_ui(!self.state.show_mode, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Loudness target: ");
+                            ui.add(
+                                egui::DragValue::new(&mut self.state.project.loudness_target)
+                                    .range(-60.0..=0.0)
+                                    .speed(0.1)
+                                    .suffix(" LUFS"),
+                            );
+                        });
+                        time_format_ui(ui, &mut self.state.project.time_format);
+                        if ui
+                            .button("Normalize All Audio")
+                            .on_hover_text("Trim every audio cue to the loudness target")
+                            .clicked()
                         {
-                            self.state.select_cue(i);
+                            let report = self.state.project.normalize_loudness();
+                            for (id, why) in &report.skipped {
+                                warn!("Did not normalize cue {}: {}", id, why);
+                            }
                         }
-                    }
+                    });
                 });
 
                 // ui.with_layout(
@@ -302,11 +591,13 @@ impl eframe::App for CueballApp {
                 //     );
                 // });
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::LEFT), |ui| {
-                    ui.toggle_value(
-                        &mut self.state.debug_settings.disable_continue,
-                        "Disable Continue",
-                    );
+                    // debug settings are for rehearsing, not the performance
+                    let debug_settings = &mut self.state.debug_settings;
+                    ui.add_enabled_ui(!self.state.show_mode, |ui| {
+                        ui.toggle_value(&mut debug_settings.disable_continue, "Disable Continue")
+                    });
                     ui.label("Debug Settings:");
+                    self.show_mode_button_ui(ui);
                     ui.add_sized(
                         ui.available_size(),
                         egui::Label::new(RichText::new(self.state.project.name.clone()).strong()),
@@ -315,27 +606,68 @@ impl eframe::App for CueballApp {
             });
         });
 
+        relink_dialog_ui(ctx, &mut self.state);
+        output_effects_ui(ctx, &mut self.state);
+        stage_window_ui(ctx, &mut self.state);
+        render_window_ui(ctx, &mut self.state);
+        let bindings = KeyBindings::new(&self.state.project, &self.state.shortcut_keys);
+        shortcut_editor_ui(
+            ctx,
+            &mut self.state.shortcut_editor,
+            &mut self.shortcuts,
+            &bindings,
+        );
+
+        if self.state.active_cues_panel.open {
+            egui::SidePanel::left("active_cues_panel")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    active_cues_panel_ui(ui, &mut self.state);
+                });
+        }
+
+        if self.state.show_master_strip {
+            egui::SidePanel::right("master_strip")
+                .resizable(false)
+                .show(ctx, master_strip_ui);
+        }
+
+        if self.state.readiness_panel.open {
+            egui::SidePanel::right("readiness_panel")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    readiness_panel_ui(ui, &mut self.state);
+                });
+        }
+
         egui::TopBottomPanel::bottom("inspector_panel")
             .resizable(true)
             .show(ctx, |ui| {
                 ui.set_min_height(216.);
-                if let Some(cue_index) = self.state.selected_cue {
+                let context = InspectorContext::new(&self.state.project);
+                if let Some(cue) = self.state.inspected_cue_mut() {
+                    // which tabs this cue has
+                    let tabs: Vec<InspectorPanelTabs> = match get_cue_inspector(cue, context) {
+                        Some(cue_inspector) => InspectorPanelTabs::ITER
+                            .into_iter()
+                            .map(|(tab, _)| tab)
+                            .filter(|tab| cue_inspector.has_tab(tab))
+                            .collect(),
+                        None => Vec::new(),
+                    };
                     ui.vertical(|ui| {
                         // tab ribbon
                         ui.horizontal(|ui| {
                             ui.set_height(16.);
 
                             // add buttons for each tab
-                            let cue = &mut self.state.project.cues[cue_index];
-                            if let Some(cue_inspector) = get_cue_inspector(cue) {
-                                for (tab, name) in InspectorPanelTabs::ITER {
-                                    if cue_inspector.has_tab(&tab) {
-                                        ui.selectable_value(
-                                            &mut self.state.inspector_panel.selected_tab,
-                                            tab,
-                                            name,
-                                        );
-                                    }
+                            for (tab, name) in InspectorPanelTabs::ITER {
+                                if tabs.contains(&tab) {
+                                    ui.selectable_value(
+                                        &mut self.state.inspector_panel.selected_tab,
+                                        tab,
+                                        name,
+                                    );
                                 }
                             }
                         });
@@ -350,28 +682,37 @@ impl eframe::App for CueballApp {
                 }
             });
 
+        if self.state.timeline_panel.open && self.state.cart_view.active.is_none() {
+            egui::TopBottomPanel::bottom("timeline_panel")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    timeline_panel_ui(ui, &mut self.state);
+                });
+        }
+
         // central panel
         egui::CentralPanel::default().show(ctx, |ui| {
-            cue_list_ui(ui, &mut self.state);
+            cue_lists_ui(ui, &mut self.state);
         });
+
+        // the selected cue may not have been drawn to take the request
+        self.state.edit_request = None;
     }
 }
 
 fn open_project(path: PathBuf) -> Result<Project, anyhow::Error> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let mut project: Project = serde_json::from_reader(reader)?;
-
-    project.cues.init_cues();
-
-    debug!("Loaded project {}", project.name);
+    Project::open(path)
+}
 
-    Ok(project)
+// a failed backup shouldn't stop the save it was protecting
+fn back_up(path: &Path, backup_count: usize) {
+    if let Err(err) = backup::rotate_backups(path, backup_count) {
+        warn!("Could not back up {}: {}", path.display(), err);
+    }
 }
 
-fn save_project(project: &Project) -> Result<PathBuf, anyhow::Error> {
-    match &project.path {
+fn save_project(project: &mut Project, backup_count: usize) -> Result<PathBuf, anyhow::Error> {
+    match project.path.clone() {
         None => {
             // pick new save path
             match FileDialog::new()
@@ -381,35 +722,54 @@ fn save_project(project: &Project) -> Result<PathBuf, anyhow::Error> {
             {
                 None => Err(anyhow!("No file save path selected!")),
                 Some(path) => {
-                    let file = File::create(&path)?;
-                    serde_json::to_writer(file, project)?;
-                    debug!("Saved project {} to {}", project.name, path.display());
+                    back_up(&path, backup_count);
+                    project.save_to(&path)?;
                     Ok(path)
                 }
             }
         }
         Some(path) => {
             // save path already set
-            let file = File::create(&path)?;
-            serde_json::to_writer(file, project)?;
-            debug!("Saved project {} to {}", project.name, path.display());
-            Ok(path.to_path_buf())
+            back_up(&path, backup_count);
+            project.save_to(&path)?;
+            Ok(path)
         }
     }
 }
 
 fn inspector_panel_body(ui: &mut egui::Ui, state: &mut AppState) {
     //let cue = &mut project.cues.list[project.selected_cue.unwrap()];
-    let cue = match state.selected_cue {
-        Some(cue_index) => &mut state.project.cues[cue_index],
+    let show_mode = state.show_mode;
+    let selected_tab = state.inspector_panel.selected_tab;
+    let in_cart = state.cart_view.active.is_some();
+    let context = InspectorContext::new(&state.project);
+    let bindings = KeyBindings::new(&state.project, &state.shortcut_keys);
+    // a cart cue's hotkey presses its cell, starting or stopping it
+    let hotkey_binding = match state.cart_view.active {
+        Some(c) => state
+            .cart_view
+            .selected_cell
+            .map(|i| KeyBinding::Cart(c, i)),
+        None => {
+            let l = state.active_list;
+            state.project.cue_lists[l]
+                .playhead
+                .map(|i| KeyBinding::Cue(l, i))
+        }
+    };
+    let cue = match state.inspected_cue_mut() {
+        Some(cue) => cue,
         None => return,
     };
     ui.vertical(|ui| {
         //ui.set_min_height(200.);
         ui.set_width(ui.available_width());
+        if show_mode {
+            ui.disable();
+        }
 
         // special handling for basics tab
-        if state.inspector_panel.selected_tab == InspectorPanelTabs::Basics {
+        if selected_tab == InspectorPanelTabs::Basics {
             // first row, default things for all cues
             ui.horizontal(|ui| {
                 // cue number
@@ -433,92 +793,197 @@ fn inspector_panel_body(ui: &mut egui::Ui, state: &mut AppState) {
                     ui.text_edit_singleline(&mut cue_name);
                     cue.set_name(&cue_name);
                 });
+                let runnable = cue.get_attributes().runnable;
+                if let Some(binding) = hotkey_binding.filter(|_| runnable) {
+                    ui.horizontal(|ui| {
+                        ui.label("Hotkey:");
+                        let mut key = cue.get_hotkey().cloned();
+                        shortcuts::hotkey_edit_ui(ui, &mut key, 60., &bindings, binding)
+                            .on_hover_text(
+                                "Key that fires this cue on its own, e.g. A, F1 or Num1. \
+                                 Leave empty for none",
+                            );
+                        cue.set_hotkey(key);
+                    });
+                }
+            });
+
+            // markings every cue has
+            ui.horizontal(|ui| {
+                let mut colored = cue.get_color().is_some();
+                ui.checkbox(&mut colored, "Color");
+                let mut color = cue.get_color().unwrap_or(DEFAULT_CUE_COLOR);
+                if colored {
+                    ui.color_edit_button_srgb(&mut color);
+                }
+                cue.set_color(colored.then_some(color));
+
+                let mut flagged = cue.is_flagged();
+                ui.checkbox(&mut flagged, "Flagged")
+                    .on_hover_text("Mark the cue to come back to later");
+                cue.set_flagged(flagged);
+
+                ui.label("Notes:");
+                let mut notes = cue.get_notes();
+                ui.add(egui::TextEdit::singleline(&mut notes).desired_width(f32::INFINITY));
+                cue.set_notes(&notes);
             });
 
+            // how the cue is timed within its sequence
+            if !in_cart {
+                ui.horizontal(|ui| {
+                    ui.label("Pre-wait:");
+                    let mut pre_wait = cue.get_pre_wait();
+                    ui.add(
+                        time_drag(
+                            &mut pre_wait,
+                            context.time_format,
+                            CueTime::ZERO..=CueTime::from_nanos(i64::MAX),
+                        )
+                        .speed(0.05),
+                    )
+                    .on_hover_text("Time between the cue being triggered and it firing");
+                    cue.set_pre_wait(pre_wait);
+
+                    ui.label("Continue:");
+                    let mut mode = cue.get_continue();
+                    egui::ComboBox::from_id_salt("continue_mode")
+                        .selected_text(
+                            ContinueMode::ITER
+                                .iter()
+                                .find(|(m, _)| *m == mode)
+                                .map_or("", |(_, name)| *name),
+                        )
+                        .show_ui(ui, |ui| {
+                            for (m, name) in ContinueMode::ITER {
+                                ui.selectable_value(&mut mode, m, name);
+                            }
+                        });
+                    cue.set_continue(mode);
+                });
+            }
+
             // second row, for things specifc to a type of cue
-            if let Some(mut cue_inspector) = get_cue_inspector(cue) {
+            if let Some(mut cue_inspector) = get_cue_inspector(cue, context) {
                 cue_inspector.draw_tab(ui, &InspectorPanelTabs::Basics);
             }
         } else {
-            if let Some(mut cue_inspector) = get_cue_inspector(cue) {
-                cue_inspector.draw_tab(ui, &state.inspector_panel.selected_tab);
+            if let Some(mut cue_inspector) = get_cue_inspector(cue, context) {
+                cue_inspector.draw_tab(ui, &selected_tab);
             }
         }
     });
 }
 
-fn cue_list_ui(ui: &mut egui::Ui, state: &mut AppState) {
-    let focus = ui.memory(|mem| mem.focused());
-
+fn cue_list_ui(ui: &mut egui::Ui, state: &mut AppState, l: usize) {
     let scroll_height = ui.available_height();
-    TableBuilder::new(ui)
+    let columns = state.list_columns.clone();
+
+    let mut table = TableBuilder::new(ui)
+        .id_salt(l)
         .striped(true)
         .resizable(true)
         .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
         .min_scrolled_height(0.0)
         .max_scroll_height(scroll_height)
         .drag_to_scroll(false)
-        .column(Column::auto())
-        .column(Column::auto())
-        .column(Column::remainder())
-        .column(Column::remainder())
+        .column(Column::exact(MARKER_WIDTH_PX))
+        .column(Column::auto());
+    if columns.kind {
+        table = table.column(Column::auto());
+    }
+    table = table.column(Column::remainder());
+    if columns.duration {
+        table = table.column(Column::remainder());
+    }
+    if columns.level {
+        table = table.column(Column::exact(METER_WIDTH_PX));
+    }
+    if columns.state {
+        table = table.column(Column::auto());
+    }
+    if columns.file {
+        table = table.column(Column::auto().clip(true));
+    }
+    if columns.referents {
+        table = table.column(Column::auto());
+    }
+    if columns.notes {
+        table = table.column(Column::remainder().clip(true));
+    }
+
+    table
         .sense(egui::Sense::click_and_drag())
         .header(20.0, |mut header| {
-            header.col(|ui| {
-                ui.set_min_width(CUE_ID_WIDTH_PX);
-                ui.strong("Q");
-            });
-            header.col(|ui| {
-                ui.strong("Type");
-            });
-            header.col(|ui| {
-                ui.strong("Name");
-            });
-            header.col(|ui| {
-                ui.strong("Duration");
-            });
+            // right-clicking any header picks the columns
+            let mut header_col = |name: &str| {
+                let (_, resp) = header.col(|ui| {
+                    ui.strong(name);
+                });
+                resp.context_menu(|ui| state.list_columns.menu_ui(ui));
+            };
+            header_col("");
+            header_col("Q");
+            if columns.kind {
+                header_col("Type");
+            }
+            header_col("Name");
+            if columns.duration {
+                header_col("Duration");
+            }
+            if columns.level {
+                header_col("Level");
+            }
+            if columns.state {
+                header_col("On");
+            }
+            if columns.file {
+                header_col("File");
+            }
+            if columns.referents {
+                header_col("Targets");
+            }
+            if columns.notes {
+                header_col("Notes");
+            }
         })
         .body(|mut body| {
             body.ui_mut().input(|inp| {
-                if let Some(i) = state.selected_cue {
-                    if inp.key_pressed(egui::Key::Home) {
-                        state.select_cue(0);
-                    }
-                    if inp.key_pressed(egui::Key::ArrowDown) {
-                        state.select_cue(i + 1);
-                    }
-                    if inp.key_pressed(egui::Key::ArrowUp) && i != 0 {
-                        state.select_cue(i - 1);
-                    }
-                    if inp.key_pressed(egui::Key::End) && state.project.cues.len() != 0 {
-                        state.select_cue(state.project.cues.len() - 1);
-                    }
-                    if inp.key_pressed(egui::Key::Space) && focus.is_none() {
-                        handle_go(state);
-                    }
-                    if inp.pointer.primary_released() {
-                        if let Some(h) = state.hovered_cue {
-                            if let Some(d) = state.dragged_cue {
-                                state.project.cues.move_cue(d, h);
-                                state.select_cue(h);
-                            }
+                if inp.pointer.primary_released() && !state.show_mode {
+                    // cues can only be dragged within their own list
+                    if let (Some((hl, h)), Some((dl, d))) = (state.hovered_cue, state.dragged_cue) {
+                        if hl == l && dl == l {
+                            state.project.cue_lists[l].move_cue(d, h);
+                            state.select_cue(l, h);
                         }
                     }
                 }
             });
 
+            // only the active list takes edit requests from the keyboard
+            let editing = l == state.active_list;
             let mut hovered_this_frame = false;
             let mut dragged_this_frame = false;
-            body.rows(18.0, state.project.cues.len(), |mut row| {
+            // cues counting down their pre-wait
+            let now = Instant::now();
+            let format = state.project.time_format;
+            let pre_waits: Vec<Option<CueTime>> = (0..state.project.cue_lists[l].len())
+                .map(|i| state.project.pre_wait_remaining(l, i, now))
+                .collect();
+            body.rows(18.0, state.project.cue_lists[l].len(), |mut row| {
                 let i = row.index();
-                let cue = &mut state.project.cues[i];
-                let cue_selected = Some(i) == state.selected_cue;
-                let cue_hovered = Some(i) == state.hovered_cue;
+                let cue_list = &mut state.project.cue_lists[l];
+                let cue_selected = Some(i) == cue_list.playhead;
+                let cue = &mut cue_list[i];
+                let cue_hovered = Some((l, i)) == state.hovered_cue;
 
                 row.set_selected(cue_selected);
 
-                if let Some(hovered_idx) = state.hovered_cue {
-                    if let Some(dragged_idx) = state.dragged_cue {
+                if let Some((hovered_list, hovered_idx)) = state.hovered_cue {
+                    if let Some((_, dragged_idx)) = state
+                        .dragged_cue
+                        .filter(|(dl, _)| *dl == hovered_list && *dl == l)
+                    {
                         if hovered_idx < dragged_idx {
                             // dragging cue up
                             if hovered_idx == i {
@@ -535,25 +1000,63 @@ fn cue_list_ui(ui: &mut egui::Ui, state: &mut AppState) {
                     }
                 }
 
+                // color and flag
+                row.col(|ui| {
+                    let rect = ui.max_rect();
+                    if let Some([r, g, b]) = cue.get_color() {
+                        let mut stripe = rect;
+                        stripe.set_width(4.);
+                        ui.painter()
+                            .rect_filled(stripe, 0., Color32::from_rgb(r, g, b));
+                    }
+                    if cue.is_flagged() {
+                        ui.add_space(4.);
+                        ui.label(RichText::new("⚑").color(Color32::ORANGE))
+                            .on_hover_text("Flagged");
+                    }
+                    if cue.is_loading() {
+                        ui.add(egui::Spinner::new().size(10.))
+                            .on_hover_text("Analyzing media");
+                    }
+                    if cue.is_errored() {
+                        ui.label(RichText::new("⚠").color(Color32::RED))
+                            .on_hover_text("Media is missing or can't be read");
+                    }
+                    match cue.get_continue() {
+                        ContinueMode::DoNotContinue => {}
+                        ContinueMode::AutoContinue => {
+                            ui.label("↓").on_hover_text("Auto-continue");
+                        }
+                        ContinueMode::AutoFollow => {
+                            ui.label("⤓").on_hover_text("Auto-follow");
+                        }
+                    }
+                });
+
                 // cue id
                 row.col(|ui| {
+                    ui.set_min_width(CUE_ID_WIDTH_PX);
                     let mut cue_id = cue.get_id();
                     let r = ui.add(
                         egui::TextEdit::singleline(&mut cue_id)
                             .font(TextStyle::Monospace)
                             .frame(false)
                             .desired_width(0.)
-                            .clip_text(false),
+                            .clip_text(false)
+                            .interactive(!state.show_mode),
                     );
                     cue.set_id(&cue_id);
-                    if cue_selected && ui.input(|i| i.key_pressed(egui::Key::N)) {
+                    if cue_selected && editing && state.edit_request == Some(Action::EditId) {
                         r.request_focus();
+                        state.edit_request = None;
                     }
                 });
                 // cue type
-                row.col(|ui| {
-                    ui.label(cue.type_str_short());
-                });
+                if columns.kind {
+                    row.col(|ui| {
+                        ui.label(cue.type_str_short());
+                    });
+                }
                 // cue name
                 row.col(|ui| {
                     // let r = ui.label(cue.get_name());
@@ -562,49 +1065,134 @@ fn cue_list_ui(ui: &mut egui::Ui, state: &mut AppState) {
                         egui::TextEdit::singleline(&mut cue_name)
                             .frame(false)
                             .desired_width(0.)
-                            .clip_text(false),
+                            .clip_text(false)
+                            .interactive(!state.show_mode),
                     );
                     cue.set_name(&cue_name);
-                    if cue_selected && ui.input(|i| i.key_pressed(egui::Key::Q)) {
+                    if cue_selected && editing && state.edit_request == Some(Action::EditName) {
                         r.request_focus();
+                        state.edit_request = None;
                     }
                 });
 
                 // times column
-                row.col(|ui| {
-                    ui.set_max_width(64.);
-                    if let Some(len) = cue.length() {
-                        let rect = ui.available_rect_before_wrap();
-                        // let painter = ui.painter_at(rect);
-                        let painter = ui.painter();
-                        painter.rect_stroke(
-                            rect,
-                            0.,
-                            Stroke::new(2., Color32::from_rgb(0, 200, 0)),
-                            egui::StrokeKind::Inside,
-                        );
-                        if let Some(el) = cue.elapsed() {
-                            let el_width = el / len * rect.width();
-                            painter.rect_filled(
-                                Rect {
-                                    min: rect.min,
-                                    max: egui::Pos2 {
-                                        x: rect.min.x + el_width,
-                                        y: rect.max.y,
-                                    },
-                                },
+                if columns.duration {
+                    row.col(|ui| {
+                        ui.set_max_width(64.);
+                        if cue.is_loading() {
+                            ui.weak("loading");
+                        } else if let Some(remaining) = pre_waits[i] {
+                            ui.label(
+                                RichText::new(format!("-{}", remaining.format(format)))
+                                    .color(Color32::YELLOW),
+                            )
+                            .on_hover_text("Pre-wait");
+                            ui.ctx().request_repaint();
+                        } else if let Some(len) = cue.length() {
+                            let rect = ui.available_rect_before_wrap();
+                            // let painter = ui.painter_at(rect);
+                            let painter = ui.painter();
+                            painter.rect_stroke(
+                                rect,
                                 0.,
-                                Color32::from_rgba_unmultiplied(0, 128, 0, 64),
+                                Stroke::new(2., Color32::from_rgb(0, 200, 0)),
+                                egui::StrokeKind::Inside,
                             );
-                            ui.ctx().request_repaint();
-                            ui.label(format!("{:.3}", el));
+                            if let Some(el) = cue.elapsed() {
+                                let progress = el.as_secs() / len.as_secs().max(f64::EPSILON);
+                                let el_width = progress as f32 * rect.width();
+                                painter.rect_filled(
+                                    Rect {
+                                        min: rect.min,
+                                        max: egui::Pos2 {
+                                            x: rect.min.x + el_width,
+                                            y: rect.max.y,
+                                        },
+                                    },
+                                    0.,
+                                    Color32::from_rgba_unmultiplied(0, 128, 0, 64),
+                                );
+                                ui.ctx().request_repaint();
+                                ui.label(el.format(format));
+                            } else {
+                                ui.label(len.format(format));
+                            }
                         } else {
-                            ui.label(format!("{:.3}", len));
+                            ui.weak(CueTime::ZERO.format(format));
                         }
-                    } else {
-                        ui.weak(format!("{:.3}", 0.));
-                    }
-                });
+                    });
+                }
+
+                // level meter column
+                if columns.level {
+                    row.col(|ui| {
+                        let readings = cue.levels(meter_frame(ui)).unwrap_or_default();
+                        level_meter_ui(
+                            ui,
+                            egui::Id::new(("cue_meter", l, i)),
+                            &readings,
+                            egui::vec2(METER_WIDTH_PX, 12.),
+                            false,
+                        );
+                    });
+                }
+
+                // enabled and armed
+                if columns.state {
+                    row.col(|ui| {
+                        if !cue.get_attributes().runnable {
+                            return;
+                        }
+                        let mut enabled = cue.is_enabled();
+                        ui.add_enabled(
+                            !state.show_mode,
+                            egui::Checkbox::without_text(&mut enabled),
+                        )
+                        .on_hover_text("Enabled");
+                        cue.set_enabled(enabled);
+                        let mut armed = cue.is_armed();
+                        ui.add_enabled(!state.show_mode, egui::Checkbox::without_text(&mut armed))
+                            .on_hover_text("Armed");
+                        cue.set_armed(armed);
+                    });
+                }
+
+                // media file names
+                if columns.file {
+                    row.col(|ui| {
+                        let media = cue.get_media();
+                        let names: Vec<String> = media
+                            .iter()
+                            .map(|path| match Path::new(path).file_name() {
+                                Some(name) => name.to_string_lossy().to_string(),
+                                None => path.to_string(),
+                            })
+                            .collect();
+                        if !names.is_empty() {
+                            let paths: Vec<&str> = media.iter().map(|p| p.as_str()).collect();
+                            ui.label(names.join(", ")).on_hover_text(paths.join("\n"));
+                        }
+                    });
+                }
+
+                // cues this one acts on
+                if columns.referents {
+                    row.col(|ui| {
+                        let referents: Vec<&str> =
+                            cue.get_referents().iter().map(|r| r.as_str()).collect();
+                        ui.label(referents.join(", "));
+                    });
+                }
+
+                if columns.notes {
+                    row.col(|ui| {
+                        let notes = cue.get_notes();
+                        if !notes.is_empty() {
+                            ui.add(egui::Label::new(&notes).truncate())
+                                .on_hover_text(&notes);
+                        }
+                    });
+                }
 
                 let resp = row.response();
                 let clicked = resp.clicked();
@@ -612,34 +1200,35 @@ fn cue_list_ui(ui: &mut egui::Ui, state: &mut AppState) {
                 let hovered = resp.contains_pointer();
 
                 if clicked {
-                    if cue_selected {
-                        state.selected_cue = None;
+                    if cue_selected && l == state.active_list {
+                        state.project.cue_lists[l].set_playhead(None);
                     } else {
-                        state.select_cue(i);
+                        state.select_cue(l, i);
                     }
                 }
                 if dragged {
-                    state.dragged_cue = Some(i);
+                    state.dragged_cue = Some((l, i));
                     dragged_this_frame = true;
                 }
                 if hovered {
-                    state.hovered_cue = Some(i);
+                    state.hovered_cue = Some((l, i));
                     hovered_this_frame = true;
                 }
             });
 
-            if !dragged_this_frame {
+            // other lists keep track of their own drags
+            if !dragged_this_frame && state.dragged_cue.is_some_and(|(dl, _)| dl == l) {
                 state.dragged_cue = None;
             }
-            if !hovered_this_frame {
+            if !hovered_this_frame && state.hovered_cue.is_some_and(|(hl, _)| hl == l) {
                 state.hovered_cue = None;
             }
         });
 }
 
-fn handle_go(state: &mut AppState) {
+fn handle_go(state: &mut AppState, list: usize) {
     // get current cue
-    let cue_index = match state.selected_cue {
+    let cue_index = match state.project.cue_lists[list].playhead {
         Some(i) => i,
         None => {
             debug!("No cue selected for Go");
@@ -647,22 +1236,14 @@ fn handle_go(state: &mut AppState) {
         }
     };
 
-    // immutably get cue for next cue index
-    let cue = &state.project.cues[cue_index];
-    let next_cue_index = cue_index
-        + if state.debug_settings.disable_continue {
-            0
-        } else {
-            cue.next_offset()
-        };
-
-    let cue_mut = &mut state.project.cues[cue_index];
-
     // play current cue
-    cue_mut.go();
+    let next_cue_index = state.project.go_cue(list, cue_index);
 
     // advance playhead
-    if let None = state.select_cue(next_cue_index) {
-        state.selected_cue = None;
-    }
+    let next_cue_index = if state.debug_settings.disable_continue && !state.show_mode {
+        cue_index
+    } else {
+        next_cue_index
+    };
+    state.project.cue_lists[list].set_playhead(Some(next_cue_index));
 }
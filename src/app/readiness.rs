@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use egui::{Color32, RichText};
+
+use super::AppState;
+use crate::project::{CheckProgress, ProblemSeverity};
+
+// how often to look again while media is still being analyzed
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Default)]
+pub struct ReadinessPanel {
+    pub open: bool,
+    // results of the last check, or None if the project needs rechecking
+    progress: Option<CheckProgress>,
+    last_run: Option<Instant>,
+}
+
+impl ReadinessPanel {
+    // forget the last results, so they get recomputed next time the panel is
+    // shown
+    pub fn invalidate(&mut self) {
+        self.progress = None;
+    }
+}
+
+pub fn readiness_panel_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    // media is decoded by the analysis workers, so the check is run again
+    // until they have got through all of it
+    let panel = &mut state.readiness_panel;
+    let due = match (&panel.progress, panel.last_run) {
+        (Some(progress), Some(last_run)) => {
+            !progress.is_done() && last_run.elapsed() >= POLL_INTERVAL
+        }
+        _ => true,
+    };
+    if due {
+        panel.progress = Some(state.project.check_progress());
+        panel.last_run = Some(Instant::now());
+    }
+    let Some(progress) = &state.readiness_panel.progress else {
+        return;
+    };
+    let problems = &progress.problems;
+
+    let errors = problems
+        .iter()
+        .filter(|p| p.severity() == ProblemSeverity::Error)
+        .count();
+    let warnings = problems.len() - errors;
+    let (media, waiting) = (progress.media, progress.waiting);
+
+    ui.horizontal(|ui| {
+        ui.heading("Show Readiness");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.button("Re-check").clicked() {
+                state.readiness_panel.invalidate();
+            }
+        });
+    });
+    if waiting > 0 {
+        let done = media - waiting;
+        ui.add(
+            egui::ProgressBar::new(done as f32 / media as f32)
+                .text(format!("Checking media, {} of {}", done, media)),
+        );
+        ui.ctx().request_repaint_after(POLL_INTERVAL);
+    } else if errors == 0 && warnings == 0 {
+        ui.label(RichText::new("Ready to run").color(Color32::GREEN));
+    } else {
+        ui.label(format!("{} errors, {} warnings", errors, warnings));
+    }
+    ui.separator();
+
+    let mut clicked_cue = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        if let Some(progress) = &state.readiness_panel.progress {
+            for problem in &progress.problems {
+                let color = match problem.severity() {
+                    ProblemSeverity::Warning => Color32::YELLOW,
+                    ProblemSeverity::Error => Color32::RED,
+                };
+                let resp = ui.add(
+                    egui::Label::new(RichText::new(problem.to_string()).color(color))
+                        .sense(egui::Sense::click()),
+                );
+                if resp.on_hover_text("Select this cue").clicked() {
+                    clicked_cue = Some(problem.cue().to_string());
+                }
+            }
+        }
+    });
+
+    if let Some(id) = clicked_cue {
+        if let Some((l, i)) = state.project.resolve_ref(state.active_list, &id) {
+            state.select_cue(l, i);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use egui::{Color32, RichText};
+use log::{error, warn};
+use rfd::FileDialog;
+
+use super::AppState;
+use crate::project::RelinkReport;
+
+// how often to look for missing media again, for files that turn up or go
+// away outside of the app
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+pub struct RelinkDialog {
+    pub open: bool,
+    last_report: Option<RelinkReport>,
+    // missing media as of when it was last looked for
+    missing: Option<(Vec<String>, Instant)>,
+}
+
+impl RelinkDialog {
+    // look for missing media again next time the dialog is shown
+    pub fn invalidate(&mut self) {
+        self.missing = None;
+    }
+}
+
+pub fn relink_dialog_ui(ctx: &egui::Context, state: &mut AppState) {
+    let mut open = state.relink_dialog.open;
+    egui::Window::new("Relink Missing Media")
+        .open(&mut open)
+        .resizable(true)
+        .show(ctx, |ui| {
+            let stale = match &state.relink_dialog.missing {
+                Some((_, checked)) => checked.elapsed() >= REFRESH_INTERVAL,
+                None => true,
+            };
+            if stale {
+                let missing = state.project.missing_media();
+                state.relink_dialog.missing = Some((missing, Instant::now()));
+            }
+            ui.ctx().request_repaint_after(REFRESH_INTERVAL);
+            let missing = match &state.relink_dialog.missing {
+                Some((missing, _)) => missing.clone(),
+                None => Vec::new(),
+            };
+            if missing.is_empty() {
+                ui.label(RichText::new("All media files found").color(Color32::GREEN));
+            } else {
+                ui.label(format!("{} media files are missing:", missing.len()));
+                egui::ScrollArea::vertical()
+                    .max_height(200.)
+                    .show(ui, |ui| {
+                        for path in &missing {
+                            ui.monospace(path);
+                        }
+                    });
+
+                if ui.button("Search Folder...").clicked() {
+                    match FileDialog::new().pick_folder() {
+                        Some(dir) => match state.project.relink_media(&dir) {
+                            Ok(report) => {
+                                state.relink_dialog.last_report = Some(report);
+                                state.relink_dialog.invalidate();
+                                state.readiness_panel.invalidate();
+                            }
+                            Err(err) => error!("Failed to search {}: {}", dir.display(), err),
+                        },
+                        None => warn!("No folder selected!"),
+                    }
+                }
+            }
+
+            if let Some(report) = &state.relink_dialog.last_report {
+                ui.separator();
+                ui.label(format!("Relinked {} files", report.relinked.len()));
+                for (old, new) in &report.relinked {
+                    ui.monospace(format!("{} -> {}", old, new));
+                }
+            }
+        });
+    state.relink_dialog.open = open;
+}
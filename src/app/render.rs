@@ -0,0 +1,136 @@
+use egui::{Color32, RichText};
+use log::{error, warn};
+use rfd::FileDialog;
+
+use super::{time::time_drag, AppState};
+use crate::{cues::CueTime, project::RenderOptions, Cue};
+
+const RATES: [u32; 3] = [44100, 48000, 96000];
+
+#[derive(Debug, Default)]
+pub struct RenderWindow {
+    pub open: bool,
+    options: RenderOptions,
+    // cue indices in the active list
+    from: usize,
+    to: Option<usize>,
+    last_result: Option<Result<String, String>>,
+}
+
+// pick a cue of the active list by index
+fn cue_picker(ui: &mut egui::Ui, id_salt: &str, state: &AppState, index: &mut usize) {
+    let list = &state.project.cue_lists[state.active_list];
+    let label = |i: usize| match list.get(i) {
+        Some(cue) => format!("{} {}", cue.get_id(), cue.get_name()),
+        None => String::new(),
+    };
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(label(*index))
+        .show_ui(ui, |ui| {
+            for i in 0..list.len() {
+                ui.selectable_value(index, i, label(i));
+            }
+        });
+}
+
+// render a range of the active list to a WAV file, offline
+pub fn render_window_ui(ctx: &egui::Context, state: &mut AppState) {
+    let mut open = state.render_window.open;
+    egui::Window::new("Render to WAV")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let len = state.project.cue_lists[state.active_list].len();
+            if len == 0 {
+                ui.label("No cues to render");
+                return;
+            }
+            let mut from = state.render_window.from.min(len - 1);
+            let mut to = state.render_window.to.unwrap_or(len - 1).min(len - 1);
+            let mut options = state.render_window.options;
+
+            egui::Grid::new("render_options")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("From");
+                    cue_picker(ui, "render_from", state, &mut from);
+                    ui.end_row();
+                    ui.label("To");
+                    cue_picker(ui, "render_to", state, &mut to);
+                    ui.end_row();
+                    ui.label("Channels");
+                    ui.add(egui::DragValue::new(&mut options.channels).range(1..=64));
+                    ui.end_row();
+                    ui.label("Sample rate");
+                    egui::ComboBox::from_id_salt("render_rate")
+                        .selected_text(format!("{} Hz", options.rate))
+                        .show_ui(ui, |ui| {
+                            for rate in RATES {
+                                ui.selectable_value(
+                                    &mut options.rate,
+                                    rate,
+                                    format!("{} Hz", rate),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Tail");
+                    ui.add(
+                        time_drag(
+                            &mut options.tail,
+                            state.project.time_format,
+                            CueTime::ZERO..=CueTime::from_millis(60000),
+                        )
+                        .speed(0.1),
+                    );
+                    ui.end_row();
+                });
+            to = to.max(from);
+
+            if ui.button("Render...").clicked() {
+                match FileDialog::new()
+                    .set_file_name(format!("{}.wav", state.project.file_stem()))
+                    .add_filter("WAV", &["wav"])
+                    .save_file()
+                {
+                    Some(path) => {
+                        let list = state.active_list;
+                        let result = state.project.render_to(list, from, to, options, &path);
+                        state.render_window.last_result = Some(match result {
+                            Ok(report) => {
+                                for (id, why) in &report.skipped {
+                                    warn!("Did not render cue {}: {}", id, why);
+                                }
+                                Ok(format!(
+                                    "Rendered {} cues ({}), skipped {}",
+                                    report.rendered.len(),
+                                    CueTime::from_frames(report.frames as i64, options.rate)
+                                        .format(state.project.time_format),
+                                    report.skipped.len()
+                                ))
+                            }
+                            Err(err) => {
+                                error!("Failed to render to {}: {}", path.display(), err);
+                                Err(err.to_string())
+                            }
+                        });
+                    }
+                    None => warn!("No render path selected!"),
+                }
+            }
+            match &state.render_window.last_result {
+                Some(Ok(text)) => {
+                    ui.label(text);
+                }
+                Some(Err(text)) => {
+                    ui.label(RichText::new(text).color(Color32::RED));
+                }
+                None => (),
+            }
+
+            state.render_window.from = from;
+            state.render_window.to = Some(to);
+            state.render_window.options = options;
+        });
+    state.render_window.open = open;
+}
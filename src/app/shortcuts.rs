@@ -0,0 +1,538 @@
+use std::collections::BTreeMap;
+
+use egui::{Color32, Key, KeyboardShortcut, Modifiers, RichText};
+use serde::{Deserialize, Serialize};
+
+use crate::{Cue, Project};
+
+// something the operator can do from the keyboard
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Go,
+    Panic,
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    EditId,
+    EditName,
+    Save,
+    SaveAs,
+    Open,
+    New,
+}
+
+impl Action {
+    pub const ITER: [(Action, &'static str); 12] = [
+        (Action::Go, "GO"),
+        (Action::Panic, "Panic (stop everything)"),
+        (Action::SelectNext, "Select next cue"),
+        (Action::SelectPrevious, "Select previous cue"),
+        (Action::SelectFirst, "Select first cue"),
+        (Action::SelectLast, "Select last cue"),
+        (Action::EditId, "Edit cue ID"),
+        (Action::EditName, "Edit cue name"),
+        (Action::Save, "Save"),
+        (Action::SaveAs, "Save As"),
+        (Action::Open, "Open"),
+        (Action::New, "New Project"),
+    ];
+
+    fn default_shortcut(self) -> Option<Shortcut> {
+        Some(match self {
+            Action::Go => Shortcut::new(Key::Space),
+            Action::Panic => Shortcut::new(Key::Escape),
+            Action::SelectNext => Shortcut::new(Key::ArrowDown),
+            Action::SelectPrevious => Shortcut::new(Key::ArrowUp),
+            Action::SelectFirst => Shortcut::new(Key::Home),
+            Action::SelectLast => Shortcut::new(Key::End),
+            Action::EditId => Shortcut::new(Key::N),
+            Action::EditName => Shortcut::new(Key::Q),
+            Action::Save => Shortcut::new(Key::S).command(),
+            Action::SaveAs => Shortcut::new(Key::S).command().shift(),
+            Action::Open => Shortcut::new(Key::O).command(),
+            Action::New => Shortcut::new(Key::N).command(),
+        })
+    }
+
+    // panic has to work no matter what, even halfway through typing a name
+    fn fires_while_typing(self) -> bool {
+        self == Action::Panic
+    }
+}
+
+// a key and the modifiers held with it. the key is stored by name, as
+// understood by egui
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+    pub key: String,
+    #[serde(default)]
+    pub command: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn new(key: Key) -> Self {
+        Self {
+            key: key.name().to_string(),
+            command: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    fn command(mut self) -> Self {
+        self.command = true;
+        self
+    }
+
+    fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    fn from_egui(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key: key.name().to_string(),
+            command: modifiers.command,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+        }
+    }
+
+    pub fn to_egui(&self) -> Option<KeyboardShortcut> {
+        let mut modifiers = Modifiers::NONE;
+        if self.command {
+            modifiers |= Modifiers::COMMAND;
+        }
+        if self.shift {
+            modifiers |= Modifiers::SHIFT;
+        }
+        if self.alt {
+            modifiers |= Modifiers::ALT;
+        }
+        Some(KeyboardShortcut::new(modifiers, Key::from_name(&self.key)?))
+    }
+
+    fn modifier_count(&self) -> usize {
+        [self.command, self.shift, self.alt]
+            .into_iter()
+            .filter(|&m| m)
+            .count()
+    }
+
+    // the key, if it's bound without any modifiers
+    fn plain_key(&self) -> Option<Key> {
+        (self.modifier_count() == 0)
+            .then(|| Key::from_name(&self.key))
+            .flatten()
+    }
+
+    // whether a focused text field would act on this instead
+    fn types_text(&self) -> bool {
+        !self.command && !self.alt && Key::from_name(&self.key).is_some_and(edits_text)
+    }
+
+    pub fn format(&self, ctx: &egui::Context) -> String {
+        match self.to_egui() {
+            Some(shortcut) => ctx.format_shortcut(&shortcut),
+            None => format!("{} (unknown key)", self.key),
+        }
+    }
+}
+
+// keyboard shortcuts, remembered between sessions
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Shortcuts {
+    // only the bindings that have been changed from their defaults, so that
+    // new actions get their default shortcut. None means unbound
+    overrides: BTreeMap<Action, Option<Shortcut>>,
+}
+
+impl Shortcuts {
+    pub fn binding(&self, action: Action) -> Option<Shortcut> {
+        match self.overrides.get(&action) {
+            Some(shortcut) => shortcut.clone(),
+            None => action.default_shortcut(),
+        }
+    }
+
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        if shortcut == action.default_shortcut() {
+            self.overrides.remove(&action);
+        } else {
+            self.overrides.insert(action, shortcut);
+        }
+    }
+
+    pub fn reset_all(&mut self) {
+        self.overrides.clear();
+    }
+
+    // actions bound to a key without modifiers, which hotkeys could clash with
+    pub fn plain_keys(&self) -> Vec<(Action, Key)> {
+        Action::ITER
+            .into_iter()
+            .filter_map(|(action, _)| Some((action, self.binding(action)?.plain_key()?)))
+            .collect()
+    }
+
+    // other actions bound to the same shortcut as `action`
+    fn conflicts(&self, action: Action) -> Vec<Action> {
+        let Some(shortcut) = self.binding(action) else {
+            return Vec::new();
+        };
+        Action::ITER
+            .into_iter()
+            .map(|(other, _)| other)
+            .filter(|&other| other != action && self.binding(other).as_ref() == Some(&shortcut))
+            .collect()
+    }
+
+    // actions whose shortcut was pressed this frame. their key presses are
+    // consumed, so focused widgets don't act on them too. while `typing`,
+    // shortcuts a text field would act on are left to it
+    pub fn fired(&self, inp: &mut egui::InputState, typing: bool) -> Vec<Action> {
+        let mut bound: Vec<(Action, Shortcut)> = Action::ITER
+            .into_iter()
+            .filter_map(|(action, _)| Some((action, self.binding(action)?)))
+            .collect();
+        // egui ignores extra modifiers when matching, so Ctrl+Shift+S has to
+        // be checked before Ctrl+S gets a chance to take it
+        bound.sort_by_key(|(_, shortcut)| std::cmp::Reverse(shortcut.modifier_count()));
+
+        let mut fired = Vec::new();
+        for (action, shortcut) in bound {
+            if typing && shortcut.types_text() && !action.fires_while_typing() {
+                continue;
+            }
+            if let Some(egui_shortcut) = shortcut.to_egui() {
+                if inp.consume_shortcut(&egui_shortcut) {
+                    fired.push(action);
+                }
+            }
+        }
+        fired
+    }
+}
+
+// something a key can be bound to on its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyBinding {
+    // a list's GO key
+    Go(usize),
+    // a cue's own hotkey, by (list, cue) indices
+    Cue(usize, usize),
+    // the hotkey of a cart cell's cue, by (cart, cell) indices
+    Cart(usize, usize),
+    // a shortcut without modifiers
+    Action(Action),
+}
+
+// every key bound without modifiers, in the order they get to take a key
+// press: list GO keys, cue hotkeys, cart hotkeys and then the shortcuts. a
+// key press only ever fires the first of them, and the editors refuse a key
+// that's already taken
+pub struct KeyBindings(Vec<(KeyBinding, Key, String)>);
+
+impl KeyBindings {
+    pub fn new(project: &Project, action_keys: &[(Action, Key)]) -> Self {
+        let mut bindings = Vec::new();
+        let key = |name: Option<&str>| name.and_then(Key::from_name);
+        for (l, list) in project.cue_lists.iter().enumerate() {
+            if let Some(key) = key(list.go_key.as_deref()) {
+                bindings.push((KeyBinding::Go(l), key, format!("GO for {}", list.name)));
+            }
+        }
+        for (l, list) in project.cue_lists.iter().enumerate() {
+            for (i, cue) in list.iter().enumerate() {
+                if let Some(key) = key(cue.get_hotkey().map(String::as_str)) {
+                    bindings.push((KeyBinding::Cue(l, i), key, format!("cue {}", cue.get_id())));
+                }
+            }
+        }
+        for (c, cart) in project.carts.iter().enumerate() {
+            for (i, cell) in cart.cells().enumerate() {
+                if let Some(key) = key(cell.cue.get_hotkey().map(String::as_str)) {
+                    let owner = format!("cue {} in {}", cell.cue.get_id(), cart.name);
+                    bindings.push((KeyBinding::Cart(c, i), key, owner));
+                }
+            }
+        }
+        for (action, key) in action_keys {
+            let name = Action::ITER
+                .into_iter()
+                .find_map(|(other, name)| (other == *action).then_some(name))
+                .unwrap_or_default();
+            bindings.push((KeyBinding::Action(*action), *key, name.to_string()));
+        }
+        Self(bindings)
+    }
+
+    // what, other than `binding`, already uses `key`
+    pub fn owner(&self, key: Key, binding: KeyBinding) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(other, other_key, _)| *other_key == key && *other != binding)
+            .map(|(_, _, owner)| owner.as_str())
+    }
+
+    // GO keys and hotkeys pressed this frame. their key presses are consumed,
+    // so nothing bound after them fires on the same press. while `typing`,
+    // hotkeys a text field would act on are left to it, but a list's GO key
+    // always fires, and takes the character it would have typed
+    pub fn pressed_hotkeys(&self, inp: &mut egui::InputState, typing: bool) -> Vec<KeyBinding> {
+        self.0
+            .iter()
+            .filter(|(binding, key, _)| match binding {
+                KeyBinding::Action(_) => false,
+                KeyBinding::Go(_) => consume_plain_key(inp, *key, typing),
+                _ if typing && edits_text(*key) => false,
+                _ => consume_plain_key(inp, *key, false),
+            })
+            .map(|(binding, _, _)| *binding)
+            .collect()
+    }
+}
+
+// whether pressing `key` on its own types a character
+fn types_char(key: Key) -> bool {
+    let arrow = matches!(
+        key,
+        Key::ArrowDown | Key::ArrowLeft | Key::ArrowRight | Key::ArrowUp
+    );
+    key == Key::Space || (key.symbol_or_name().chars().count() == 1 && !arrow)
+}
+
+// whether a focused text field acts on `key` pressed without Ctrl or Alt,
+// either typing it or editing and moving through what's been typed. function
+// keys and the like never do, so they can fire while typing
+fn edits_text(key: Key) -> bool {
+    types_char(key)
+        || matches!(
+            key,
+            Key::Tab
+                | Key::Enter
+                | Key::Backspace
+                | Key::Delete
+                | Key::Home
+                | Key::End
+                | Key::ArrowDown
+                | Key::ArrowLeft
+                | Key::ArrowRight
+                | Key::ArrowUp
+        )
+}
+
+// whether `key` was pressed without modifiers this frame, taking the press.
+// unlike `consume_key`, a press with Shift held is left for the shortcuts,
+// and holding the key down doesn't fire it again. with `take_text`, the
+// character typed by the press is taken too
+fn consume_plain_key(inp: &mut egui::InputState, key: Key, take_text: bool) -> bool {
+    let mut pressed = false;
+    let mut owed_text = 0;
+    inp.events.retain(|event| match event {
+        egui::Event::Key {
+            key: other,
+            pressed: true,
+            repeat: false,
+            modifiers,
+            ..
+        } if *other == key && modifiers.is_none() => {
+            pressed = true;
+            if take_text && types_char(key) {
+                owed_text += 1;
+            }
+            false
+        }
+        egui::Event::Text(_) if owed_text > 0 => {
+            owed_text -= 1;
+            false
+        }
+        _ => true,
+    });
+    pressed
+}
+
+// text field for a key bound on its own. what's typed is only taken once the
+// field loses focus, and not at all if the key is unknown or already taken
+pub fn hotkey_edit_ui(
+    ui: &mut egui::Ui,
+    hotkey: &mut Option<String>,
+    width: f32,
+    bindings: &KeyBindings,
+    binding: KeyBinding,
+) -> egui::Response {
+    let id = egui::Id::new(("hotkey_edit", binding));
+    let mut text = ui
+        .data(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| hotkey.clone().unwrap_or_default());
+    let resp = ui.add(
+        egui::TextEdit::singleline(&mut text)
+            .id(id.with("text"))
+            .desired_width(width),
+    );
+
+    let problem = match Key::from_name(&text) {
+        _ if text.is_empty() => None,
+        None => Some("Unknown key".to_string()),
+        Some(key) => bindings
+            .owner(key, binding)
+            .map(|owner| format!("Used by {}", owner)),
+    };
+    if let Some(problem) = &problem {
+        ui.label(RichText::new(problem).color(Color32::RED));
+    }
+
+    if resp.has_focus() {
+        ui.data_mut(|d| d.insert_temp(id, text));
+    } else {
+        if resp.lost_focus() && problem.is_none() {
+            *hotkey = (!text.is_empty()).then_some(text);
+        }
+        ui.data_mut(|d| d.remove::<String>(id));
+    }
+    resp
+}
+
+#[derive(Debug, Default)]
+pub struct ShortcutEditor {
+    pub open: bool,
+    // action waiting for the next key press to become its shortcut
+    recording: Option<Action>,
+    // why the last shortcut recorded wasn't taken
+    rejected: Option<String>,
+}
+
+impl ShortcutEditor {
+    // while recording, key presses belong to the editor and shouldn't fire
+    // anything
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+}
+
+pub fn shortcut_editor_ui(
+    ctx: &egui::Context,
+    editor: &mut ShortcutEditor,
+    shortcuts: &mut Shortcuts,
+    bindings: &KeyBindings,
+) {
+    if !editor.open {
+        editor.recording = None;
+        return;
+    }
+
+    // take the next key pressed as the shortcut being recorded
+    if let Some(action) = editor.recording {
+        let pressed = ctx.input(|inp| {
+            inp.events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    repeat: false,
+                    modifiers,
+                    ..
+                } => Some(Shortcut::from_egui(*key, *modifiers)),
+                _ => None,
+            })
+        });
+        if let Some(shortcut) = pressed {
+            // a shortcut can't be shared, with another action or a hotkey
+            let owner = match shortcut.plain_key() {
+                Some(key) => bindings
+                    .owner(key, KeyBinding::Action(action))
+                    .map(str::to_string),
+                None => Action::ITER
+                    .into_iter()
+                    .find(|(other, _)| {
+                        *other != action && shortcuts.binding(*other) == Some(shortcut.clone())
+                    })
+                    .map(|(_, name)| name.to_string()),
+            };
+            editor.rejected =
+                owner.map(|owner| format!("{} is already used by {}", shortcut.format(ctx), owner));
+            if editor.rejected.is_none() {
+                shortcuts.set(action, Some(shortcut));
+            }
+            editor.recording = None;
+        }
+    }
+
+    let mut open = editor.open;
+    egui::Window::new("Keyboard Shortcuts")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("List GO keys are set on each list's tab, and cue hotkeys in the inspector.");
+            ui.weak("While typing into a field, shortcuts without Ctrl or Alt type instead, except Panic.");
+            if let Some(rejected) = &editor.rejected {
+                ui.label(RichText::new(rejected).color(Color32::RED));
+            }
+            ui.separator();
+
+            egui::Grid::new("shortcut_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (action, name) in Action::ITER {
+                        ui.label(name);
+
+                        if editor.recording == Some(action) {
+                            ui.label(RichText::new("Press a key...").italics());
+                            if ui.button("Cancel").clicked() {
+                                editor.recording = None;
+                            }
+                        } else {
+                            match shortcuts.binding(action) {
+                                Some(shortcut) => {
+                                    ui.monospace(shortcut.format(ctx));
+                                }
+                                None => {
+                                    ui.weak("None");
+                                }
+                            }
+                            if ui.button("Record").clicked() {
+                                editor.recording = Some(action);
+                                editor.rejected = None;
+                            }
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Clear").clicked() {
+                                shortcuts.set(action, None);
+                            }
+                            if ui.button("Default").clicked() {
+                                shortcuts.set(action, action.default_shortcut());
+                            }
+                        });
+
+                        let conflicts = shortcuts.conflicts(action);
+                        if !conflicts.is_empty() {
+                            let names: Vec<&str> = Action::ITER
+                                .into_iter()
+                                .filter(|(other, _)| conflicts.contains(other))
+                                .map(|(_, name)| name)
+                                .collect();
+                            ui.label(
+                                RichText::new(format!("Also used by {}", names.join(", ")))
+                                    .color(Color32::YELLOW),
+                            );
+                        } else {
+                            ui.label("");
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            if ui.button("Reset All").clicked() {
+                shortcuts.reset_all();
+                editor.recording = None;
+            }
+        });
+    editor.open = open;
+}
@@ -0,0 +1,223 @@
+use std::time::Instant;
+
+use egui::{Color32, RichText};
+use serde::{Deserialize, Serialize};
+
+use super::CueballApp;
+
+// what it takes to leave show mode, so that it can't happen by accident
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnlockMethod {
+    #[default]
+    Click,
+    Hold,
+    Password,
+}
+
+impl UnlockMethod {
+    pub const ITER: [(UnlockMethod, &'static str); 3] = [
+        (UnlockMethod::Click, "Click"),
+        (UnlockMethod::Hold, "Press and hold"),
+        (UnlockMethod::Password, "Password"),
+    ];
+}
+
+// show mode preferences, remembered between sessions
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ShowModeSettings {
+    pub unlock: UnlockMethod,
+    // seconds the unlock button has to be held down for
+    pub hold_time: f32,
+    // kept in plain text, since this guards against slips rather than
+    // intruders. an empty password unlocks on click
+    pub password: String,
+}
+
+impl Default for ShowModeSettings {
+    fn default() -> Self {
+        Self {
+            unlock: UnlockMethod::Click,
+            hold_time: 2.,
+            password: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ShowModeLock {
+    // when the unlock button started being held down
+    held_since: Option<Instant>,
+    prompt_open: bool,
+    entered: String,
+    wrong_password: bool,
+}
+
+impl CueballApp {
+    pub(super) fn set_show_mode(&mut self, ctx: &egui::Context, on: bool) {
+        self.state.show_mode = on;
+        self.state.show_mode_lock = ShowModeLock::default();
+        if on {
+            // whatever was being typed into stops taking keys
+            ctx.memory_mut(|mem| mem.stop_text_input());
+            self.state.edit_request = None;
+            self.state.dragged_cue = None;
+            self.state.relink_dialog.open = false;
+        }
+    }
+
+    // top bar button for entering and leaving show mode
+    pub(super) fn show_mode_button_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.state.show_mode {
+            if ui
+                .button("Show Mode")
+                .on_hover_text("Lock editing for the performance")
+                .clicked()
+            {
+                self.set_show_mode(ui.ctx(), true);
+            }
+            return;
+        }
+
+        let label = RichText::new("🔒 SHOW MODE").strong().color(Color32::RED);
+        let mut unlock = self.show_mode_settings.unlock;
+        if unlock == UnlockMethod::Password && self.show_mode_settings.password.is_empty() {
+            unlock = UnlockMethod::Click;
+        }
+        match unlock {
+            UnlockMethod::Click => {
+                if ui
+                    .button(label)
+                    .on_hover_text("Click to unlock editing")
+                    .clicked()
+                {
+                    self.set_show_mode(ui.ctx(), false);
+                }
+            }
+            UnlockMethod::Hold => {
+                let hold_time = self.show_mode_settings.hold_time;
+                let resp = ui
+                    .button(label)
+                    .on_hover_text(format!("Hold for {:.1} s to unlock editing", hold_time));
+                let lock = &mut self.state.show_mode_lock;
+                if resp.is_pointer_button_down_on() {
+                    let held = lock.held_since.get_or_insert_with(Instant::now);
+                    let progress = held.elapsed().as_secs_f32() / hold_time.max(0.01);
+
+                    // fill the button as it is held
+                    let mut filled = resp.rect;
+                    filled.set_width(resp.rect.width() * progress.min(1.));
+                    ui.painter().rect_filled(
+                        filled,
+                        2.,
+                        Color32::from_rgba_unmultiplied(255, 0, 0, 48),
+                    );
+                    ui.ctx().request_repaint();
+
+                    if progress >= 1. {
+                        self.set_show_mode(ui.ctx(), false);
+                    }
+                } else {
+                    lock.held_since = None;
+                }
+            }
+            UnlockMethod::Password => {
+                if ui
+                    .button(label)
+                    .on_hover_text("Enter the password to unlock editing")
+                    .clicked()
+                {
+                    let lock = &mut self.state.show_mode_lock;
+                    lock.prompt_open = true;
+                    lock.entered.clear();
+                    lock.wrong_password = false;
+                }
+            }
+        }
+    }
+
+    pub(super) fn unlock_prompt_ui(&mut self, ctx: &egui::Context) {
+        if !self.state.show_mode || !self.state.show_mode_lock.prompt_open {
+            return;
+        }
+
+        let mut unlocked = false;
+        let lock = &mut self.state.show_mode_lock;
+        egui::Modal::new(egui::Id::new("show_mode_unlock")).show(ctx, |ui| {
+            ui.heading("Leave show mode");
+            let resp = ui.add(
+                egui::TextEdit::singleline(&mut lock.entered)
+                    .password(true)
+                    .hint_text("Password"),
+            );
+            resp.request_focus();
+            if lock.wrong_password {
+                ui.label(RichText::new("Wrong password").color(Color32::RED));
+            }
+
+            let submitted = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                if ui.button("Unlock").clicked() || submitted {
+                    if lock.entered == self.show_mode_settings.password {
+                        unlocked = true;
+                    } else {
+                        lock.wrong_password = true;
+                        lock.entered.clear();
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    lock.prompt_open = false;
+                }
+            });
+        });
+
+        if unlocked {
+            self.set_show_mode(ctx, false);
+        }
+    }
+}
+
+// preferences for leaving show mode. they can't be changed from inside it
+pub fn show_mode_settings_ui(ui: &mut egui::Ui, settings: &mut ShowModeSettings, show_mode: bool) {
+    if show_mode {
+        ui.disable();
+    }
+    ui.horizontal(|ui| {
+        ui.label("Unlock by:");
+        egui::ComboBox::from_id_salt("unlock_method")
+            .selected_text(
+                UnlockMethod::ITER
+                    .iter()
+                    .find(|(method, _)| *method == settings.unlock)
+                    .map_or("", |(_, name)| *name),
+            )
+            .show_ui(ui, |ui| {
+                for (method, name) in UnlockMethod::ITER {
+                    ui.selectable_value(&mut settings.unlock, method, name);
+                }
+            });
+    });
+    match settings.unlock {
+        UnlockMethod::Click => {}
+        UnlockMethod::Hold => {
+            ui.horizontal(|ui| {
+                ui.label("Hold for:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.hold_time)
+                        .range(0.5..=10.0)
+                        .speed(0.1)
+                        .suffix(" s"),
+                );
+            });
+        }
+        UnlockMethod::Password => {
+            ui.horizontal(|ui| {
+                ui.label("Password:");
+                ui.add(egui::TextEdit::singleline(&mut settings.password).password(true));
+            });
+            if settings.password.is_empty() {
+                ui.weak("No password set, so a click unlocks");
+            }
+        }
+    }
+}
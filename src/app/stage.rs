@@ -0,0 +1,172 @@
+use egui::{pos2, vec2, Color32, CursorIcon, Id, Pos2, Rect, Sense, Stroke, TextStyle};
+
+use super::AppState;
+use crate::audio::Speaker;
+
+const MARGIN: f32 = 12.;
+const HANDLE_RADIUS: f32 = 6.;
+const SPEAKER_COLOR: Color32 = Color32::from_rgb(120, 200, 255);
+
+#[derive(Debug, Default)]
+pub struct StageWindow {
+    pub open: bool,
+}
+
+// the stage from above, running from -1 to 1 each way with the audience at
+// the bottom
+pub(super) struct StagePlot {
+    rect: Rect,
+    painter: egui::Painter,
+}
+
+impl StagePlot {
+    pub fn new(ui: &mut egui::Ui, size: f32) -> Self {
+        let (rect, _) = ui.allocate_exact_size(vec2(size, size), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4., Color32::BLACK);
+        let plot = Self { rect, painter };
+        let grid = Stroke::new(1., Color32::from_gray(40));
+        plot.painter
+            .hline(rect.x_range(), plot.to_screen(0., 0.).y, grid);
+        plot.painter
+            .vline(plot.to_screen(0., 0.).x, rect.y_range(), grid);
+        plot.painter.text(
+            pos2(rect.center().x, rect.bottom() - 2.),
+            egui::Align2::CENTER_BOTTOM,
+            "audience",
+            TextStyle::Small.resolve(ui.style()),
+            Color32::from_gray(100),
+        );
+        plot
+    }
+
+    pub fn to_screen(&self, x: f32, y: f32) -> Pos2 {
+        let half = self.rect.shrink(MARGIN).size() / 2.;
+        self.rect.center() + vec2(x * half.x, -y * half.y)
+    }
+
+    fn to_stage(&self, pos: Pos2) -> (f32, f32) {
+        let half = self.rect.shrink(MARGIN).size() / 2.;
+        let offset = pos - self.rect.center();
+        (
+            (offset.x / half.x).clamp(-1., 1.),
+            (-offset.y / half.y).clamp(-1., 1.),
+        )
+    }
+
+    pub fn painter(&self) -> &egui::Painter {
+        &self.painter
+    }
+
+    pub fn speakers(&self, ui: &egui::Ui, speakers: &[Speaker]) {
+        for speaker in speakers {
+            let at = self.to_screen(speaker.x, speaker.y);
+            self.painter
+                .rect_filled(Rect::from_center_size(at, vec2(8., 8.)), 1., SPEAKER_COLOR);
+            self.painter.text(
+                at + vec2(6., -6.),
+                egui::Align2::LEFT_BOTTOM,
+                &speaker.name,
+                TextStyle::Small.resolve(ui.style()),
+                SPEAKER_COLOR,
+            );
+        }
+    }
+
+    // a draggable point, returning where it was dragged to this frame
+    pub fn handle(
+        &self,
+        ui: &mut egui::Ui,
+        id: Id,
+        (x, y): (f32, f32),
+        color: Color32,
+    ) -> Option<(f32, f32)> {
+        let at = self.to_screen(x, y);
+        let resp = ui
+            .interact(
+                Rect::from_center_size(at, vec2(2. * HANDLE_RADIUS, 2. * HANDLE_RADIUS)),
+                id,
+                Sense::drag(),
+            )
+            .on_hover_cursor(CursorIcon::Grab);
+        let stroke = if resp.hovered() || resp.dragged() {
+            Stroke::new(2., Color32::WHITE)
+        } else {
+            Stroke::NONE
+        };
+        self.painter.circle(at, HANDLE_RADIUS, color, stroke);
+        if resp.dragged() {
+            resp.interact_pointer_pos().map(|pos| self.to_stage(pos))
+        } else {
+            None
+        }
+    }
+}
+
+// where the output's speakers are, each dragged around the stage or typed in
+pub fn stage_window_ui(ctx: &egui::Context, state: &mut AppState) {
+    let mut open = state.stage_window.open;
+    egui::Window::new("Stage Plot")
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.add_enabled_ui(!state.show_mode, |ui| {
+                let mut speakers = state.project.speaker_layout();
+                let before = speakers.clone();
+
+                ui.horizontal_top(|ui| {
+                    let plot = StagePlot::new(ui, 240.);
+                    plot.speakers(ui, &speakers);
+                    for (i, speaker) in speakers.iter_mut().enumerate() {
+                        let id = Id::new(("stage_speaker", i));
+                        if let Some((x, y)) =
+                            plot.handle(ui, id, (speaker.x, speaker.y), Color32::TRANSPARENT)
+                        {
+                            (speaker.x, speaker.y) = (x, y);
+                        }
+                    }
+
+                    egui::Grid::new("stage_speakers")
+                        .num_columns(4)
+                        .show(ui, |ui| {
+                            for (i, speaker) in speakers.iter_mut().enumerate() {
+                                ui.label(format!("{}", i + 1));
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut speaker.name)
+                                        .desired_width(48.),
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut speaker.x)
+                                        .range(-1.0..=1.0)
+                                        .speed(0.01)
+                                        .prefix("x "),
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut speaker.y)
+                                        .range(-1.0..=1.0)
+                                        .speed(0.01)
+                                        .prefix("y "),
+                                );
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                if speakers != before {
+                    state.project.speakers = speakers;
+                    state.project.apply_output_settings();
+                }
+                if ui
+                    .add_enabled(
+                        !state.project.speakers.is_empty(),
+                        egui::Button::new("Reset to Default"),
+                    )
+                    .clicked()
+                {
+                    state.project.speakers.clear();
+                    state.project.apply_output_settings();
+                }
+            });
+        });
+    state.stage_window.open = open;
+}
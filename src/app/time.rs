@@ -0,0 +1,57 @@
+use std::ops::RangeInclusive;
+
+use egui::DragValue;
+
+use crate::cues::{CueTime, TimeFormat};
+
+// a time shown and typed in `format`, dragged a second at a time per 100
+// points. clock times show any microseconds so typed ones aren't lost
+pub(super) fn time_drag(
+    value: &mut CueTime,
+    format: TimeFormat,
+    range: RangeInclusive<CueTime>,
+) -> DragValue<'_> {
+    DragValue::from_get_set(move |set| {
+        if let Some(secs) = set {
+            *value = CueTime::from_secs(secs);
+        }
+        value.as_secs()
+    })
+    .range(range.start().as_secs()..=range.end().as_secs())
+    .speed(0.01)
+    .custom_formatter(move |secs, _| CueTime::from_secs(secs).format_precise(format))
+    .custom_parser(move |text| CueTime::parse(text, format).map(CueTime::as_secs))
+}
+
+// pick how times are shown across the app, and the rate or tempo they're
+// counted at
+pub(super) fn time_format_ui(ui: &mut egui::Ui, format: &mut TimeFormat) {
+    ui.horizontal(|ui| {
+        ui.label("Time format: ");
+        egui::ComboBox::from_id_salt("time_format")
+            .selected_text(format.name())
+            .show_ui(ui, |ui| {
+                for kind in TimeFormat::kinds() {
+                    let selected = std::mem::discriminant(format) == std::mem::discriminant(&kind);
+                    if ui.selectable_label(selected, kind.name()).clicked() && !selected {
+                        *format = kind;
+                    }
+                }
+            });
+        match format {
+            TimeFormat::Clock => {}
+            TimeFormat::Smpte { fps } => {
+                ui.add(DragValue::new(fps).range(1..=120).suffix(" fps"));
+            }
+            TimeFormat::Beats { bpm, per_bar } => {
+                ui.add(
+                    DragValue::new(bpm)
+                        .range(1.0..=999.0)
+                        .speed(0.1)
+                        .suffix(" BPM"),
+                );
+                ui.add(DragValue::new(per_bar).range(1..=16).suffix(" / bar"));
+            }
+        }
+    });
+}
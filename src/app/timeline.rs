@@ -0,0 +1,203 @@
+use std::time::Instant;
+
+use egui::{pos2, vec2, Align2, Color32, CursorIcon, FontId, Id, Rect, Sense, Stroke};
+
+use super::AppState;
+use crate::{
+    cues::{CueRunning, CueTime},
+    Cue,
+};
+
+const ROW_HEIGHT: f32 = 22.;
+const LABEL_WIDTH: f32 = 160.;
+const AXIS_HEIGHT: f32 = 18.;
+// narrowest a bar gets, so instant cues can still be seen and dragged
+const MIN_BAR_WIDTH: f32 = 6.;
+// closest time axis ticks are allowed to get
+const MIN_TICK_SPACING: f32 = 60.;
+const BAR_COLOR: Color32 = Color32::from_rgb(60, 120, 200);
+
+#[derive(Debug)]
+pub struct TimelinePanel {
+    pub open: bool,
+    // pixels per second
+    zoom: f32,
+}
+
+impl Default for TimelinePanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            zoom: 60.,
+        }
+    }
+}
+
+// the sequence of the active list's selected cue, laid out on a time axis.
+// while a sequence started with GO is still going, that one is shown instead
+pub fn timeline_panel_ui(ui: &mut egui::Ui, state: &mut AppState) {
+    let l = state.active_list;
+    let now = Instant::now();
+    let project = &state.project;
+    let list = &project.cue_lists[l];
+
+    ui.horizontal(|ui| {
+        ui.heading("Timeline");
+        ui.weak(&list.name);
+        ui.add(
+            egui::Slider::new(&mut state.timeline_panel.zoom, 10.0..=400.0)
+                .logarithmic(true)
+                .text("px/s"),
+        );
+    });
+    if list.len() == 0 {
+        ui.weak("This list is empty");
+        return;
+    }
+
+    let playing = project.last_go(l, now).filter(|(start, _)| {
+        project.sequence_pending()
+            || project
+                .sequence(l, *start)
+                .iter()
+                .any(|entry| list[entry.index].running() != CueRunning::Stopped)
+    });
+    let (start, playhead) = match playing {
+        Some((start, elapsed)) => (start, Some(elapsed)),
+        None => (project.sequence_start(l, list.playhead.unwrap_or(0)), None),
+    };
+    let entries = project.sequence(l, start);
+
+    let zoom = state.timeline_panel.zoom;
+    let format = project.time_format;
+    let end = entries
+        .iter()
+        .map(|entry| entry.fired + entry.length.unwrap_or_default())
+        .max()
+        .unwrap_or_default()
+        .as_secs_f32();
+    let size = vec2(
+        LABEL_WIDTH + (end + 1.) * zoom,
+        AXIS_HEIGHT + entries.len() as f32 * ROW_HEIGHT,
+    );
+
+    let mut drags = Vec::new();
+    egui::ScrollArea::both().show(ui, |ui| {
+        let (rect, _) = ui.allocate_exact_size(size.max(ui.available_size()), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals().clone();
+        let x = |t: f32| rect.min.x + LABEL_WIDTH + t * zoom;
+        let at = |t: CueTime| x(t.as_secs_f32());
+
+        // time axis, with ticks spaced out to stay readable
+        let step = [0.1, 0.25, 0.5, 1., 2., 5., 10., 30., 60., 120., 300.]
+            .into_iter()
+            .find(|step| step * zoom >= MIN_TICK_SPACING)
+            .unwrap_or(600.);
+        let mut t = 0.;
+        while x(t) <= rect.max.x {
+            painter.vline(
+                x(t),
+                rect.min.y..=rect.max.y,
+                Stroke::new(1., visuals.faint_bg_color),
+            );
+            painter.text(
+                pos2(x(t) + 2., rect.min.y),
+                Align2::LEFT_TOP,
+                format!("{}", t),
+                FontId::monospace(10.),
+                visuals.weak_text_color(),
+            );
+            t += step;
+        }
+
+        for (row, entry) in entries.iter().enumerate() {
+            let cue = &list[entry.index];
+            let top = rect.min.y + AXIS_HEIGHT + row as f32 * ROW_HEIGHT;
+            let middle = top + ROW_HEIGHT / 2.;
+
+            painter.text(
+                pos2(rect.min.x + 4., middle),
+                Align2::LEFT_CENTER,
+                format!("{} {}", cue.get_id(), cue.get_name()),
+                FontId::proportional(12.),
+                visuals.text_color(),
+            );
+
+            // pre-wait, from being triggered to firing
+            if entry.fired > entry.triggered {
+                painter.hline(
+                    at(entry.triggered)..=at(entry.fired),
+                    middle,
+                    Stroke::new(1., visuals.weak_text_color()),
+                );
+                painter.vline(
+                    at(entry.triggered),
+                    middle - 4.0..=middle + 4.,
+                    Stroke::new(1., visuals.weak_text_color()),
+                );
+            }
+
+            // the cue itself
+            let width = (entry.length.unwrap_or_default().as_secs_f32() * zoom).max(MIN_BAR_WIDTH);
+            let bar = Rect::from_min_size(
+                pos2(at(entry.fired), top + 3.),
+                vec2(width, ROW_HEIGHT - 6.),
+            );
+            let color = match cue.get_color() {
+                Some([r, g, b]) => Color32::from_rgb(r, g, b),
+                None => BAR_COLOR,
+            };
+            let fill = match cue.running() {
+                CueRunning::Stopped => color.gamma_multiply(0.6),
+                _ => color,
+            };
+            painter.rect_filled(bar, 2., fill);
+
+            // continuing into the next cue
+            if let Some(next) = entries.get(row + 1) {
+                painter.vline(
+                    at(next.triggered),
+                    middle..=middle + ROW_HEIGHT,
+                    Stroke::new(1., visuals.strong_text_color()),
+                );
+            }
+
+            let resp = ui
+                .interact(
+                    bar,
+                    Id::new(("timeline_bar", l, entry.index)),
+                    Sense::drag(),
+                )
+                .on_hover_text(format!(
+                    "Pre-wait {}, length {}",
+                    cue.get_pre_wait().format(format),
+                    match entry.length {
+                        Some(len) => len.format(format),
+                        None => "unknown".to_string(),
+                    }
+                ));
+            if !state.show_mode {
+                let resp = resp.on_hover_cursor(CursorIcon::ResizeHorizontal);
+                if resp.dragged() {
+                    drags.push((entry.index, resp.drag_delta().x / zoom));
+                }
+            }
+        }
+
+        if let Some(playhead) = playhead {
+            painter.vline(
+                at(playhead),
+                rect.min.y..=rect.max.y,
+                Stroke::new(1.5, Color32::YELLOW),
+            );
+            ui.ctx().request_repaint();
+        }
+    });
+
+    // dragging a cue along the axis changes its pre-wait
+    for (index, delta) in drags {
+        let cue = &mut state.project.cue_lists[l][index];
+        cue.set_pre_wait((cue.get_pre_wait() + CueTime::from_secs_f32(delta)).max(CueTime::ZERO));
+    }
+}
@@ -0,0 +1,172 @@
+use log::error;
+use rfd::FileDialog;
+
+use super::{open_project, CueballApp};
+use crate::Project;
+
+// actions that would throw away the current project
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingAction {
+    New,
+    Open,
+    Quit,
+}
+
+#[derive(Debug, Default)]
+pub struct ModifiedTracker {
+    // fingerprint of the project as of the last save or open
+    saved: Option<u64>,
+    modified: bool,
+    // there was input last frame, so the project may have been edited
+    recheck: bool,
+    // action waiting on the Save / Discard / Cancel dialog
+    pending: Option<PendingAction>,
+    // set once the operator has agreed to quit, so that closing the window
+    // isn't cancelled again
+    quitting: bool,
+    title: String,
+}
+
+impl ModifiedTracker {
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    // the project now matches what is on disk
+    pub fn mark_saved(&mut self, project: &Project) {
+        self.saved = project.fingerprint().ok();
+        self.modified = false;
+    }
+
+    // the project differs from what is on disk, whether or not it looks it
+    pub fn mark_unsaved(&mut self) {
+        self.saved = None;
+        self.modified = true;
+    }
+
+    // compare the project against the last save. this catches every kind of
+    // edit without each one having to report itself, and since edits only
+    // come from input it is only done on the frame after some
+    pub fn update(&mut self, project: &Project, had_input: bool) {
+        if self.recheck {
+            self.modified = match (self.saved, project.fingerprint()) {
+                (Some(saved), Ok(current)) => saved != current,
+                _ => true,
+            };
+        }
+        self.recheck = had_input;
+    }
+}
+
+impl CueballApp {
+    // run `action`, asking first if it would lose unsaved changes
+    pub(super) fn request(&mut self, ctx: &egui::Context, action: PendingAction) {
+        if self.state.modified.is_modified() {
+            self.state.modified.pending = Some(action);
+        } else {
+            self.perform(ctx, action);
+        }
+    }
+
+    fn perform(&mut self, ctx: &egui::Context, action: PendingAction) {
+        match action {
+            PendingAction::New => self.load_project(Project::default()),
+            PendingAction::Open => match FileDialog::new()
+                .add_filter("cueball", &["cueball", "cbp"])
+                .pick_file()
+            {
+                None => {
+                    error!("No file path selected!");
+                }
+                Some(path) => match open_project(path) {
+                    Ok(new_project) => {
+                        self.load_project(new_project);
+                    }
+                    Err(err) => {
+                        error!("Failed to open project: {}", err);
+                    }
+                },
+            },
+            PendingAction::Quit => {
+                self.state.modified.quitting = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+
+    pub(super) fn track_modified(&mut self, ctx: &egui::Context) {
+        let had_input = ctx.input(|i| !i.events.is_empty());
+        self.state.modified.update(&self.state.project, had_input);
+        if had_input {
+            // make sure there is a next frame to look for the edit in
+            ctx.request_repaint();
+        }
+
+        let title = format!(
+            "{}{} - cueball",
+            self.state.project.name,
+            if self.state.modified.is_modified() {
+                "*"
+            } else {
+                ""
+            }
+        );
+        if title != self.state.modified.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.state.modified.title = title;
+        }
+
+        // closing the window counts as quitting
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.state.modified.quitting
+            && self.state.modified.is_modified()
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.state.modified.pending = Some(PendingAction::Quit);
+        }
+    }
+
+    pub(super) fn unsaved_changes_ui(&mut self, ctx: &egui::Context) {
+        let action = match self.state.modified.pending {
+            Some(a) => a,
+            None => return,
+        };
+
+        let mut choice = None;
+        egui::Modal::new(egui::Id::new("unsaved_changes")).show(ctx, |ui| {
+            ui.heading("Unsaved changes");
+            ui.label(format!(
+                "Do you want to save the changes to {}?",
+                self.state.project.name
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    choice = Some(true);
+                }
+                if ui.button("Discard").clicked() {
+                    choice = Some(false);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.state.modified.pending = None;
+                }
+            });
+        });
+
+        match choice {
+            Some(true) => {
+                self.save(false);
+                // only carry on if the save actually went through
+                if !self.state.modified.is_modified() {
+                    self.state.modified.pending = None;
+                    self.perform(ctx, action);
+                }
+            }
+            Some(false) => {
+                self.state.autosave.remove_recovery(&self.state.project);
+                self.state.modified.pending = None;
+                self.perform(ctx, action);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use clap::{Args, Parser};
+use log::{error, LevelFilter};
+use mlua::prelude::*;
+
+use crate::{audio, project::ProblemSeverity, Project};
+
+// arguments shared by every cueball binary
+#[derive(Args, Debug)]
+pub struct CommonArgs {
+    /// Project file to open
+    pub project: Option<PathBuf>,
+
+    /// Audio output device to use instead of the system default
+    #[arg(short, long)]
+    pub device: Option<String>,
+
+    /// List the available audio output devices and exit
+    #[arg(long)]
+    pub list_devices: bool,
+
+    /// Log level, overriding RUST_LOG
+    #[arg(short, long)]
+    pub log_level: Option<LevelFilter>,
+
+    /// Validate the project file and exit, with a non-zero status if any
+    /// problems were found
+    #[arg(long, requires = "project")]
+    pub check: bool,
+}
+
+/// An open-source theatrical cue player
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct GuiArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Launch with editing locked
+    #[arg(long)]
+    pub show_mode: bool,
+}
+
+/// Run cueball shows and Lua from the terminal
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct CliArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Lua script to execute on startup
+    #[arg(short, long)]
+    pub script: Option<PathBuf>,
+}
+
+impl CommonArgs {
+    pub fn init_logger(&self, default_level: LevelFilter) {
+        let mut builder = env_logger::builder();
+        builder.filter_level(default_level).parse_default_env();
+        if let Some(level) = self.log_level {
+            builder.filter_level(level);
+        }
+        builder.init();
+    }
+
+    // handle the arguments that exit immediately, returning the exit code if
+    // the program should stop here
+    pub fn run_early_exits(&self) -> Option<i32> {
+        if self.list_devices {
+            return match audio::output_device_names() {
+                Ok(names) => {
+                    for name in names {
+                        println!("{}", name);
+                    }
+                    Some(0)
+                }
+                Err(err) => {
+                    error!("Could not list output devices: {}", err);
+                    Some(1)
+                }
+            };
+        }
+
+        if self.check {
+            if let Some(path) = &self.project {
+                // open the output so the check can tell if it's available
+                if let Err(err) = self.init_audio() {
+                    error!("Could not initialize audio output: {}", err);
+                }
+                return Some(if check_project(path) { 0 } else { 1 });
+            }
+        }
+
+        None
+    }
+
+    pub fn init_audio(&self) -> Result<(), anyhow::Error> {
+        audio::init(audio::Backend::Device(self.device.clone()))
+    }
+}
+
+// print every problem with the project file at the given path, returning
+// whether it is ready to run. warnings are printed but don't fail the check
+pub fn check_project(path: &Path) -> bool {
+    let project = match Project::read(path) {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}: error: could not read project: {}", path.display(), err);
+            return false;
+        }
+    };
+
+    let problems = project.check();
+    for problem in &problems {
+        let severity = match problem.severity() {
+            ProblemSeverity::Warning => "warning",
+            ProblemSeverity::Error => "error",
+        };
+        println!("{}: {}: {}", path.display(), severity, problem);
+    }
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+    }
+
+    !problems
+        .iter()
+        .any(|p| p.severity() == ProblemSeverity::Error)
+}
+
+pub fn run_script(lua: &Lua, path: &Path) -> Result<(), anyhow::Error> {
+    let script = std::fs::read_to_string(path)?;
+    lua.load(script)
+        .set_name(path.display().to_string())
+        .exec()
+        .map_err(|err| anyhow!("{}", err))?;
+    Ok(())
+}
@@ -0,0 +1,375 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, LazyLock, Mutex, OnceLock},
+    thread,
+    time::SystemTime,
+};
+
+use anyhow::anyhow;
+use log::{debug, warn};
+use rodio::{Decoder, Source};
+use serde::{Deserialize, Serialize};
+
+use super::{integrated_loudness, LoudnessMeter, PeakBuilder, Peaks};
+use crate::{cues::CueTime, project::backup};
+
+// bumped whenever analysis finds out more, so older cache files get redone
+const INFO_VERSION: u32 = 3;
+// samples decoded between progress updates
+const PROGRESS_INTERVAL: usize = 1 << 16;
+// most files analyzed at once
+const MAX_WORKERS: usize = 4;
+
+// what analysis found out about a media file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MediaInfo {
+    version: u32,
+    pub channels: u16,
+    pub rate: u32,
+    pub frames: u64,
+    // highest absolute sample value
+    pub peak: f32,
+    // root mean square of every sample, across all channels
+    pub rms: f32,
+    // integrated loudness in LUFS, if anything was loud enough to measure
+    pub loudness: Option<f32>,
+    // highest level between samples as well as at them
+    pub true_peak: f32,
+    // K-weighted energy of each 100 ms, for the loudness of part of the file
+    pub loudness_hops: Vec<f32>,
+}
+
+impl MediaInfo {
+    // in seconds
+    pub fn length(&self) -> f32 {
+        self.frames as f32 / self.rate as f32
+    }
+
+    // integrated loudness in LUFS between two points in the file, counting
+    // the 100 ms hops that fall wholly inside them, or those that overlap
+    // them if none do
+    pub fn loudness_between(&self, start: CueTime, end: CueTime) -> Option<f32> {
+        let hop = (self.rate as i64 / 10).max(1);
+        let len = self.loudness_hops.len() as i64;
+        let (start, end) = (start.to_frames(self.rate), end.to_frames(self.rate));
+        let (mut from, mut to) = ((start + hop - 1).div_euclid(hop), end.div_euclid(hop));
+        if to <= from {
+            (from, to) = (start.div_euclid(hop), (end + hop - 1).div_euclid(hop));
+        }
+        let (from, to) = (from.clamp(0, len) as usize, to.clamp(0, len) as usize);
+        let hops: Vec<f64> = self.loudness_hops[from..to.max(from)]
+            .iter()
+            .map(|&e| e as f64)
+            .collect();
+        integrated_loudness(&hops)
+    }
+}
+
+#[derive(Debug)]
+pub struct Analysis {
+    pub info: MediaInfo,
+    pub peaks: Peaks,
+}
+
+#[derive(Debug, Clone)]
+pub enum MediaStatus {
+    Queued,
+    // how far through, if known
+    Analyzing(Option<f32>),
+    Ready(Arc<Analysis>),
+    Failed(String),
+}
+
+impl MediaStatus {
+    pub fn is_loading(&self) -> bool {
+        matches!(self, MediaStatus::Queued | MediaStatus::Analyzing(_))
+    }
+
+    pub fn progress(&self) -> Option<f32> {
+        match self {
+            MediaStatus::Analyzing(done) => *done,
+            _ => None,
+        }
+    }
+}
+
+// decode a whole file, reporting how far through it is from 0 to 1 along the
+// way, if that can be known
+pub fn analyze_file(path: &Path, mut progress: impl FnMut(f32)) -> Result<Analysis, anyhow::Error> {
+    let source = Decoder::new(BufReader::new(File::open(path)?))?;
+    let channels = source.channels();
+    let rate = source.sample_rate();
+    if channels == 0 || rate == 0 {
+        return Err(anyhow!("No audio in {}", path.display()));
+    }
+    let expected = source
+        .total_duration()
+        .map(|d| d.as_secs_f64() * rate as f64 * channels as f64);
+
+    let mut peaks = PeakBuilder::new(channels as usize, rate);
+    let mut loudness = LoudnessMeter::new(channels as usize, rate);
+    let (mut count, mut peak, mut squares) = (0, 0f32, 0f64);
+    for sample in source {
+        peaks.push(sample);
+        loudness.push(sample);
+        peak = peak.max(sample.abs());
+        squares += (sample as f64).powi(2);
+        count += 1;
+        if count % PROGRESS_INTERVAL == 0 {
+            if let Some(expected) = expected {
+                progress((count as f64 / expected).min(1.) as f32);
+            }
+        }
+    }
+
+    Ok(Analysis {
+        info: MediaInfo {
+            version: INFO_VERSION,
+            channels,
+            rate,
+            frames: (count / channels as usize) as u64,
+            peak,
+            rms: (squares / count.max(1) as f64).sqrt() as f32,
+            loudness: loudness.integrated(),
+            true_peak: loudness.true_peak(),
+            loudness_hops: loudness.hops().iter().map(|&e| e as f32).collect(),
+        },
+        peaks: peaks.finish(),
+    })
+}
+
+static CACHE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// keep analysis results in `dir` rather than the user's cache, for the rest
+// of the process
+pub fn set_analysis_cache_dir(dir: impl Into<PathBuf>) {
+    if let Ok(mut cache_dir) = CACHE_DIR.lock() {
+        *cache_dir = Some(dir.into());
+    }
+}
+
+// where analysis results are kept between sessions
+pub fn analysis_cache_dir() -> PathBuf {
+    if let Some(dir) = CACHE_DIR.lock().ok().and_then(|dir| dir.clone()) {
+        return dir;
+    }
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cueball")
+        .join("media")
+}
+
+// cache files for a media file as it is now, without an extension. editing
+// or replacing the media changes its modification time and size, so stale
+// results are never found
+fn cache_stem(path: &Path) -> Result<PathBuf, anyhow::Error> {
+    let meta = std::fs::metadata(path)?;
+    let mut hasher = DefaultHasher::new();
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .hash(&mut hasher);
+    meta.modified()?.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    Ok(analysis_cache_dir().join(format!("{:016x}", hasher.finish())))
+}
+
+fn load_cached(stem: &Path) -> Result<Analysis, anyhow::Error> {
+    let info: MediaInfo = serde_json::from_slice(&std::fs::read(stem.with_extension("json"))?)?;
+    if info.version != INFO_VERSION {
+        return Err(anyhow!("Analysis is out of date"));
+    }
+    let peaks = Peaks::from_bytes(&std::fs::read(stem.with_extension("peaks"))?)?;
+    Ok(Analysis { info, peaks })
+}
+
+fn store_cached(stem: &Path, analysis: &Analysis) -> Result<(), anyhow::Error> {
+    std::fs::create_dir_all(analysis_cache_dir())?;
+    backup::write_atomic(&stem.with_extension("peaks"), &analysis.peaks.to_bytes())?;
+    backup::write_atomic(
+        &stem.with_extension("json"),
+        serde_json::to_string(&analysis.info)?.as_bytes(),
+    )
+}
+
+// analysis of `path` from the cache directory, or worked out and stored there
+fn load_or_analyze(path: &Path, progress: impl FnMut(f32)) -> Result<Analysis, anyhow::Error> {
+    let stem = cache_stem(path)?;
+    match load_cached(&stem) {
+        Ok(analysis) => return Ok(analysis),
+        Err(err) => debug!("No cached analysis of {}: {}", path.display(), err),
+    }
+
+    let analysis = analyze_file(path, progress)?;
+    if let Err(err) = store_cached(&stem, &analysis) {
+        warn!("Could not cache analysis of {}: {}", path.display(), err);
+    }
+    Ok(analysis)
+}
+
+struct Job {
+    path: PathBuf,
+    status: Arc<Mutex<MediaStatus>>,
+}
+
+struct Entry {
+    modified: Option<SystemTime>,
+    status: Arc<Mutex<MediaStatus>>,
+}
+
+// every file analysis has been asked for this session, and the files still
+// waiting on a worker
+#[derive(Default)]
+struct Library {
+    entries: Mutex<HashMap<PathBuf, Entry>>,
+    queue: Mutex<VecDeque<Job>>,
+    queued: Condvar,
+    // notified, with `entries` held, whenever an analysis finishes
+    finished: Condvar,
+    workers: OnceLock<()>,
+}
+
+static LIBRARY: LazyLock<Library> = LazyLock::new(Library::default);
+
+fn set_status(status: &Mutex<MediaStatus>, to: MediaStatus) {
+    if let Ok(mut status) = status.lock() {
+        *status = to;
+    }
+}
+
+impl Library {
+    fn start_workers(&'static self) {
+        self.workers.get_or_init(|| {
+            let count = thread::available_parallelism().map_or(1, |n| n.get().min(MAX_WORKERS));
+            for i in 0..count {
+                let spawned = thread::Builder::new()
+                    .name(format!("media-analysis-{}", i))
+                    .spawn(|| LIBRARY.work());
+                if let Err(err) = spawned {
+                    warn!("Could not start media analysis worker: {}", err);
+                }
+            }
+        });
+    }
+
+    fn work(&self) {
+        loop {
+            let job = {
+                let Ok(mut queue) = self.queue.lock() else {
+                    return;
+                };
+                loop {
+                    match queue.pop_front() {
+                        Some(job) => break job,
+                        None => match self.queued.wait(queue) {
+                            Ok(q) => queue = q,
+                            Err(_) => return,
+                        },
+                    }
+                }
+            };
+
+            self.run(&job.path, &job.status);
+        }
+    }
+
+    // the status of an up to date analysis of `path`, or a new queued one
+    // and true if it needs working out
+    fn entry(&self, path: &Path) -> Option<(Arc<Mutex<MediaStatus>>, bool)> {
+        let modified = modified(path);
+        let mut entries = self.entries.lock().ok()?;
+        if let Some(entry) = entries.get(path).filter(|e| e.modified == modified) {
+            return Some((entry.status.clone(), false));
+        }
+        let status = Arc::new(Mutex::new(MediaStatus::Queued));
+        entries.insert(
+            path.to_path_buf(),
+            Entry {
+                modified,
+                status: status.clone(),
+            },
+        );
+        Some((status, true))
+    }
+
+    // analyze `path` on this thread, unless it's already been taken by
+    // another. the result is cached on disk as well as kept in the library
+    fn run(&self, path: &Path, status: &Mutex<MediaStatus>) {
+        let claimed = status.lock().is_ok_and(|mut status| {
+            let queued = matches!(*status, MediaStatus::Queued);
+            if queued {
+                *status = MediaStatus::Analyzing(None);
+            }
+            queued
+        });
+        if !claimed {
+            return;
+        }
+
+        let result = load_or_analyze(path, |done| {
+            set_status(status, MediaStatus::Analyzing(Some(done)));
+        });
+        let entries = self.entries.lock();
+        set_status(
+            status,
+            match result {
+                Ok(analysis) => MediaStatus::Ready(Arc::new(analysis)),
+                Err(err) => MediaStatus::Failed(err.to_string()),
+            },
+        );
+        drop(entries);
+        self.finished.notify_all();
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// how analysis of `path` is going, queueing it for a background worker if it
+// hasn't been analyzed yet or has changed since
+pub fn analyze(path: &Path) -> MediaStatus {
+    let Some((status, new)) = LIBRARY.entry(path) else {
+        return MediaStatus::Failed("Media analysis is unavailable".to_string());
+    };
+    if !new {
+        return match status.lock() {
+            Ok(status) => status.clone(),
+            Err(_) => MediaStatus::Failed("Media analysis is unavailable".to_string()),
+        };
+    }
+
+    LIBRARY.start_workers();
+    if let Ok(mut queue) = LIBRARY.queue.lock() {
+        queue.push_back(Job {
+            path: path.to_path_buf(),
+            status,
+        });
+        LIBRARY.queued.notify_one();
+    }
+    MediaStatus::Queued
+}
+
+// analysis of `path`, worked out on this thread if no worker has started on
+// it yet, or waited for if one has
+pub fn analyze_now(path: &Path) -> Result<Arc<Analysis>, anyhow::Error> {
+    let unavailable = || anyhow!("Media analysis is unavailable");
+    let (status, _) = LIBRARY.entry(path).ok_or_else(unavailable)?;
+    LIBRARY.run(path, &status);
+
+    let mut entries = LIBRARY.entries.lock().map_err(|_| unavailable())?;
+    loop {
+        match status.lock().map_err(|_| unavailable())?.clone() {
+            MediaStatus::Ready(analysis) => return Ok(analysis),
+            MediaStatus::Failed(err) => return Err(anyhow!(err)),
+            MediaStatus::Queued | MediaStatus::Analyzing(_) => {}
+        }
+        entries = LIBRARY.finished.wait(entries).map_err(|_| unavailable())?;
+    }
+}
@@ -0,0 +1,617 @@
+use std::{
+    f32::consts::PI,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rodio::{source::SeekError, ChannelCount, Sample, SampleRate, Source};
+use serde::{Deserialize, Serialize};
+
+use super::{db_to_gain, gain_to_db};
+use crate::cues::CueTime;
+
+// how many samples go by between checks for an edited chain, so that the
+// audio thread isn't taking a lock on every sample
+const CHECK_INTERVAL: u32 = 256;
+// frames between settings updates while a ramp is running, so filters
+// aren't worked out again on every sample
+const RAMP_STEP: u64 = 32;
+// longest delay line. delay lines are allocated at this length up front so
+// that changing the time never allocates
+pub const MAX_DELAY: CueTime = CueTime::from_millis(1000);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Effect {
+    // a parametric EQ band, boosting or cutting around `freq`
+    Eq {
+        freq: f32,
+        gain: f32,
+        q: f32,
+    },
+    HighPass {
+        freq: f32,
+        q: f32,
+    },
+    LowPass {
+        freq: f32,
+        q: f32,
+    },
+    // levels, gains and `threshold` in dB, times in seconds
+    Compressor {
+        threshold: f32,
+        ratio: f32,
+        attack: f32,
+        release: f32,
+        makeup: f32,
+    },
+    // keeps peaks under `ceiling` dB
+    Limiter {
+        ceiling: f32,
+        release: f32,
+    },
+    // for lining up speakers at different distances
+    Delay {
+        time: CueTime,
+    },
+}
+
+impl Effect {
+    // every kind of effect, with sensible starting settings
+    pub const ITER: [Effect; 6] = [
+        Effect::Eq {
+            freq: 1000.,
+            gain: 0.,
+            q: 1.,
+        },
+        Effect::HighPass {
+            freq: 80.,
+            q: 0.707,
+        },
+        Effect::LowPass {
+            freq: 12000.,
+            q: 0.707,
+        },
+        Effect::Compressor {
+            threshold: -18.,
+            ratio: 4.,
+            attack: 0.01,
+            release: 0.1,
+            makeup: 0.,
+        },
+        Effect::Limiter {
+            ceiling: -1.,
+            release: 0.05,
+        },
+        Effect::Delay {
+            time: CueTime::ZERO,
+        },
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::Eq { .. } => "EQ",
+            Effect::HighPass { .. } => "High pass",
+            Effect::LowPass { .. } => "Low pass",
+            Effect::Compressor { .. } => "Compressor",
+            Effect::Limiter { .. } => "Limiter",
+            Effect::Delay { .. } => "Delay",
+        }
+    }
+
+    // settings `t` of the way from these to `to`, with `t` from 0 to 1.
+    // frequencies move evenly in pitch and everything else in a straight
+    // line. different kinds of effect can't be mixed, so they jump to `to`
+    pub fn lerp(&self, to: &Effect, t: f32) -> Effect {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let pitch = |a: f32, b: f32| match a > 0. && b > 0. {
+            true => a * (b / a).powf(t),
+            false => mix(a, b),
+        };
+        match (*self, *to) {
+            (
+                Effect::Eq {
+                    freq: f0,
+                    gain: g0,
+                    q: q0,
+                },
+                Effect::Eq { freq, gain, q },
+            ) => Effect::Eq {
+                freq: pitch(f0, freq),
+                gain: mix(g0, gain),
+                q: mix(q0, q),
+            },
+            (Effect::HighPass { freq: f0, q: q0 }, Effect::HighPass { freq, q }) => {
+                Effect::HighPass {
+                    freq: pitch(f0, freq),
+                    q: mix(q0, q),
+                }
+            }
+            (Effect::LowPass { freq: f0, q: q0 }, Effect::LowPass { freq, q }) => Effect::LowPass {
+                freq: pitch(f0, freq),
+                q: mix(q0, q),
+            },
+            (
+                Effect::Compressor {
+                    threshold: t0,
+                    ratio: r0,
+                    attack: a0,
+                    release: rel0,
+                    makeup: m0,
+                },
+                Effect::Compressor {
+                    threshold,
+                    ratio,
+                    attack,
+                    release,
+                    makeup,
+                },
+            ) => Effect::Compressor {
+                threshold: mix(t0, threshold),
+                ratio: mix(r0, ratio),
+                attack: mix(a0, attack),
+                release: mix(rel0, release),
+                makeup: mix(m0, makeup),
+            },
+            (
+                Effect::Limiter {
+                    ceiling: c0,
+                    release: r0,
+                },
+                Effect::Limiter { ceiling, release },
+            ) => Effect::Limiter {
+                ceiling: mix(c0, ceiling),
+                release: mix(r0, release),
+            },
+            (Effect::Delay { time: t0 }, Effect::Delay { time }) => Effect::Delay {
+                time: t0 + (time - t0).mul_f64(t as f64),
+            },
+            (_, to) => to,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct EffectSlot {
+    pub effect: Effect,
+    // passes audio through untouched, keeping the settings
+    #[serde(default)]
+    pub bypass: bool,
+}
+
+impl EffectSlot {
+    pub fn new(effect: Effect) -> Self {
+        Self {
+            effect,
+            bypass: false,
+        }
+    }
+}
+
+// effects applied one after another, first first
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct EffectChain(pub Vec<EffectSlot>);
+
+// effects that run on the same kind of processor, so one can carry on from
+// the other keeping its filter or delay state
+fn same_processor(a: &Effect, b: &Effect) -> bool {
+    let kind = |effect: &Effect| match effect {
+        Effect::Eq { .. } | Effect::HighPass { .. } | Effect::LowPass { .. } => 0,
+        Effect::Compressor { .. } | Effect::Limiter { .. } => 1,
+        Effect::Delay { .. } => 2,
+    };
+    kind(a) == kind(b)
+}
+
+// settings of a chain moving towards those of an edit
+#[derive(Debug, Default)]
+struct Ramp {
+    // settings of every slot when the ramp started, filled in by the audio
+    // thread as it takes the edit on
+    from: Vec<Effect>,
+    to: Vec<Effect>,
+    // in frames
+    length: u64,
+    done: u64,
+}
+
+// a new chain made ready off the audio thread, so taking it on there never
+// allocates or frees anything
+#[derive(Debug)]
+struct Edit {
+    chain: EffectChain,
+    // a processor for every slot of `chain`. slots that can carry on with the
+    // processor they already have are left Kept, for it to be moved across
+    slots: Vec<(EffectSlot, Processor)>,
+    ramp: Ramp,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    // channels and sample rate of the source the chain is running on, once
+    // there is one
+    format: Option<(usize, f32)>,
+    // the chain the audio thread is running
+    playing: EffectChain,
+    pending: Option<Edit>,
+    // the last edit taken on, now holding everything it replaced, waiting to
+    // be freed by the next edit
+    retired: Option<Edit>,
+}
+
+// lets a chain be edited while it is playing
+#[derive(Clone, Debug, Default)]
+pub struct EffectsHandle(Arc<Mutex<Shared>>);
+
+impl EffectsHandle {
+    pub fn set(&self, chain: &EffectChain) {
+        self.ramp_to(chain, CueTime::ZERO);
+    }
+
+    // move the settings of every slot that keeps the same kind of effect to
+    // those of `chain` over `length`. anything else changes straight away
+    pub fn ramp_to(&self, chain: &EffectChain, length: CueTime) {
+        let Ok(mut shared) = self.0.lock() else {
+            return;
+        };
+        // nothing is playing yet, and it will start with the new chain
+        let Some((channels, rate)) = shared.format else {
+            return;
+        };
+        let garbage = (shared.retired.take(), shared.pending.take());
+        let slots = chain
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                let playing = shared.playing.0.get(i);
+                let processor = match playing {
+                    Some(playing) if same_processor(&playing.effect, &slot.effect) => {
+                        Processor::Kept
+                    }
+                    _ => Processor::new(&slot.effect, channels, rate),
+                };
+                (*slot, processor)
+            })
+            .collect();
+        let to: Vec<Effect> = chain.0.iter().map(|slot| slot.effect).collect();
+        let ramp = Ramp {
+            from: to.clone(),
+            to,
+            length: length.to_frames(rate as u32).max(0) as u64,
+            done: 0,
+        };
+        shared.pending = Some(Edit {
+            chain: chain.clone(),
+            slots,
+            ramp,
+        });
+        drop(shared);
+        drop(garbage);
+    }
+
+    // the chain is starting to run on a source
+    fn start(&self, chain: &EffectChain, channels: usize, rate: f32) {
+        if let Ok(mut shared) = self.0.lock() {
+            *shared = Shared {
+                format: Some((channels, rate)),
+                playing: chain.clone(),
+                ..Shared::default()
+            };
+        }
+    }
+}
+
+// second order IIR filter, in transposed direct form II, with coefficients
+// from the RBJ audio EQ cookbook
+#[derive(Clone, Copy, Debug, Default)]
+struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    z: [f32; 2],
+}
+
+impl Biquad {
+    fn set(&mut self, effect: &Effect, rate: f32) {
+        let (freq, q) = match *effect {
+            Effect::Eq { freq, q, .. }
+            | Effect::HighPass { freq, q }
+            | Effect::LowPass { freq, q } => (freq, q),
+            _ => return,
+        };
+        let w0 = 2. * PI * freq.clamp(10., rate * 0.49) / rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2. * q.max(0.01));
+
+        let (b, a) = match *effect {
+            Effect::Eq { gain, .. } => {
+                let a = 10f32.powf(gain / 40.);
+                (
+                    [1. + alpha * a, -2. * cos, 1. - alpha * a],
+                    [1. + alpha / a, -2. * cos, 1. - alpha / a],
+                )
+            }
+            Effect::HighPass { .. } => (
+                [(1. + cos) / 2., -(1. + cos), (1. + cos) / 2.],
+                [1. + alpha, -2. * cos, 1. - alpha],
+            ),
+            _ => (
+                [(1. - cos) / 2., 1. - cos, (1. - cos) / 2.],
+                [1. + alpha, -2. * cos, 1. - alpha],
+            ),
+        };
+        self.b = b.map(|b| b / a[0]);
+        self.a = [a[1] / a[0], a[2] / a[0]];
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+// one slot of a chain while it is playing, with separate state for each
+// channel
+#[derive(Debug)]
+enum Processor {
+    Filter(Vec<Biquad>),
+    // gain reduction of each channel, in dB
+    Dynamics(Vec<f32>),
+    Delay {
+        lines: Vec<Vec<f32>>,
+        write: usize,
+        length: usize,
+    },
+    // stands in for a processor being carried over from the last chain
+    Kept,
+}
+
+impl Processor {
+    fn new(effect: &Effect, channels: usize, rate: f32) -> Self {
+        let mut processor = match effect {
+            Effect::Eq { .. } | Effect::HighPass { .. } | Effect::LowPass { .. } => {
+                Processor::Filter(vec![Biquad::default(); channels])
+            }
+            Effect::Compressor { .. } | Effect::Limiter { .. } => {
+                Processor::Dynamics(vec![0.; channels])
+            }
+            Effect::Delay { .. } => Processor::Delay {
+                lines: vec![vec![0.; MAX_DELAY.to_frames(rate as u32) as usize + 1]; channels],
+                write: 0,
+                length: 0,
+            },
+        };
+        processor.update(effect, rate);
+        processor
+    }
+
+    fn update(&mut self, effect: &Effect, rate: f32) {
+        match self {
+            Processor::Filter(filters) => {
+                for filter in filters {
+                    filter.set(effect, rate);
+                }
+            }
+            Processor::Dynamics(_) | Processor::Kept => {}
+            Processor::Delay { lines, length, .. } => {
+                if let Effect::Delay { time } = effect {
+                    let max = lines.first().map_or(1, Vec::len) - 1;
+                    *length = (time.to_frames(rate as u32).max(0) as usize).min(max);
+                }
+            }
+        }
+    }
+
+    fn process(&mut self, effect: &Effect, channel: usize, rate: f32, x: f32) -> f32 {
+        match self {
+            Processor::Filter(filters) => filters[channel].process(x),
+            Processor::Dynamics(reduction) => {
+                let (threshold, ratio, attack, release, makeup) = match *effect {
+                    Effect::Compressor {
+                        threshold,
+                        ratio,
+                        attack,
+                        release,
+                        makeup,
+                    } => (threshold, ratio.max(1.), attack, release, makeup),
+                    Effect::Limiter { ceiling, release } => {
+                        (ceiling, f32::INFINITY, 0., release, 0.)
+                    }
+                    _ => return x,
+                };
+                let over = (gain_to_db(x.abs()) - threshold).max(0.);
+                let target = over * (1. - 1. / ratio);
+                let time = if target > reduction[channel] {
+                    attack
+                } else {
+                    release
+                };
+                let reduction = &mut reduction[channel];
+                *reduction = if time <= 0. {
+                    target
+                } else {
+                    target + (*reduction - target) * (-1. / (time * rate)).exp()
+                };
+                x * db_to_gain(makeup - *reduction)
+            }
+            Processor::Delay {
+                lines,
+                write,
+                length,
+            } => {
+                let line = &mut lines[channel];
+                let size = line.len();
+                line[*write] = x;
+                let y = line[(*write + size - *length) % size];
+                // the last channel of a frame moves every line on
+                if channel + 1 == lines.len() {
+                    *write = (*write + 1) % size;
+                }
+                y
+            }
+            Processor::Kept => x,
+        }
+    }
+}
+
+// a chain while it is playing
+#[derive(Debug)]
+pub struct ChainProcessor {
+    channels: usize,
+    rate: f32,
+    // with the settings in use right now, which differ from the chain's
+    // while it is ramping
+    slots: Vec<(EffectSlot, Processor)>,
+    ramp: Ramp,
+}
+
+impl ChainProcessor {
+    pub fn new(chain: &EffectChain, channels: usize, rate: u32) -> Self {
+        let (channels, rate) = (channels.max(1), rate as f32);
+        Self {
+            channels,
+            rate,
+            slots: chain
+                .0
+                .iter()
+                .map(|slot| (*slot, Processor::new(&slot.effect, channels, rate)))
+                .collect(),
+            ramp: Ramp::default(),
+        }
+    }
+
+    // swap in the slots and ramp of an edit, keeping filter and delay state
+    // wherever the kind of effect in a slot hasn't changed. the edit is left
+    // holding the old ones
+    fn take_on(&mut self, edit: &mut Edit) {
+        let ramp = &mut edit.ramp;
+        for (i, (slot, processor)) in edit.slots.iter_mut().enumerate() {
+            if let Processor::Kept = processor {
+                if let Some((current_slot, current)) = self.slots.get_mut(i) {
+                    std::mem::swap(processor, current);
+                    // ramps carry on from wherever the settings are now
+                    if ramp.length > 0 {
+                        ramp.from[i] = current_slot.effect;
+                        slot.effect = current_slot.effect.lerp(&slot.effect, 0.);
+                    }
+                }
+                processor.update(&slot.effect, self.rate);
+            }
+        }
+        std::mem::swap(&mut self.slots, &mut edit.slots);
+        std::mem::swap(&mut self.ramp, ramp);
+    }
+
+    // move ramping settings on by a frame
+    fn step_ramp(&mut self) {
+        let ramp = &mut self.ramp;
+        if ramp.done >= ramp.length {
+            return;
+        }
+        ramp.done += 1;
+        if !ramp.done.is_multiple_of(RAMP_STEP) && ramp.done != ramp.length {
+            return;
+        }
+        let t = ramp.done as f32 / ramp.length as f32;
+        for (i, (slot, processor)) in self.slots.iter_mut().enumerate() {
+            if let (Some(from), Some(to)) = (ramp.from.get(i), ramp.to.get(i)) {
+                slot.effect = from.lerp(to, t);
+                processor.update(&slot.effect, self.rate);
+            }
+        }
+    }
+
+    // run a sample of `channel` through every slot that isn't bypassed
+    pub fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        if channel == 0 {
+            self.step_ramp();
+        }
+        let rate = self.rate;
+        self.slots
+            .iter_mut()
+            .filter(|(slot, _)| !slot.bypass)
+            .fold(sample, |x, (slot, processor)| {
+                processor.process(&slot.effect, channel, rate, x)
+            })
+    }
+}
+
+// a source run through an effect chain, which can be edited through an
+// EffectsHandle while it plays
+pub struct Processed<S> {
+    inner: S,
+    handle: EffectsHandle,
+    chain: ChainProcessor,
+
+    // channel of the next sample
+    channel: usize,
+    until_check: u32,
+}
+
+impl<S: Source> Processed<S> {
+    pub fn new(inner: S, chain: &EffectChain, handle: EffectsHandle) -> Self {
+        let processor = ChainProcessor::new(chain, inner.channels() as usize, inner.sample_rate());
+        handle.start(chain, processor.channels, processor.rate);
+        Self {
+            chain: processor,
+            inner,
+            handle,
+            channel: 0,
+            until_check: CHECK_INTERVAL,
+        }
+    }
+
+    // runs on the audio thread, so it only moves things around. what the
+    // edit replaced goes back through the handle to be freed
+    fn check_for_edit(&mut self) {
+        let Ok(mut shared) = self.handle.0.try_lock() else {
+            return;
+        };
+        if shared.retired.is_some() {
+            return;
+        }
+        if let Some(mut edit) = shared.pending.take() {
+            self.chain.take_on(&mut edit);
+            std::mem::swap(&mut shared.playing, &mut edit.chain);
+            shared.retired = Some(edit);
+        }
+    }
+}
+
+impl<S: Source> Iterator for Processed<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        // only between frames, so every channel of a frame goes through the
+        // same chain
+        self.until_check = self.until_check.saturating_sub(1);
+        if self.until_check == 0 && self.channel == 0 {
+            self.until_check = CHECK_INTERVAL;
+            self.check_for_edit();
+        }
+
+        let sample = self.inner.next()?;
+        let out = self.chain.process(self.channel, sample);
+        self.channel = (self.channel + 1) % self.chain.channels;
+        Some(out)
+    }
+}
+
+impl<S: Source> Source for Processed<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    time::Duration,
+};
+
+use rodio::{source::SeekError, ChannelCount, Sample, SampleRate, Source};
+use serde::{Deserialize, Serialize};
+
+use crate::cues::CueTime;
+
+// shortest ramp at a trimmed in or out point, so that cutting into the
+// middle of a waveform doesn't click
+pub const DECLICK_LENGTH: CueTime = CueTime::from_millis(3);
+// level an exponential fade starts from, in dB
+const EXPONENTIAL_FLOOR: f32 = -60.;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FadeCurve {
+    Linear,
+    // keeps the sum of two crossfading cues at a constant power
+    #[default]
+    EqualPower,
+    // linear in dB, which sounds even to the ear
+    Exponential,
+    // slow at both ends
+    SCurve,
+}
+
+impl FadeCurve {
+    pub const ITER: [(FadeCurve, &'static str); 4] = [
+        (FadeCurve::Linear, "Linear"),
+        (FadeCurve::EqualPower, "Equal power"),
+        (FadeCurve::Exponential, "Exponential"),
+        (FadeCurve::SCurve, "S-curve"),
+    ];
+
+    // gain `t` of the way through a fade in, from 0 to 1. fades out run the
+    // same curve backwards
+    pub fn gain(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EqualPower => (t * FRAC_PI_2).sin(),
+            FadeCurve::Exponential if t == 0. => 0.,
+            FadeCurve::Exponential => 10f32.powf((1. - t) * EXPONENTIAL_FLOOR / 20.),
+            FadeCurve::SCurve => 0.5 - 0.5 * (t * PI).cos(),
+        }
+    }
+}
+
+// a fade at one end of a cue
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fade {
+    // zero is no fade
+    pub length: CueTime,
+    pub curve: FadeCurve,
+}
+
+impl Fade {
+    // the same fade, lengthened to at least `min`
+    pub fn at_least(self, min: CueTime) -> Self {
+        Self {
+            length: self.length.max(min),
+            ..self
+        }
+    }
+}
+
+// a source faded in from its first sample and out to its last, which is
+// `length` in
+pub struct Enveloped<S> {
+    inner: S,
+    channels: usize,
+    rate: u32,
+    fade_in: Fade,
+    fade_out: Fade,
+    length: CueTime,
+
+    // samples so far, across all channels
+    position: u64,
+    gain: f32,
+}
+
+impl<S: Source> Enveloped<S> {
+    pub fn new(inner: S, fade_in: Fade, fade_out: Fade, length: CueTime) -> Self {
+        Self {
+            channels: inner.channels().max(1) as usize,
+            rate: inner.sample_rate(),
+            inner,
+            fade_in,
+            fade_out,
+            length,
+            position: 0,
+            gain: 0.,
+        }
+    }
+}
+
+// how far through `whole` `part` is
+fn fraction(part: CueTime, whole: CueTime) -> f32 {
+    (part.as_nanos() as f64 / whole.as_nanos() as f64) as f32
+}
+
+// gain of a cue `at` into its trimmed region
+pub fn envelope_gain(fade_in: &Fade, fade_out: &Fade, length: CueTime, at: CueTime) -> f32 {
+    let mut gain = 1.;
+    if fade_in.length > CueTime::ZERO && at < fade_in.length {
+        gain *= fade_in.curve.gain(fraction(at, fade_in.length));
+    }
+    let left = length - at;
+    if fade_out.length > CueTime::ZERO && left < fade_out.length {
+        gain *= fade_out.curve.gain(fraction(left, fade_out.length));
+    }
+    gain
+}
+
+impl<S: Source> Iterator for Enveloped<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        // the same gain for every channel of a frame
+        if (self.position as usize).is_multiple_of(self.channels) {
+            let frame = self.position / self.channels as u64;
+            let at = CueTime::from_frames(frame as i64, self.rate);
+            self.gain = envelope_gain(&self.fade_in, &self.fade_out, self.length, at);
+        }
+        self.position += 1;
+        self.inner.next().map(|sample| sample * self.gain)
+    }
+}
+
+impl<S: Source> Source for Enveloped<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        let frame = CueTime::from(pos).to_frames(self.rate).max(0) as u64;
+        self.position = frame * self.channels as u64;
+        Ok(())
+    }
+}
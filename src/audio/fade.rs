@@ -0,0 +1,124 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rodio::{source::SeekError, ChannelCount, Sample, SampleRate, Source};
+
+use crate::cues::CueTime;
+
+// how many samples go by between checks for a new fade, so that the audio
+// thread isn't taking a lock on every sample
+const CHECK_INTERVAL: u32 = 256;
+
+#[derive(Clone, Copy, Debug)]
+struct FadeRequest {
+    target: f32,
+    length: CueTime,
+    stop_when_done: bool,
+}
+
+// lets a cue fade a source while it is playing
+#[derive(Clone, Debug, Default)]
+pub struct FadeHandle(Arc<Mutex<Option<FadeRequest>>>);
+
+impl FadeHandle {
+    // ramp the gain to `target` over `length`, ending the source afterwards
+    // if `stop_when_done` is set
+    pub fn fade_to(&self, target: f32, length: CueTime, stop_when_done: bool) {
+        if let Ok(mut request) = self.0.lock() {
+            *request = Some(FadeRequest {
+                target,
+                length,
+                stop_when_done,
+            });
+        }
+    }
+}
+
+// a source whose gain can be ramped through a FadeHandle
+pub struct Fadeable<S> {
+    inner: S,
+    handle: FadeHandle,
+
+    gain: f32,
+    step: f32,
+    target: f32,
+    // samples left in the current ramp
+    remaining: u64,
+    stop_when_done: bool,
+    until_check: u32,
+}
+
+impl<S: Source> Fadeable<S> {
+    pub fn new(inner: S, handle: FadeHandle) -> Self {
+        Self {
+            inner,
+            handle,
+            gain: 1.,
+            step: 0.,
+            target: 1.,
+            remaining: 0,
+            stop_when_done: false,
+            until_check: 0,
+        }
+    }
+
+    fn check_for_fade(&mut self) {
+        let request = match self.handle.0.try_lock() {
+            Ok(mut request) => request.take(),
+            Err(_) => None,
+        };
+        if let Some(request) = request {
+            let frames = request.length.to_frames(self.inner.sample_rate()).max(0) as u64;
+            let samples = (frames * self.inner.channels() as u64).max(1);
+            self.target = request.target;
+            self.step = (request.target - self.gain) / samples as f32;
+            self.remaining = samples;
+            self.stop_when_done = request.stop_when_done;
+        }
+    }
+}
+
+impl<S: Source> Iterator for Fadeable<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.until_check == 0 {
+            self.until_check = CHECK_INTERVAL;
+            self.check_for_fade();
+        }
+        self.until_check -= 1;
+
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.gain = if self.remaining == 0 {
+                self.target
+            } else {
+                self.gain + self.step
+            };
+        } else if self.stop_when_done {
+            return None;
+        }
+
+        self.inner.next().map(|sample| sample * self.gain)
+    }
+}
+
+impl<S: Source> Source for Fadeable<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
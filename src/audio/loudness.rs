@@ -0,0 +1,246 @@
+use std::f64::consts::PI;
+
+// loudness below this is never counted, in LUFS
+const ABSOLUTE_GATE: f64 = -70.;
+// blocks this far below the ungated loudness aren't counted either, in LU
+const RELATIVE_GATE: f64 = -10.;
+// gating blocks are 400 ms long and start every 100 ms
+const HOPS_PER_BLOCK: usize = 4;
+// taps of each phase of the true peak interpolator
+const TAPS: usize = 12;
+// true peak is measured at four times the sample rate
+const OVERSAMPLE: usize = 4;
+
+pub fn gain_to_db(gain: f32) -> f32 {
+    20. * gain.max(1e-6).log10()
+}
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.)
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10. * energy.log10()
+}
+
+// second order IIR filter, in direct form I
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.; 2],
+            y: [0.; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[1] * self.y[0]
+            - self.a[2] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+// the K-weighting of BS.1770: a high shelf for the head, then a high pass.
+// coefficients are worked out for any sample rate, rather than only 48 kHz
+fn k_weighting(rate: u32) -> [Biquad; 2] {
+    let rate = rate as f64;
+
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1. + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2. * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [1., 2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1. + k / q + k * k;
+    let high_pass = Biquad::new(
+        [1., -2., 1.],
+        [1., 2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+    );
+
+    [shelf, high_pass]
+}
+
+// how much each channel counts towards loudness, assuming the usual
+// L R C LFE Ls Rs order for 5.1
+fn channel_weights(channels: usize) -> Vec<f64> {
+    match channels {
+        5 => vec![1., 1., 1., 1.41, 1.41],
+        6 => vec![1., 1., 1., 0., 1.41, 1.41],
+        _ => vec![1.; channels],
+    }
+}
+
+// windowed sinc coefficients for each phase of the true peak interpolator,
+// each normalized so that it passes DC unchanged
+fn interpolator() -> Vec<[f64; TAPS]> {
+    let center = (TAPS / 2) as f64;
+    (0..OVERSAMPLE)
+        .map(|phase| {
+            let mut taps = [0.; TAPS];
+            for (k, tap) in taps.iter_mut().enumerate() {
+                let d = center - k as f64 - phase as f64 / OVERSAMPLE as f64;
+                let sinc = if d == 0. {
+                    1.
+                } else {
+                    (PI * d).sin() / (PI * d)
+                };
+                let window = 0.5 + 0.5 * (PI * d / (center + 1.)).cos();
+                *tap = sinc * window;
+            }
+            let sum: f64 = taps.iter().sum();
+            taps.map(|tap| tap / sum)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct ChannelState {
+    filters: [Biquad; 2],
+    // sum of the squared, weighted samples in the current hop
+    hop_energy: f64,
+    // latest samples, newest first, for the true peak interpolator
+    history: [f64; TAPS],
+}
+
+// integrated loudness and true peak of interleaved audio, per EBU R128 and
+// ITU-R BS.1770
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    channels: Vec<ChannelState>,
+    weights: Vec<f64>,
+    hop_length: usize,
+    // samples pushed so far, across all channels
+    pushed: usize,
+    // weighted mean square of every channel over each finished hop
+    hops: Vec<f64>,
+    interpolator: Vec<[f64; TAPS]>,
+    true_peak: f64,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: usize, rate: u32) -> Self {
+        let channels = channels.max(1);
+        Self {
+            channels: vec![
+                ChannelState {
+                    filters: k_weighting(rate),
+                    hop_energy: 0.,
+                    history: [0.; TAPS],
+                };
+                channels
+            ],
+            weights: channel_weights(channels),
+            hop_length: (rate as usize / 10).max(1),
+            pushed: 0,
+            hops: Vec::new(),
+            interpolator: interpolator(),
+            true_peak: 0.,
+        }
+    }
+
+    // add the next interleaved sample
+    pub fn push(&mut self, sample: f32) {
+        let sample = sample as f64;
+        let index = self.pushed % self.channels.len();
+        let channel = &mut self.channels[index];
+
+        let weighted = channel
+            .filters
+            .iter_mut()
+            .fold(sample, |x, filter| filter.process(x));
+        channel.hop_energy += weighted * weighted;
+
+        channel.history.rotate_right(1);
+        channel.history[0] = sample;
+        for phase in &self.interpolator {
+            let value: f64 = phase
+                .iter()
+                .zip(channel.history.iter())
+                .map(|(tap, x)| tap * x)
+                .sum();
+            self.true_peak = self.true_peak.max(value.abs());
+        }
+        self.true_peak = self.true_peak.max(sample.abs());
+
+        self.pushed += 1;
+        if self
+            .pushed
+            .is_multiple_of(self.hop_length * self.channels.len())
+        {
+            self.end_hop();
+        }
+    }
+
+    fn end_hop(&mut self) {
+        let mut energy = 0.;
+        for (channel, weight) in self.channels.iter_mut().zip(&self.weights) {
+            energy += weight * channel.hop_energy / self.hop_length as f64;
+            channel.hop_energy = 0.;
+        }
+        self.hops.push(energy);
+    }
+
+    // integrated loudness in LUFS, or None if there was nothing loud enough
+    // to measure
+    pub fn integrated(&self) -> Option<f32> {
+        integrated_loudness(&self.hops)
+    }
+
+    // weighted mean square of every channel over each 100 ms so far, from
+    // which the loudness of any part of the audio can be worked out
+    pub fn hops(&self) -> &[f64] {
+        &self.hops
+    }
+
+    // highest level between samples as well as at them, as a gain
+    pub fn true_peak(&self) -> f32 {
+        self.true_peak as f32
+    }
+}
+
+// integrated loudness in LUFS of a run of hops, as kept by a meter. a run too
+// short to fill a gating block is measured as one block
+pub fn integrated_loudness(hops: &[f64]) -> Option<f32> {
+    let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+
+    let blocks: Vec<f64> = match hops.len() {
+        0 => return None,
+        n if n < HOPS_PER_BLOCK => vec![mean(hops)],
+        _ => hops.windows(HOPS_PER_BLOCK).map(mean).collect(),
+    };
+    let audible: Vec<f64> = blocks
+        .into_iter()
+        .filter(|&e| e > 0. && energy_to_lufs(e) > ABSOLUTE_GATE)
+        .collect();
+    if audible.is_empty() {
+        return None;
+    }
+    let threshold = energy_to_lufs(mean(&audible)) + RELATIVE_GATE;
+    let gated: Vec<f64> = audible
+        .into_iter()
+        .filter(|&e| energy_to_lufs(e) > threshold)
+        .collect();
+    Some(energy_to_lufs(mean(&gated)) as f32)
+}
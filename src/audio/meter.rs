@@ -0,0 +1,176 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rodio::{source::SeekError, ChannelCount, Sample, SampleRate, Source};
+
+// how many frames a meter accumulates before publishing, so that the audio
+// thread isn't taking a lock on every sample
+const PUBLISH_INTERVAL: usize = 512;
+
+// level of one channel over some stretch of time, where 1 is full scale
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeterReading {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+impl MeterReading {
+    pub fn scaled(self, gain: f32) -> Self {
+        Self {
+            peak: self.peak * gain,
+            rms: self.rms * gain,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelAccumulator {
+    peak: f32,
+    sum_squares: f64,
+    samples: u64,
+}
+
+impl ChannelAccumulator {
+    fn add(&mut self, sample: Sample) {
+        self.peak = self.peak.max(sample.abs());
+        self.sum_squares += (sample as f64) * (sample as f64);
+        self.samples += 1;
+    }
+
+    fn merge(&mut self, other: &ChannelAccumulator) {
+        self.peak = self.peak.max(other.peak);
+        self.sum_squares += other.sum_squares;
+        self.samples += other.samples;
+    }
+
+    fn reading(&self) -> MeterReading {
+        let rms = if self.samples == 0 {
+            0.
+        } else {
+            (self.sum_squares / self.samples as f64).sqrt() as f32
+        };
+        MeterReading {
+            peak: self.peak,
+            rms,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MeterShared {
+    // everything published since the last read
+    pending: Vec<ChannelAccumulator>,
+    // the last read, handed out again to anyone else asking in the same frame
+    last_frame: Option<u64>,
+    last_readings: Vec<MeterReading>,
+}
+
+// per-channel peak and RMS level of a playing source, shared with whoever
+// wants to draw it
+#[derive(Clone, Debug, Default)]
+pub struct LevelMeter(Arc<Mutex<MeterShared>>);
+
+impl LevelMeter {
+    // levels of each channel since the previous frame. every caller asking
+    // with the same frame number gets the same readings, so a meter can be
+    // drawn in several places at once
+    pub fn read(&self, frame: u64) -> Vec<MeterReading> {
+        let Ok(mut shared) = self.0.lock() else {
+            return Vec::new();
+        };
+        if shared.last_frame != Some(frame) {
+            shared.last_frame = Some(frame);
+            let readings = shared.pending.iter().map(|acc| acc.reading()).collect();
+            shared.last_readings = readings;
+            let channels = shared.pending.len();
+            shared.pending = vec![ChannelAccumulator::default(); channels];
+        }
+        shared.last_readings.clone()
+    }
+
+    fn publish(&self, block: &mut [ChannelAccumulator]) {
+        // try again next block rather than holding up the audio thread
+        let Ok(mut shared) = self.0.try_lock() else {
+            return;
+        };
+        if shared.pending.len() != block.len() {
+            shared.pending = vec![ChannelAccumulator::default(); block.len()];
+        }
+        for (pending, acc) in shared.pending.iter_mut().zip(block.iter_mut()) {
+            pending.merge(acc);
+            *acc = ChannelAccumulator::default();
+        }
+    }
+}
+
+// passes a source through unchanged while recording its level
+pub struct Metered<S> {
+    inner: S,
+    meter: LevelMeter,
+    block: Vec<ChannelAccumulator>,
+    channel: usize,
+    frames: usize,
+}
+
+impl<S: Source> Metered<S> {
+    pub fn new(inner: S, meter: LevelMeter) -> Self {
+        let channels = inner.channels() as usize;
+        Self {
+            inner,
+            meter,
+            block: vec![ChannelAccumulator::default(); channels],
+            channel: 0,
+            frames: 0,
+        }
+    }
+}
+
+impl<S: Source> Iterator for Metered<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let sample = match self.inner.next() {
+            Some(sample) => sample,
+            None => {
+                // don't lose the tail end
+                self.meter.publish(&mut self.block);
+                return None;
+            }
+        };
+
+        if let Some(acc) = self.block.get_mut(self.channel) {
+            acc.add(sample);
+        }
+        self.channel += 1;
+        if self.channel >= self.block.len() {
+            self.channel = 0;
+            self.frames += 1;
+            if self.frames >= PUBLISH_INTERVAL {
+                self.frames = 0;
+                self.meter.publish(&mut self.block);
+            }
+        }
+
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for Metered<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
@@ -0,0 +1,203 @@
+mod analysis;
+mod effects;
+mod envelope;
+mod fade;
+mod loudness;
+mod meter;
+mod panner;
+mod peaks;
+mod virtual_output;
+mod wav;
+
+pub use analysis::{
+    analysis_cache_dir, analyze, analyze_file, analyze_now, set_analysis_cache_dir, Analysis,
+    MediaInfo, MediaStatus,
+};
+pub use effects::{
+    ChainProcessor, Effect, EffectChain, EffectSlot, EffectsHandle, Processed, MAX_DELAY,
+};
+pub use envelope::{envelope_gain, Enveloped, Fade, FadeCurve, DECLICK_LENGTH};
+pub use fade::{FadeHandle, Fadeable};
+pub use loudness::{db_to_gain, gain_to_db, integrated_loudness, LoudnessMeter};
+pub use meter::{LevelMeter, MeterReading, Metered};
+pub use panner::{
+    dbap_gains, default_speakers, placement_gains, position_at, Keyframe, Panned, Placement,
+    PlacementHandle, Speaker,
+};
+pub use peaks::{PeakBuilder, Peaks};
+pub use virtual_output::VirtualOutput;
+pub use wav::WavWriter;
+
+use std::{cell::RefCell, time::Duration};
+
+use anyhow::anyhow;
+use log::warn;
+use rodio::{
+    cpal::{self, traits::HostTrait},
+    mixer::Mixer,
+    source::Zero,
+    DeviceTrait, OutputStream, OutputStreamBuilder, Sink,
+};
+
+thread_local!(
+    pub static AUDIO_MANAGER: RefCell<Option<AudioManager>> = RefCell::new(None)
+);
+
+// where the audio goes
+#[derive(Clone, Debug, PartialEq)]
+pub enum Backend {
+    // the named output device, or the system default
+    Device(Option<String>),
+    // no device at all: a simulated clock that is moved on with `advance`,
+    // recording everything played
+    Virtual { channels: u16, rate: u32 },
+}
+
+// open the output everything plays through
+pub fn init(backend: Backend) -> Result<(), anyhow::Error> {
+    let stream = match &backend {
+        Backend::Device(None) => Some(OutputStreamBuilder::open_default_stream()?),
+        Backend::Device(Some(name)) => {
+            let device = cpal::default_host()
+                .output_devices()?
+                .find(|d| d.name().is_ok_and(|n| &n == name))
+                .ok_or_else(|| anyhow!("No output device named {}", name))?;
+            Some(OutputStreamBuilder::from_device(device)?.open_stream()?)
+        }
+        Backend::Virtual { .. } => None,
+    };
+    let (channels, rate) = match (&stream, backend) {
+        (Some(stream), _) => (
+            stream.config().channel_count(),
+            stream.config().sample_rate(),
+        ),
+        (None, Backend::Virtual { channels, rate }) if channels > 0 && rate > 0 => (channels, rate),
+        _ => return Err(anyhow!("Cannot open an output with no channels")),
+    };
+
+    // cues play into our own mixer rather than the stream's, so that the
+    // whole output can be run through effects and metered
+    let (mixer, mix) = rodio::mixer::mixer(channels, rate);
+    let master_meter = LevelMeter::default();
+    let output_effects = EffectsHandle::default();
+    let mix = Processed::new(mix, &EffectChain::default(), output_effects.clone());
+    let mix = Metered::new(mix, master_meter.clone());
+    let virtual_output = match &stream {
+        Some(stream) => {
+            stream.mixer().add(mix);
+            None
+        }
+        None => {
+            // the mixer stops when it has nothing to play, unlike a stream
+            mixer.add(Zero::new(channels, rate));
+            Some(VirtualOutput::new(Box::new(mix), channels, rate))
+        }
+    };
+
+    AUDIO_MANAGER.with(|mgr| {
+        mgr.replace(Some(AudioManager {
+            stream,
+            virtual_output,
+            mixer,
+            master_meter,
+            output_effects,
+            channels,
+            speakers: default_speakers(channels as usize),
+        }));
+    });
+
+    Ok(())
+}
+
+// run a virtual output `length` further on
+pub fn advance(length: Duration) -> Result<(), anyhow::Error> {
+    with_virtual_output(|output| output.advance(length))
+}
+
+// how much a virtual output has played
+pub fn clock() -> Result<Duration, anyhow::Error> {
+    with_virtual_output(|output| output.clock())
+}
+
+// what a virtual output has played since this was last called, as
+// interleaved samples
+pub fn take_recording() -> Result<Vec<f32>, anyhow::Error> {
+    with_virtual_output(|output| output.take_recording())
+}
+
+fn with_virtual_output<T>(f: impl FnOnce(&mut VirtualOutput) -> T) -> Result<T, anyhow::Error> {
+    AUDIO_MANAGER.with_borrow_mut(|am| match am {
+        Some(am) => match &mut am.virtual_output {
+            Some(output) => Ok(f(output)),
+            None => Err(anyhow!("Audio output is not virtual")),
+        },
+        None => Err(anyhow!("AudioManager not initialized")),
+    })
+}
+
+pub fn output_device_names() -> Result<Vec<String>, anyhow::Error> {
+    Ok(cpal::default_host()
+        .output_devices()?
+        .filter_map(|d| d.name().ok())
+        .collect())
+}
+
+// levels of each output channel since the last frame, if there is an output
+pub fn master_levels(frame: u64) -> Option<Vec<MeterReading>> {
+    AUDIO_MANAGER.with_borrow(|am| am.as_ref().map(|am| am.master_meter.read(frame)))
+}
+
+// change the effects the whole output goes through, if there is an output
+pub fn set_output_effects(chain: &EffectChain) {
+    AUDIO_MANAGER.with_borrow(|am| {
+        if let Some(am) = am {
+            am.output_effects.set(chain);
+        }
+    });
+}
+
+// place the output's speakers on the stage plot. an empty list puts them
+// where they are assumed to be by default, as does a list that doesn't have
+// one speaker per output channel
+pub fn set_speakers(speakers: &[Speaker]) {
+    AUDIO_MANAGER.with_borrow_mut(|am| {
+        if let Some(am) = am {
+            am.speakers = if speakers.is_empty() {
+                default_speakers(am.channels as usize)
+            } else if speakers.len() != am.channels as usize {
+                warn!(
+                    "Speaker layout has {} speakers but the output has {} channels, using the default layout",
+                    speakers.len(),
+                    am.channels
+                );
+                default_speakers(am.channels as usize)
+            } else {
+                speakers.to_vec()
+            };
+        }
+    });
+}
+
+// the output's speakers, if there is an output
+pub fn speakers() -> Option<Vec<Speaker>> {
+    AUDIO_MANAGER.with_borrow(|am| am.as_ref().map(|am| am.speakers.clone()))
+}
+
+// a sink on the main output for playing something other than a cue, such as
+// a preview. None if there is no output
+pub fn new_sink() -> Option<Sink> {
+    AUDIO_MANAGER.with_borrow(|am| am.as_ref().map(|am| Sink::connect_new(&am.mixer)))
+}
+
+pub struct AudioManager {
+    // None when the output is virtual
+    pub stream: Option<OutputStream>,
+    virtual_output: Option<VirtualOutput>,
+    // everything that plays goes through this
+    pub mixer: Mixer,
+    master_meter: LevelMeter,
+    output_effects: EffectsHandle,
+    channels: u16,
+    // one for each output channel
+    speakers: Vec<Speaker>,
+}
@@ -0,0 +1,312 @@
+use std::{
+    f32::consts::{FRAC_PI_4, TAU},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rodio::{source::SeekError, ChannelCount, Sample, SampleRate, Source};
+use serde::{Deserialize, Serialize};
+
+use crate::cues::CueTime;
+
+// frames between working out new gains, which are ramped to over the frames
+// in between so that moving sounds don't zipper
+const GAIN_INTERVAL: usize = 64;
+// keeps a sound right on top of a speaker from coming out of it alone
+const SPATIAL_BLUR: f32 = 0.2;
+// how much quieter a speaker gets for each doubling of distance, in dB
+const ROLLOFF: f32 = 6.;
+
+// a speaker on the stage plot, feeding one output channel. the stage runs
+// from -1 to 1 each way, with the audience towards -1 on y
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Speaker {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Speaker {
+    pub fn new(name: impl Into<String>, x: f32, y: f32) -> Self {
+        Self {
+            name: name.into(),
+            x,
+            y,
+        }
+    }
+}
+
+// where speakers are assumed to be for an output with `channels` channels,
+// until they are placed: left and right for stereo, otherwise a ring
+pub fn default_speakers(channels: usize) -> Vec<Speaker> {
+    match channels {
+        0 => vec![],
+        1 => vec![Speaker::new("Mono", 0., 1.)],
+        2 => vec![Speaker::new("L", -1., 1.), Speaker::new("R", 1., 1.)],
+        n => (0..n)
+            .map(|i| {
+                // clockwise from front left
+                let angle = 3. * FRAC_PI_4 - i as f32 * TAU / n as f32;
+                Speaker::new((i + 1).to_string(), angle.cos(), angle.sin())
+            })
+            .collect(),
+    }
+}
+
+// a point a positioned sound passes through, `time` into the cue
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time: CueTime,
+    pub x: f32,
+    pub y: f32,
+}
+
+// how a cue's channels reach the outputs
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum Placement {
+    // channel for channel, as the file has them
+    #[default]
+    Direct,
+    // across the first two outputs, from -1 (left) to 1 (right)
+    Pan(f32),
+    // a point on the stage plot, moving between keyframes in time order
+    Object(Vec<Keyframe>),
+}
+
+impl Placement {
+    // one of each kind, as a cue starts out when switched to it
+    pub fn kinds() -> [Placement; 3] {
+        [
+            Placement::Direct,
+            Placement::Pan(0.),
+            Placement::Object(vec![Keyframe {
+                time: CueTime::ZERO,
+                x: 0.,
+                y: 0.,
+            }]),
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Placement::Direct => "Direct",
+            Placement::Pan(_) => "Pan",
+            Placement::Object(_) => "Position",
+        }
+    }
+}
+
+// where a sound moving through `keyframes` is `at` in, holding still before
+// the first and after the last
+pub fn position_at(keyframes: &[Keyframe], at: CueTime) -> Option<(f32, f32)> {
+    let first = keyframes.first()?;
+    if at <= first.time {
+        return Some((first.x, first.y));
+    }
+    for pair in keyframes.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if at < b.time {
+            let span = (b.time - a.time).as_nanos().max(1);
+            let t = ((at - a.time).as_nanos() as f64 / span as f64) as f32;
+            return Some((a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t));
+        }
+    }
+    keyframes.last().map(|k| (k.x, k.y))
+}
+
+// distance based amplitude panning: louder the closer each speaker is, with
+// the total power kept the same wherever the sound is
+pub fn dbap_gains(speakers: &[Speaker], x: f32, y: f32) -> Vec<f32> {
+    let exponent = ROLLOFF / (20. * 2f32.log10());
+    let inverse: Vec<f32> = speakers
+        .iter()
+        .map(|s| {
+            let distance = ((s.x - x).powi(2) + (s.y - y).powi(2) + SPATIAL_BLUR.powi(2)).sqrt();
+            distance.powf(-exponent)
+        })
+        .collect();
+    let power: f32 = inverse.iter().map(|g| g * g).sum();
+    let k = 1. / power.sqrt().max(f32::EPSILON);
+    inverse.into_iter().map(|g| g * k).collect()
+}
+
+// gain from each input channel to each output channel, input by input,
+// `at` into the cue
+pub fn placement_gains(
+    placement: &Placement,
+    inputs: usize,
+    speakers: &[Speaker],
+    at: CueTime,
+) -> Vec<f32> {
+    let outputs = speakers.len();
+    let mut gains = vec![0.; inputs * outputs];
+    // every input mixed down to one and sent out with `out` gains
+    let mono = |gains: &mut [f32], out: &[f32]| {
+        for i in 0..inputs {
+            for (o, g) in out.iter().enumerate() {
+                gains[i * outputs + o] = g / inputs as f32;
+            }
+        }
+    };
+
+    match placement {
+        Placement::Direct => {
+            for i in 0..inputs.min(outputs) {
+                gains[i * outputs + i] = 1.;
+            }
+        }
+        Placement::Pan(_) if outputs < 2 => mono(&mut gains, &[1.]),
+        Placement::Pan(pan) => {
+            let angle = (pan.clamp(-1., 1.) + 1.) * FRAC_PI_4;
+            let (left, right) = (angle.cos(), angle.sin());
+            if inputs == 2 {
+                // a balance control, leaving the middle at full level
+                gains[0] = (left * 2f32.sqrt()).min(1.);
+                gains[outputs + 1] = (right * 2f32.sqrt()).min(1.);
+            } else {
+                mono(&mut gains, &[left, right]);
+            }
+        }
+        Placement::Object(keyframes) => {
+            let (x, y) = position_at(keyframes, at).unwrap_or_default();
+            mono(&mut gains, &dbap_gains(speakers, x, y));
+        }
+    }
+    gains
+}
+
+// lets a placement be changed while it is playing
+#[derive(Clone, Debug, Default)]
+pub struct PlacementHandle(Arc<Mutex<Option<Placement>>>);
+
+impl PlacementHandle {
+    pub fn set(&self, placement: &Placement) {
+        if let Ok(mut pending) = self.0.lock() {
+            *pending = Some(placement.clone());
+        }
+    }
+}
+
+// a source mixed to one channel per speaker by its placement
+pub struct Panned<S> {
+    inner: S,
+    handle: PlacementHandle,
+    placement: Placement,
+    speakers: Vec<Speaker>,
+    inputs: usize,
+
+    // the input frame being sent out, and the output channel up to
+    frame: Vec<f32>,
+    output: usize,
+    frames: u64,
+    gains: Vec<f32>,
+    steps: Vec<f32>,
+}
+
+impl<S: Source> Panned<S> {
+    pub fn new(
+        inner: S,
+        placement: &Placement,
+        speakers: Vec<Speaker>,
+        handle: PlacementHandle,
+    ) -> Self {
+        let inputs = inner.channels().max(1) as usize;
+        let gains = placement_gains(placement, inputs, &speakers, CueTime::ZERO);
+        Self {
+            steps: vec![0.; gains.len()],
+            gains,
+            inner,
+            handle,
+            placement: placement.clone(),
+            speakers,
+            inputs,
+            frame: vec![0.; inputs],
+            output: 0,
+            frames: 0,
+        }
+    }
+
+    // work out where the gains should be by the next update, and how far to
+    // move them each frame to get there
+    fn retarget(&mut self) {
+        if let Ok(mut pending) = self.handle.0.try_lock() {
+            if let Some(placement) = pending.take() {
+                self.placement = placement;
+            }
+        }
+        let frame = self.frames + GAIN_INTERVAL as u64;
+        let at = CueTime::from_frames(frame as i64, self.inner.sample_rate());
+        let target = placement_gains(&self.placement, self.inputs, &self.speakers, at);
+        for ((step, gain), target) in self.steps.iter_mut().zip(&self.gains).zip(target) {
+            *step = (target - gain) / GAIN_INTERVAL as f32;
+        }
+    }
+
+    // read the next input frame, moving the gains along. false once the
+    // input has run out
+    fn next_frame(&mut self) -> bool {
+        if (self.frames as usize).is_multiple_of(GAIN_INTERVAL) {
+            self.retarget();
+        }
+        for (gain, step) in self.gains.iter_mut().zip(&self.steps) {
+            *gain += step;
+        }
+        self.frames += 1;
+
+        for i in 0..self.inputs {
+            match self.inner.next() {
+                Some(sample) => self.frame[i] = sample,
+                None if i == 0 => return false,
+                // an incomplete last frame
+                None => self.frame[i] = 0.,
+            }
+        }
+        true
+    }
+}
+
+impl<S: Source> Iterator for Panned<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let outputs = self.speakers.len();
+        if outputs == 0 {
+            return None;
+        }
+        if self.output == 0 && !self.next_frame() {
+            return None;
+        }
+        let o = self.output;
+        self.output = (self.output + 1) % outputs;
+        Some(
+            self.frame
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| sample * self.gains[i * outputs + o])
+                .sum(),
+        )
+    }
+}
+
+impl<S: Source> Source for Panned<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+    fn channels(&self) -> ChannelCount {
+        self.speakers.len() as ChannelCount
+    }
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        self.frames = CueTime::from(pos)
+            .to_frames(self.inner.sample_rate())
+            .max(0) as u64;
+        self.output = 0;
+        Ok(())
+    }
+}
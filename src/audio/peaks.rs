@@ -0,0 +1,266 @@
+use anyhow::anyhow;
+
+// frames summarized by each entry of the finest level. each level after it
+// summarizes LEVEL_FACTOR times as many
+const FINEST_BLOCK: usize = 16;
+const LEVEL_FACTOR: usize = 8;
+const LEVEL_COUNT: usize = 4;
+
+// start of every peak file, followed by the format version
+const MAGIC: &[u8; 4] = b"CBPK";
+const FILE_VERSION: u32 = 1;
+
+// min/max envelope of an audio file at a few resolutions, so that a waveform
+// can be drawn at any zoom without going back to the samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct Peaks {
+    channels: usize,
+    rate: u32,
+    frames: usize,
+    // finest first
+    levels: Vec<PeakLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PeakLevel {
+    // frames per entry
+    block: usize,
+    // (min, max) of each channel over each block, interleaved and scaled to
+    // the range of an i16
+    data: Vec<(i16, i16)>,
+}
+
+impl PeakLevel {
+    // the next level up, with LEVEL_FACTOR times larger blocks
+    fn coarsen(&self, channels: usize) -> PeakLevel {
+        let mut data = Vec::with_capacity(self.data.len() / LEVEL_FACTOR + channels);
+        for group in self.data.chunks(LEVEL_FACTOR * channels) {
+            for channel in 0..channels {
+                let (min, max) = group
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .fold((i16::MAX, i16::MIN), |(lo, hi), (min, max)| {
+                        (lo.min(*min), hi.max(*max))
+                    });
+                data.push((min, max));
+            }
+        }
+        PeakLevel {
+            block: self.block * LEVEL_FACTOR,
+            data,
+        }
+    }
+}
+
+fn quantize(sample: f32) -> i16 {
+    (sample.clamp(-1., 1.) * i16::MAX as f32) as i16
+}
+
+fn unquantize(value: i16) -> f32 {
+    value as f32 / i16::MAX as f32
+}
+
+// builds up peaks as samples are decoded
+#[derive(Debug)]
+pub struct PeakBuilder {
+    channels: usize,
+    rate: u32,
+    // samples pushed so far, across all channels
+    pushed: usize,
+    // (min, max) of each channel in the block being filled
+    current: Vec<(f32, f32)>,
+    finest: Vec<(i16, i16)>,
+}
+
+impl PeakBuilder {
+    pub fn new(channels: usize, rate: u32) -> Self {
+        let channels = channels.max(1);
+        Self {
+            channels,
+            rate,
+            pushed: 0,
+            current: vec![(f32::MAX, f32::MIN); channels],
+            finest: Vec::new(),
+        }
+    }
+
+    // add the next interleaved sample
+    pub fn push(&mut self, sample: f32) {
+        let channel = self.pushed % self.channels;
+        let (min, max) = &mut self.current[channel];
+        *min = min.min(sample);
+        *max = max.max(sample);
+        self.pushed += 1;
+
+        if self.pushed.is_multiple_of(FINEST_BLOCK * self.channels) {
+            self.end_block();
+        }
+    }
+
+    fn end_block(&mut self) {
+        for (min, max) in &mut self.current {
+            if *min > *max {
+                // the last block can be missing some channels
+                (*min, *max) = (0., 0.);
+            }
+            self.finest.push((quantize(*min), quantize(*max)));
+            (*min, *max) = (f32::MAX, f32::MIN);
+        }
+    }
+
+    pub fn finish(mut self) -> Peaks {
+        if !self.pushed.is_multiple_of(FINEST_BLOCK * self.channels) {
+            self.end_block();
+        }
+        let mut levels = vec![PeakLevel {
+            block: FINEST_BLOCK,
+            data: self.finest,
+        }];
+        for _ in 1..LEVEL_COUNT {
+            let coarser = levels[levels.len() - 1].coarsen(self.channels);
+            levels.push(coarser);
+        }
+        Peaks {
+            channels: self.channels,
+            rate: self.rate,
+            frames: self.pushed / self.channels,
+            levels,
+        }
+    }
+}
+
+impl Peaks {
+    pub fn from_samples(channels: usize, rate: u32, samples: &[f32]) -> Self {
+        let mut builder = PeakBuilder::new(channels, rate);
+        for sample in samples {
+            builder.push(*sample);
+        }
+        builder.finish()
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    // in seconds
+    pub fn length(&self) -> f32 {
+        self.frames as f32 / self.rate as f32
+    }
+
+    // lowest and highest sample of `channel` between two frames, from the
+    // coarsest level that still has a few blocks in the range
+    pub fn envelope(&self, channel: usize, from: usize, to: usize) -> (f32, f32) {
+        let span = to.saturating_sub(from).max(1);
+        let level = self
+            .levels
+            .iter()
+            .rev()
+            .find(|level| level.block * 2 <= span)
+            .unwrap_or(&self.levels[0]);
+
+        let (mut min, mut max) = (i16::MAX, i16::MIN);
+        let last = to.div_ceil(level.block).max(from / level.block + 1);
+        for block in from / level.block..last {
+            if let Some((lo, hi)) = level.data.get(block * self.channels + channel) {
+                min = min.min(*lo);
+                max = max.max(*hi);
+            }
+        }
+        if min > max {
+            (0., 0.)
+        } else {
+            (unquantize(min), unquantize(max))
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FILE_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.channels as u32).to_le_bytes());
+        out.extend_from_slice(&self.rate.to_le_bytes());
+        out.extend_from_slice(&(self.frames as u64).to_le_bytes());
+        out.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+        for level in &self.levels {
+            out.extend_from_slice(&(level.block as u32).to_le_bytes());
+            out.extend_from_slice(&(level.data.len() as u64).to_le_bytes());
+            for (min, max) in &level.data {
+                out.extend_from_slice(&min.to_le_bytes());
+                out.extend_from_slice(&max.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        let mut reader = ByteReader(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(anyhow!("Not a peak file"));
+        }
+        let version = reader.u32()?;
+        if version != FILE_VERSION {
+            return Err(anyhow!("Unsupported peak file version {}", version));
+        }
+        let channels = reader.u32()? as usize;
+        let rate = reader.u32()?;
+        let frames = reader.u64()? as usize;
+        let level_count = reader.u32()?;
+        if channels == 0 || rate == 0 || level_count == 0 {
+            return Err(anyhow!("Peak file is empty"));
+        }
+
+        let mut levels = Vec::new();
+        for _ in 0..level_count {
+            let block = reader.u32()? as usize;
+            let len = reader.u64()? as usize;
+            if block == 0 || len > reader.0.len() / 4 {
+                return Err(anyhow!("Peak file is truncated"));
+            }
+            let mut data = Vec::with_capacity(len);
+            for _ in 0..len {
+                data.push((reader.i16()?, reader.i16()?));
+            }
+            levels.push(PeakLevel { block, data });
+        }
+        Ok(Self {
+            channels,
+            rate,
+            frames,
+            levels,
+        })
+    }
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], anyhow::Error> {
+        if self.0.len() < n {
+            return Err(anyhow!("Peak file is truncated"));
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, anyhow::Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, anyhow::Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn i16(&mut self) -> Result<i16, anyhow::Error> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+}
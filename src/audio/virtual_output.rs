@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use rodio::Source;
+
+// an output with no device behind it. nothing is played until the clock is
+// moved on, at which point that much of the mix is pulled through and kept,
+// so playback can be run and checked without sound hardware
+pub struct VirtualOutput {
+    mix: Box<dyn Source + Send>,
+    channels: u16,
+    rate: u32,
+    // frames pulled since the output was opened
+    frames: u64,
+    recording: Vec<f32>,
+}
+
+impl VirtualOutput {
+    pub fn new(mix: Box<dyn Source + Send>, channels: u16, rate: u32) -> Self {
+        Self {
+            mix,
+            channels,
+            rate,
+            frames: 0,
+            recording: Vec::new(),
+        }
+    }
+
+    // play `length` more of the mix, to the nearest frame
+    pub fn advance(&mut self, length: Duration) {
+        let target = ((self.frames as f64 + length.as_secs_f64() * self.rate as f64).round()
+            as u64)
+            .max(self.frames);
+        let samples = (target - self.frames) as usize * self.channels as usize;
+        self.recording.reserve(samples);
+        for _ in 0..samples {
+            // the mix never runs dry, but keep time if it somehow does
+            self.recording.push(self.mix.next().unwrap_or(0.));
+        }
+        self.frames = target;
+    }
+
+    // how much has been played
+    pub fn clock(&self) -> Duration {
+        Duration::from_secs_f64(self.frames as f64 / self.rate as f64)
+    }
+
+    // the interleaved samples played since the last time this was called
+    pub fn take_recording(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.recording)
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use anyhow::anyhow;
+
+const FORMAT_IEEE_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xfffe;
+// the IEEE float subformat GUID, for files with more than two channels
+const SUBFORMAT_IEEE_FLOAT: [u8; 16] = [
+    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+// writes 32 bit float samples to a WAV file as they come, filling in the
+// sizes once it is finished
+pub struct WavWriter {
+    out: BufWriter<File>,
+    channels: u16,
+    frames: u32,
+    samples: u64,
+    // where the sizes that are only known at the end go
+    riff_size_at: u64,
+    fact_at: u64,
+    data_size_at: u64,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, channels: u16, rate: u32) -> Result<Self, anyhow::Error> {
+        if channels == 0 || rate == 0 {
+            return Err(anyhow!("Cannot write a WAV with no channels"));
+        }
+        let mut out = BufWriter::new(File::create(path)?);
+        let extensible = channels > 2;
+        let block_align = channels * 4;
+
+        out.write_all(b"RIFF")?;
+        let riff_size_at = 4;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(b"WAVE")?;
+
+        out.write_all(b"fmt ")?;
+        out.write_all(&(if extensible { 40u32 } else { 18 }).to_le_bytes())?;
+        let format = if extensible {
+            FORMAT_EXTENSIBLE
+        } else {
+            FORMAT_IEEE_FLOAT
+        };
+        out.write_all(&format.to_le_bytes())?;
+        out.write_all(&channels.to_le_bytes())?;
+        out.write_all(&rate.to_le_bytes())?;
+        out.write_all(&(rate * block_align as u32).to_le_bytes())?;
+        out.write_all(&block_align.to_le_bytes())?;
+        out.write_all(&32u16.to_le_bytes())?;
+        if extensible {
+            out.write_all(&22u16.to_le_bytes())?;
+            out.write_all(&32u16.to_le_bytes())?;
+            // leave the speaker mask unset, since outputs are placed on the
+            // stage plot rather than in standard positions
+            out.write_all(&0u32.to_le_bytes())?;
+            out.write_all(&SUBFORMAT_IEEE_FLOAT)?;
+        } else {
+            out.write_all(&0u16.to_le_bytes())?;
+        }
+
+        out.write_all(b"fact")?;
+        out.write_all(&4u32.to_le_bytes())?;
+        let fact_at = out.stream_position()?;
+        out.write_all(&0u32.to_le_bytes())?;
+
+        out.write_all(b"data")?;
+        let data_size_at = out.stream_position()?;
+        out.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            out,
+            channels,
+            frames: 0,
+            samples: 0,
+            riff_size_at,
+            fact_at,
+            data_size_at,
+        })
+    }
+
+    // interleaved samples
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), anyhow::Error> {
+        for sample in samples {
+            self.out.write_all(&sample.to_le_bytes())?;
+        }
+        self.samples += samples.len() as u64;
+        self.frames = (self.samples / self.channels as u64) as u32;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), anyhow::Error> {
+        let data_size = u32::try_from(self.samples * 4)
+            .map_err(|_| anyhow!("Render is too long for a WAV file"))?;
+        let riff_size = self.data_size_at as u32 + 4 + data_size - 8;
+
+        for (at, value) in [
+            (self.riff_size_at, riff_size),
+            (self.fact_at, self.frames),
+            (self.data_size_at, data_size),
+        ] {
+            self.out.seek(SeekFrom::Start(at))?;
+            self.out.write_all(&value.to_le_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -1,7 +1,41 @@
-use cueball::{cli::cueball_cli, cli::CLIMode};
+use clap::Parser;
+use cueball::{
+    args::{run_script, CliArgs},
+    cli::cueball_cli,
+    cli::CLIMode,
+    Project,
+};
+use log::{error, LevelFilter};
 use mlua::prelude::*;
 
 fn main() -> Result<(), ()> {
+    let args = CliArgs::parse();
+    args.common.init_logger(LevelFilter::Info);
+    if let Some(code) = args.common.run_early_exits() {
+        std::process::exit(code);
+    }
+
+    if let Err(err) = args.common.init_audio() {
+        error!("Could not initialize audio output: {}", err);
+    }
+
+    let project = match &args.common.project {
+        Some(path) => match Project::open(path) {
+            Ok(project) => Some(project),
+            Err(err) => {
+                error!("Failed to open project at {}: {}", path.display(), err);
+                return Err(());
+            }
+        },
+        None => None,
+    };
+
     let lua = Lua::new();
-    cueball_cli(CLIMode::CLI, lua)
+    if let Some(script) = &args.script {
+        if let Err(err) = run_script(&lua, script) {
+            error!("Error running {}: {}", script.display(), err);
+        }
+    }
+
+    cueball_cli(CLIMode::CLI, lua, project)
 }
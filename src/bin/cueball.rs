@@ -1,21 +1,33 @@
 use anyhow::anyhow;
-use cueball::{app::CueballApp, audio};
+use clap::Parser;
+use cueball::{app::CueballApp, args::GuiArgs};
+use log::LevelFilter;
 
 fn main() -> Result<(), anyhow::Error> {
-    env_logger::init();
+    let args = GuiArgs::parse();
+    args.common.init_logger(LevelFilter::Error);
+    if let Some(code) = args.common.run_early_exits() {
+        std::process::exit(code);
+    }
 
-    audio::init()?;
+    args.common.init_audio()?;
 
     match eframe::run_native(
         "cueball",
         eframe::NativeOptions {
             viewport: egui::ViewportBuilder {
-                title: Some("wip-cueball".into()),
+                title: Some("cueball".into()),
                 ..Default::default()
             },
             ..Default::default()
         },
-        Box::new(|cc| Ok(Box::new(CueballApp::new(cc)))),
+        Box::new(|cc| {
+            Ok(Box::new(CueballApp::new(
+                cc,
+                args.common.project.clone(),
+                args.show_mode,
+            )))
+        }),
     ) {
         Ok(_) => Ok(()),
         Err(err) => Err(anyhow!("{}", err)),
@@ -1,6 +1,31 @@
-use log::{debug, error, warn, LevelFilter};
+use std::{
+    borrow::Cow,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cues::{CueRunning, CueTime},
+    Cue, Project,
+};
+
+use log::{debug, error, info, warn};
 use mlua::prelude::*;
-use reedline::{DefaultPrompt, DefaultPromptSegment, Reedline, Signal};
+use reedline::{
+    DefaultPrompt, DefaultPromptSegment, ExternalPrinter, Prompt, PromptEditMode,
+    PromptHistorySearch, Reedline, Signal,
+};
+
+// length of the fade command
+const FADE_LENGTH: CueTime = CueTime::from_millis(3000);
+// how often pre-waits and auto-follows are checked on, while the prompt waits
+// for input
+const TICK_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CLIMode {
@@ -21,31 +46,385 @@ fn print_help_menu() {
     println!("/exit\t\tExit Cueball");
     println!("/cli\t\tSwitch to CLI mode");
     println!("/lua\t\tSwitch to lua interpreter mode");
+
+    println!("\nCLI Commands:");
+    println!("go [list]\tFire the cue at a list's playhead and advance");
+    println!("goto <id>\tMove the playhead to a cue, or list:id in another list");
+    println!("next, prev\tMove the playhead by one cue");
+    println!("stop [id]\tStop a cue, or every cue if none is given");
+    println!("pause [id]\tPause a cue, or every running cue");
+    println!("resume [id]\tResume a cue, or every paused cue");
+    println!("fade [id]\tFade out a cue, or every running cue");
+    println!("panic\t\tStop every cue immediately");
+    println!("list\t\tList all cues in the current cue list");
+    println!("lists\t\tList all cue lists in the project");
+    println!("use <list>\tSwitch to another cue list");
+    println!("open <path>\tOpen a project file");
+    println!("render <path>\tRender the current cue list to a WAV file");
 }
 
-pub fn cueball_cli(initial_mode: CLIMode, lua: Lua) -> Result<(), ()> {
-    let mut line_editor_cli = Reedline::create();
-    let mut line_editor_lua = Reedline::create();
-    let mut mode = initial_mode;
+// state for running a show from the terminal. commands and ticks have to
+// come from the thread that opened the audio output, since cues look it up
+// there as they start
+pub struct Show {
+    pub project: Option<Project>,
+    // index of the cue list commands act on
+    list: usize,
+}
 
-    env_logger::builder()
-        .filter_level(LevelFilter::Info)
-        .parse_default_env()
-        .init();
+impl Show {
+    pub fn new(project: Option<Project>) -> Self {
+        Self { project, list: 0 }
+    }
 
-    lua.set_warning_function(|_lua, warnstr, _incomplete| Ok(warn!("{}", warnstr)));
+    // fire cues whose pre-wait is over, follow on from finished ones and
+    // pick up media that has finished loading
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(project) = &mut self.project {
+            project.update(now);
+            project.update_media();
+        }
+    }
+
+    // run a line typed at the CLI prompt. the rest of the line is the
+    // argument, since list names can have spaces in them
+    pub fn run_line(&mut self, line: &str) {
+        let line = line.trim();
+        match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => self.run_command(command, Some(arg.trim())),
+            None => self.run_command(line, None),
+        }
+    }
+
+    // left prompt segment, showing the playhead
+    fn playhead_status(&self) -> String {
+        let project = match &self.project {
+            Some(p) => p,
+            None => return "no project".to_string(),
+        };
+        let list = &project.cue_lists[self.list];
+        let prefix = if project.cue_lists.len() > 1 {
+            format!("{} > {}", project.name, list.name)
+        } else {
+            project.name.clone()
+        };
+        match list.playhead {
+            Some(i) => {
+                let cue = &list[i];
+                format!("{} > {} {}", prefix, cue.get_id(), cue.get_name())
+            }
+            None => format!("{} > end", prefix),
+        }
+    }
+
+    // ids of the running cues, and whether each is paused
+    fn running_cues(&self) -> Vec<(String, bool)> {
+        let Some(project) = &self.project else {
+            return Vec::new();
+        };
+        project
+            .cue_lists
+            .iter()
+            .flat_map(|list| list.active_cues().into_iter().map(move |i| &list[i]))
+            .map(|cue| (cue.get_id(), cue.running() == CueRunning::Paused))
+            .collect()
+    }
+
+    // right prompt segment, showing every running cue
+    fn running_status(&self) -> String {
+        let project = match &self.project {
+            Some(p) => p,
+            None => return String::new(),
+        };
+        project
+            .cue_lists
+            .iter()
+            .flat_map(|list| list.active_cues().into_iter().map(move |i| &list[i]))
+            .map(|cue| {
+                let state = match cue.running() {
+                    CueRunning::Paused => "||",
+                    _ => ">",
+                };
+                match cue.elapsed() {
+                    Some(el) => format!("{} {} {:.1}", state, cue.get_id(), el),
+                    None => format!("{} {}", state, cue.get_id()),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    fn run_command(&mut self, command: &str, arg: Option<&str>) {
+        if command == "open" {
+            match arg {
+                Some(path) => match Project::open(path) {
+                    Ok(project) => {
+                        info!("Opened project {}", project.name);
+                        *self = Show::new(Some(project));
+                    }
+                    Err(err) => error!("Failed to open project: {}", err),
+                },
+                None => error!("Usage: open <path>"),
+            }
+            return;
+        }
+
+        let project = match &mut self.project {
+            Some(p) => p,
+            None => {
+                error!("No project loaded! Try open <path>");
+                return;
+            }
+        };
+
+        // commands that take a list name rather than a cue
+        match command {
+            "lists" => {
+                for (i, list) in project.cue_lists.iter().enumerate() {
+                    let marker = if i == self.list { ">" } else { " " };
+                    let key = list.go_key.as_deref().unwrap_or("");
+                    println!("{} {}\t{} cues\t{}", marker, list.name, list.len(), key);
+                }
+                return;
+            }
+            "use" => {
+                match arg.and_then(|name| project.list_index(name)) {
+                    Some(l) => self.list = l,
+                    None => error!("Usage: use <list>"),
+                }
+                return;
+            }
+            "go" => {
+                let list = match arg {
+                    Some(name) => match project.list_index(name) {
+                        Some(l) => l,
+                        None => {
+                            error!("No cue list named {}", name);
+                            return;
+                        }
+                    },
+                    None => self.list,
+                };
+                if project.cue_lists[list].playhead.is_none() {
+                    warn!(
+                        "Playhead is at the end of cue list {}",
+                        project.cue_lists[list].name
+                    );
+                }
+                project.go(list);
+                return;
+            }
+            "render" => {
+                match arg {
+                    Some(path) => {
+                        let to = project.cue_lists[self.list].len().saturating_sub(1);
+                        let options = project.render_options();
+                        match project.render_to(self.list, 0, to, options, Path::new(path)) {
+                            Ok(report) => {
+                                for (id, reason) in report.skipped {
+                                    warn!("Skipped cue {}: {}", id, reason);
+                                }
+                            }
+                            Err(err) => error!("Failed to render: {}", err),
+                        }
+                    }
+                    None => error!("Usage: render <path>"),
+                }
+                return;
+            }
+            _ => (),
+        }
+
+        // resolve the optional cue argument to an index
+        let target = match arg {
+            Some(reference) => match project.resolve_ref(self.list, reference) {
+                Some(t) => Some(t),
+                None => {
+                    error!("No cue {}", reference);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        let list = &mut project.cue_lists[self.list];
+        match command {
+            "goto" => match target {
+                Some((l, i)) => {
+                    self.list = l;
+                    project.cue_lists[l].set_playhead(Some(i));
+                }
+                None => error!("Usage: goto <id>"),
+            },
+            "next" => {
+                let next = list.playhead.map(|i| i + 1);
+                list.set_playhead(next);
+            }
+            "prev" => {
+                let prev = match list.playhead {
+                    Some(i) => Some(i.saturating_sub(1)),
+                    None => list.len().checked_sub(1),
+                };
+                list.set_playhead(prev);
+            }
+            "stop" => match target {
+                Some((l, i)) => project.cue_lists[l][i].stop(),
+                None => project.stop_all(),
+            },
+            "pause" | "resume" | "fade" => {
+                let targets = match target {
+                    Some(t) => vec![t],
+                    None => project
+                        .cue_lists
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(l, list)| list.active_cues().into_iter().map(move |i| (l, i)))
+                        .collect(),
+                };
+                for (l, i) in targets {
+                    let cue = &mut project.cue_lists[l][i];
+                    match command {
+                        "pause" => cue.set_paused(true),
+                        "resume" => cue.set_paused(false),
+                        _ => cue.fade_out(FADE_LENGTH),
+                    }
+                }
+            }
+            "panic" => {
+                project.stop_all();
+                warn!("Panic! Stopped all cues");
+            }
+            "list" => {
+                for (i, cue) in list.iter().enumerate() {
+                    let marker = if Some(i) == list.playhead { ">" } else { " " };
+                    let length = match cue.length() {
+                        Some(len) => len.to_string(),
+                        None => String::new(),
+                    };
+                    println!(
+                        "{} {}\t{}\t{}\t{}",
+                        marker,
+                        cue.get_id(),
+                        cue.type_str_short(),
+                        cue.get_name(),
+                        length
+                    );
+                }
+            }
+            _ => error!("Unknown CLI command! Try /help"),
+        }
+    }
+}
+
+// the CLI mode prompt, built from the show whenever reedline paints it rather
+// than once per line, so it keeps up with cues starting and stopping
+struct StatusPrompt(Arc<Mutex<Show>>);
+
+impl StatusPrompt {
+    fn current(&self) -> DefaultPrompt {
+        let show = self.0.lock().unwrap();
+        DefaultPrompt::new(
+            DefaultPromptSegment::Basic(format!("Cueball [{}]", show.playhead_status())),
+            DefaultPromptSegment::Basic(show.running_status()),
+        )
+    }
+}
+
+impl Prompt for StatusPrompt {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        Cow::Owned(self.current().render_prompt_left().into_owned())
+    }
+
+    fn render_prompt_right(&self) -> Cow<'_, str> {
+        Cow::Owned(self.current().render_prompt_right().into_owned())
+    }
+
+    fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<'_, str> {
+        Cow::Owned(
+            DefaultPrompt::default()
+                .render_prompt_indicator(prompt_mode)
+                .into_owned(),
+        )
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<'_, str> {
+        Cow::Owned(
+            DefaultPrompt::default()
+                .render_prompt_multiline_indicator()
+                .into_owned(),
+        )
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<'_, str> {
+        Cow::Owned(
+            DefaultPrompt::default()
+                .render_prompt_history_search_indicator(history_search)
+                .into_owned(),
+        )
+    }
+}
+
+// a line for each cue that started, paused, resumed or finished between
+// `before` and `now`
+fn running_changes(before: &[(String, bool)], now: &[(String, bool)]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (id, paused) in now {
+        match before.iter().find(|(old, _)| old == id) {
+            None => lines.push(format!("Cue {} started", id)),
+            Some((_, was)) if was != paused => lines.push(format!(
+                "Cue {} {}",
+                id,
+                if *paused { "paused" } else { "resumed" }
+            )),
+            Some(_) => {}
+        }
+    }
+    for (id, _) in before {
+        if !now.iter().any(|(new, _)| new == id) {
+            lines.push(format!("Cue {} finished", id));
+        }
+    }
+    lines
+}
+
+// what the prompt thread hands to the thread running the show
+enum Input {
+    Line(CLIMode, String),
+    Exit,
+}
+
+// read lines at the prompt until the user leaves, handing everything but
+// mode switches and help to the show. each line is waited on, so its output
+// comes before the next prompt
+fn read_input(
+    initial_mode: CLIMode,
+    show: Arc<Mutex<Show>>,
+    printer: ExternalPrinter<String>,
+    input: Sender<Input>,
+    done: Receiver<()>,
+) {
+    let mut line_editor_cli = Reedline::create().with_external_printer(printer.clone());
+    let mut line_editor_lua = Reedline::create().with_external_printer(printer);
+    let mut mode = initial_mode;
+    let status_prompt = StatusPrompt(show);
 
     loop {
         let line_editor = match mode {
             CLIMode::CLI => &mut line_editor_cli,
             CLIMode::Lua => &mut line_editor_lua,
         };
-        let prompt = DefaultPrompt::new(
-            DefaultPromptSegment::Basic(format!("Cueball {}", mode.to_string())),
+        let lua_prompt = DefaultPrompt::new(
+            DefaultPromptSegment::Basic(format!("Cueball {}", mode)),
             DefaultPromptSegment::CurrentDateTime,
         );
-        let sig = line_editor.read_line(&prompt);
-        match sig {
+        let prompt: &dyn Prompt = match mode {
+            CLIMode::CLI => &status_prompt,
+            CLIMode::Lua => &lua_prompt,
+        };
+        match line_editor.read_line(prompt) {
             Ok(Signal::Success(inp)) => match inp.as_str() {
                 "/cli" => mode = CLIMode::CLI,
                 "/lua" => mode = CLIMode::Lua,
@@ -54,25 +433,11 @@ pub fn cueball_cli(initial_mode: CLIMode, lua: Lua) -> Result<(), ()> {
                     print_help_menu();
                 }
                 "" => (),
-                _ => match mode {
-                    CLIMode::CLI => {
-                        error!("Unknown CLI command! Try /help");
+                _ => {
+                    if input.send(Input::Line(mode.clone(), inp)).is_err() || done.recv().is_err() {
+                        break;
                     }
-                    CLIMode::Lua => match lua.load(inp).eval::<LuaMultiValue>() {
-                        Ok(xs) => {
-                            if !xs.is_empty() {
-                                println!(
-                                    "{}",
-                                    xs.iter()
-                                        .map(|x| format!("{:#?}", x))
-                                        .collect::<Vec<_>>()
-                                        .join("\t")
-                                )
-                            }
-                        }
-                        Err(err) => error!("{}", err),
-                    },
-                },
+                }
             },
             Ok(Signal::CtrlD) | Ok(Signal::CtrlC) => {
                 break;
@@ -82,6 +447,72 @@ pub fn cueball_cli(initial_mode: CLIMode, lua: Lua) -> Result<(), ()> {
             }
         }
     }
+    let _ = input.send(Input::Exit);
+}
+
+pub fn cueball_cli(initial_mode: CLIMode, lua: Lua, project: Option<Project>) -> Result<(), ()> {
+    // lines printed over the prompt, which repaints it with the new status
+    let printer = ExternalPrinter::default();
+    let changes = printer.sender();
+    let show = Arc::new(Mutex::new(Show::new(project)));
+
+    // the prompt blocks while waiting for a key, so it gets a thread of its
+    // own. this one opened the audio output, and keeps sequences going
+    let (input, lines) = mpsc::channel();
+    let (done, wait) = mpsc::channel();
+    let reader = {
+        let show = show.clone();
+        thread::spawn(move || read_input(initial_mode, show, printer, input, wait))
+    };
+
+    lua.set_warning_function(|_lua, warnstr, _incomplete| Ok(warn!("{}", warnstr)));
+
+    let mut running = Vec::new();
+    loop {
+        match lines.recv_timeout(TICK_INTERVAL) {
+            Ok(Input::Line(CLIMode::CLI, inp)) => {
+                show.lock().unwrap().run_line(&inp);
+                let _ = done.send(());
+            }
+            Ok(Input::Line(CLIMode::Lua, inp)) => {
+                match lua.load(inp).eval::<LuaMultiValue>() {
+                    Ok(xs) => {
+                        if !xs.is_empty() {
+                            println!(
+                                "{}",
+                                xs.iter()
+                                    .map(|x| format!("{:#?}", x))
+                                    .collect::<Vec<_>>()
+                                    .join("\t")
+                            )
+                        }
+                    }
+                    Err(err) => error!("{}", err),
+                }
+                let _ = done.send(());
+            }
+            Ok(Input::Exit) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => (),
+        }
+
+        // report cues as they start and stop
+        let mut show = show.lock().unwrap();
+        show.tick(Instant::now());
+        let now = show.running_cues();
+        drop(show);
+        for line in running_changes(&running, &now) {
+            // nothing is waiting on the prompt while a command runs, so
+            // drop lines rather than block when it falls behind
+            let _ = changes.try_send(line);
+        }
+        running = now;
+    }
+    let _ = reader.join();
+
+    // don't leave anything playing on the way out
+    if let Some(project) = &mut show.lock().unwrap().project {
+        project.stop_all();
+    }
 
     Ok(())
 }
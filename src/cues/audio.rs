@@ -1,13 +1,20 @@
-use std::{fmt::Debug, fs::File, io::BufReader, time::Duration};
+use std::{fmt::Debug, fs::File, io::BufReader, path::Path};
 
-use crate::audio;
+use crate::audio::{
+    self, EffectChain, EffectsHandle, Enveloped, Fade, FadeHandle, Fadeable, LevelMeter, MediaInfo,
+    MediaStatus, MeterReading, Metered, Panned, Placement, PlacementHandle, Processed, Speaker,
+    DECLICK_LENGTH,
+};
 use anyhow::anyhow;
 use log::{debug, error, warn};
 use mlua::prelude::*;
-use rodio::{Decoder, Sink, Source};
+use rodio::{mixer::Mixer, Decoder, Sink, Source};
 use serde::{Deserialize, Serialize};
 
-use super::{add_common_lua_fields, add_common_lua_methods, Cue, CueRunning, CueTime};
+use super::{
+    add_common_lua_fields, add_common_lua_methods, ContinueMode, Cue, CueRunning, CueTime,
+    CueTypeAttributes,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct AudioCue {
@@ -16,84 +23,283 @@ pub struct AudioCue {
 
     pub file_path: String,
 
-    pub start: f32,
-    pub end: f32,
+    // how much is cut off each end of the file
+    pub start: CueTime,
+    pub end: CueTime,
+    // relative to the trimmed region
+    #[serde(default)]
+    fade_in: Fade,
+    #[serde(default)]
+    fade_out: Fade,
 
     #[serde(default = "default_volume")]
     volume: f32,
+    // gain on top of the volume, in dB, usually set by normalizing
+    #[serde(default)]
+    trim: f32,
+    #[serde(default)]
+    effects: EffectChain,
+    #[serde(default)]
+    placement: Placement,
+
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default = "default_true")]
+    armed: bool,
+    #[serde(default)]
+    hotkey: Option<String>,
+    #[serde(default)]
+    color: Option<[u8; 3]>,
+    #[serde(default)]
+    flagged: bool,
+    #[serde(default)]
+    pre_wait: CueTime,
+    #[serde(default)]
+    continue_mode: ContinueMode,
+    #[serde(default)]
+    notes: String,
 
     #[serde(skip)]
     pub sink: Option<Box<Sink>>,
+    // the mix the sink plays into, kept so the cue can get a new sink from
+    // whichever thread it is fired on
     #[serde(skip)]
-    pub duration: Option<f32>,
+    output: Option<Mixer>,
+    #[serde(skip)]
+    pub duration: Option<CueTime>,
+    // waiting on the file to be analyzed
+    #[serde(skip)]
+    loading: bool,
+    // why the file couldn't be analyzed, if it couldn't
+    #[serde(skip)]
+    error: Option<String>,
+
+    #[serde(skip)]
+    fade: FadeHandle,
+    #[serde(skip)]
+    effects_handle: EffectsHandle,
+    #[serde(skip)]
+    placement_handle: PlacementHandle,
+    #[serde(skip)]
+    meter: LevelMeter,
+}
+
+// to the frame, rather than the f32 seconds the analysis gives
+fn exact_length(info: &MediaInfo) -> CueTime {
+    CueTime::from_frames(info.frames as i64, info.rate)
 }
 
 fn default_volume() -> f32 {
     0.5
 }
 
+fn default_true() -> bool {
+    true
+}
+
 impl AudioCue {
     pub fn with_id(id: String) -> Self {
         Self {
             id,
             name: "New audio cue".into(),
             file_path: "".into(),
-            start: 0.,
-            end: 0.,
+            start: CueTime::ZERO,
+            end: CueTime::ZERO,
+            fade_in: Fade::default(),
+            fade_out: Fade::default(),
             volume: 1.,
+            trim: 0.,
+            effects: EffectChain::default(),
+            placement: Placement::default(),
+            enabled: true,
+            armed: true,
+            hotkey: None,
+            color: None,
+            flagged: false,
+            pre_wait: CueTime::ZERO,
+            continue_mode: ContinueMode::DoNotContinue,
+            notes: String::new(),
             sink: None,
+            output: None,
             duration: None,
+            loading: false,
+            error: None,
+            fade: FadeHandle::default(),
+            effects_handle: EffectsHandle::default(),
+            placement_handle: PlacementHandle::default(),
+            meter: LevelMeter::default(),
         }
     }
 
     fn play_audio(&mut self) -> Result<(), anyhow::Error> {
-        if let Some(sink) = &self.sink {
-            let file = BufReader::new(File::open(self.file_path.clone())?);
-            let source = Decoder::new(file)?;
-
-            // manually buffer ourselves because .buffered() was deleting all
-            // the audio data for some reason???
-            let source = rodio::buffer::SamplesBuffer::new(
-                source.channels(),
-                source.sample_rate(),
-                source.collect::<Vec<f32>>(),
-            );
-
-            // apply start and end offsets
-            let duration = match self.duration {
-                Some(d) => Duration::from_secs_f32(d),
-                None => return Err(anyhow!("Audio cue {} had invalid duration", self.id)),
-            };
-            let start_offset = Duration::from_secs_f32(self.start);
-            let source = source.skip_duration(start_offset).take_duration(duration);
-
-            // set volume
-            sink.set_volume(self.volume);
-            // let source = source.amplify_decibel(self.volume);
-
-            sink.clear();
-            sink.append(source);
-            sink.play();
-
-            Ok(())
-        } else {
-            Err(anyhow!("Not initialized!"))
+        if self.sink.is_none() {
+            return Err(anyhow!("Not initialized!"));
+        }
+        // the speaker layout lives with the output, on the thread that opened
+        // it. firing from any other thread would lose the cue's placement
+        // without a word
+        let speakers = audio::speakers().ok_or_else(|| {
+            anyhow!(
+                "Audio cue {} was fired away from the audio output's thread",
+                self.id
+            )
+        })?;
+        let source = self.source(Some(speakers))?;
+        match &self.output {
+            // a new sink with the source already queued, so it starts on the
+            // next sample rather than after the idle sink's filler silence
+            Some(mixer) => {
+                let (sink, queue) = Sink::new();
+                sink.set_volume(self.gain());
+                sink.append(source);
+                mixer.add(queue);
+                self.sink = Some(Box::new(sink));
+            }
+            None => {
+                if let Some(sink) = &self.sink {
+                    // set volume
+                    sink.set_volume(self.gain());
+                    // let source = source.amplify_decibel(self.volume);
+
+                    sink.clear();
+                    sink.append(source);
+                    sink.play();
+                }
+            }
         }
+        Ok(())
     }
 
-    fn init_duration(&mut self) -> Result<(), anyhow::Error> {
+    // the cue's audio as it comes out before its volume: trimmed, faded, run
+    // through its effects and placed among `speakers`. live playback and
+    // offline renders both go through this
+    pub fn source(
+        &mut self,
+        speakers: Option<Vec<Speaker>>,
+    ) -> Result<Box<dyn Source + Send>, anyhow::Error> {
+        // apply start and end offsets
+        let duration = match self.duration {
+            Some(d) => d.to_duration(),
+            None if self.loading => {
+                return Err(anyhow!("Audio cue {} is still being analyzed", self.id))
+            }
+            None => return Err(anyhow!("Audio cue {} had invalid duration", self.id)),
+        };
+        let start_offset = self.start.to_duration();
+
+        // stream from disk rather than decoding the whole file up front,
+        // seeking to the start offset so the audio thread doesn't have to
+        // decode its way there
         let file = BufReader::new(File::open(self.file_path.clone())?);
+        let mut source = Decoder::new(file)?;
+        let source: Box<dyn Source + Send> = match source.try_seek(start_offset) {
+            Ok(()) => Box::new(source.take_duration(duration)),
+            Err(err) => {
+                debug!("Could not seek in {}: {}", self.file_path, err);
+                Box::new(source.skip_duration(start_offset).take_duration(duration))
+            }
+        };
 
-        let source = Decoder::new(file)?;
+        // built in fades, with at least a short ramp wherever the file
+        // has been cut into
+        let fade_in = if self.start > CueTime::ZERO {
+            self.fade_in.at_least(DECLICK_LENGTH)
+        } else {
+            self.fade_in
+        };
+        let fade_out = if self.end > CueTime::ZERO {
+            self.fade_out.at_least(DECLICK_LENGTH)
+        } else {
+            self.fade_out
+        };
+        let source = Enveloped::new(source, fade_in, fade_out, duration.into());
+
+        // fresh handles, so a fade requested during the last run doesn't
+        // carry over
+        self.fade = FadeHandle::default();
+        self.effects_handle = EffectsHandle::default();
+        self.placement_handle = PlacementHandle::default();
+        self.meter = LevelMeter::default();
+        let source = Processed::new(source, &self.effects, self.effects_handle.clone());
+
+        // out to one channel per speaker, unless the channels go straight
+        // through
+        let source: Box<dyn Source + Send> = match (&self.placement, speakers) {
+            (Placement::Direct, _) | (_, None) => Box::new(source),
+            (placement, Some(speakers)) => Box::new(Panned::new(
+                source,
+                placement,
+                speakers,
+                self.placement_handle.clone(),
+            )),
+        };
+        let source = Fadeable::new(source, self.fade.clone());
+        Ok(Box::new(Metered::new(source, self.meter.clone())))
+    }
 
-        let sample_rate = source.sample_rate();
-        let num_samples = source.count();
-        let raw_duration = (num_samples as f32) / (2. * sample_rate as f32);
-        self.duration = Some(raw_duration - (self.start + self.end));
+    // work out the duration now if it isn't known yet, waiting for the file
+    // to be analyzed
+    pub fn load_now(&mut self) -> Result<(), anyhow::Error> {
+        if self.duration.is_none() {
+            let length = self.file_length().inspect_err(|err| {
+                self.error = Some(err.to_string());
+            })?;
+            self.duration = Some(length - (self.start + self.end));
+            self.loading = false;
+            self.error = None;
+        }
         Ok(())
     }
 
-    pub fn set_start(&mut self, v: f32) -> Result<(), anyhow::Error> {
+    // length of the whole audio file, before trimming. waits for the file to
+    // be analyzed if it hasn't been yet
+    pub fn file_length(&self) -> Result<CueTime, anyhow::Error> {
+        let analysis = audio::analyze_now(Path::new(&self.file_path))?;
+        Ok(exact_length(&analysis.info))
+    }
+
+    // length of the whole audio file, or None while it is still waiting on
+    // the analysis workers
+    pub fn analyzed_file_length(&self) -> Option<Result<CueTime, anyhow::Error>> {
+        match audio::analyze(Path::new(&self.file_path)) {
+            MediaStatus::Ready(analysis) => Some(Ok(exact_length(&analysis.info))),
+            MediaStatus::Failed(err) => Some(Err(anyhow!(err))),
+            MediaStatus::Queued | MediaStatus::Analyzing(_) => None,
+        }
+    }
+
+    // work out the duration from the file's analysis. until that is ready
+    // the cue is loading, and update_media picks it up later
+    fn init_duration(&mut self) -> Result<(), anyhow::Error> {
+        let media = audio::analyze(Path::new(&self.file_path));
+        self.loading = media.is_loading();
+        self.error = None;
+        match media {
+            MediaStatus::Ready(analysis) => {
+                self.duration = Some(exact_length(&analysis.info) - (self.start + self.end));
+                Ok(())
+            }
+            MediaStatus::Failed(err) => {
+                self.duration = None;
+                self.error = Some(err.clone());
+                Err(anyhow!(err))
+            }
+            MediaStatus::Queued | MediaStatus::Analyzing(_) => {
+                self.duration = None;
+                Ok(())
+            }
+        }
+    }
+
+    // point the cue at another file, which then has to be analyzed
+    pub fn set_file_path(&mut self, path: impl Into<String>) {
+        self.file_path = path.into();
+        if let Err(err) = self.init_duration() {
+            warn!("Could not analyze audio for cue {}: {}", self.id, err);
+        }
+    }
+
+    pub fn set_start(&mut self, v: CueTime) -> Result<(), anyhow::Error> {
         Ok(match self.duration {
             Some(d) => {
                 let diff = self.start - v;
@@ -107,7 +313,7 @@ impl AudioCue {
         })
     }
 
-    pub fn set_end(&mut self, v: f32) -> Result<(), anyhow::Error> {
+    pub fn set_end(&mut self, v: CueTime) -> Result<(), anyhow::Error> {
         Ok(match self.duration {
             Some(d) => {
                 let diff = self.end - v;
@@ -121,19 +327,100 @@ impl AudioCue {
         })
     }
 
+    pub fn get_fade_in(&self) -> Fade {
+        self.fade_in
+    }
+    pub fn set_fade_in(&mut self, fade: Fade) {
+        self.fade_in = Fade {
+            length: fade.length.max(CueTime::ZERO),
+            ..fade
+        };
+    }
+
+    pub fn get_fade_out(&self) -> Fade {
+        self.fade_out
+    }
+    pub fn set_fade_out(&mut self, fade: Fade) {
+        self.fade_out = Fade {
+            length: fade.length.max(CueTime::ZERO),
+            ..fade
+        };
+    }
+
+    pub fn get_effects(&self) -> &EffectChain {
+        &self.effects
+    }
+    // takes effect straight away if the cue is playing
+    pub fn set_effects(&mut self, chain: EffectChain) {
+        self.effects = chain;
+        self.effects_handle.set(&self.effects);
+    }
+    // if the cue is playing, its effects move to the new settings over
+    // `length` rather than all at once
+    pub fn ramp_effects(&mut self, chain: EffectChain, length: CueTime) {
+        self.effects = chain;
+        self.effects_handle.ramp_to(&self.effects, length);
+    }
+
+    pub fn get_placement(&self) -> &Placement {
+        &self.placement
+    }
+    // moves the sound straight away if the cue is playing and was placed
+    // when it started
+    pub fn set_placement(&mut self, placement: Placement) {
+        self.placement = placement;
+        self.placement_handle.set(&self.placement);
+    }
+
     pub fn get_volume(&self) -> f32 {
         self.volume
     }
     pub fn set_volume(&mut self, v: f32) -> Result<(), anyhow::Error> {
         if let Some(sink) = &self.sink {
             self.volume = v;
-            sink.set_volume(self.volume);
+            sink.set_volume(self.gain());
 
             Ok(())
         } else {
             Err(anyhow!("Not initialized!"))
         }
     }
+
+    pub fn get_trim(&self) -> f32 {
+        self.trim
+    }
+    pub fn set_trim(&mut self, db: f32) {
+        self.trim = db;
+        if let Some(sink) = &self.sink {
+            sink.set_volume(self.gain());
+        }
+    }
+
+    // what the sink scales the audio by: the volume, with the trim on top
+    pub fn gain(&self) -> f32 {
+        self.volume * audio::db_to_gain(self.trim)
+    }
+
+    // set the trim so the part of the file that plays comes out at `target`
+    // LUFS, returning the new trim. the file has to have been analyzed
+    // already
+    pub fn normalize(&mut self, target: f32) -> Result<f32, anyhow::Error> {
+        match audio::analyze(Path::new(&self.file_path)) {
+            MediaStatus::Ready(analysis) => {
+                let end = exact_length(&analysis.info) - self.end;
+                let loudness = analysis
+                    .info
+                    .loudness_between(self.start, end)
+                    .ok_or_else(|| anyhow!("Audio cue {} is silent", self.id))?;
+                self.set_trim(target - loudness);
+                Ok(self.trim)
+            }
+            MediaStatus::Failed(err) => Err(anyhow!(err)),
+            MediaStatus::Queued | MediaStatus::Analyzing(_) => {
+                Err(anyhow!("Audio cue {} is still being analyzed", self.id))
+            }
+        }
+    }
 }
 
 impl PartialEq for AudioCue {
@@ -154,9 +441,29 @@ impl Clone for AudioCue {
                     file_path: self.file_path.clone(),
                     start: self.start,
                     end: self.end,
+                    fade_in: self.fade_in,
+                    fade_out: self.fade_out,
                     volume: self.volume,
-                    sink: Some(Box::new(Sink::connect_new(&am.stream.mixer()))),
+                    trim: self.trim,
+                    effects: self.effects.clone(),
+                    placement: self.placement.clone(),
+                    enabled: self.enabled,
+                    armed: self.armed,
+                    hotkey: self.hotkey.clone(),
+                    color: self.color,
+                    flagged: self.flagged,
+                    pre_wait: self.pre_wait,
+                    continue_mode: self.continue_mode,
+                    notes: self.notes.clone(),
+                    sink: Some(Box::new(Sink::connect_new(&am.mixer))),
+                    output: Some(am.mixer.clone()),
                     duration: self.duration,
+                    loading: self.loading,
+                    error: self.error.clone(),
+                    fade: FadeHandle::default(),
+                    effects_handle: EffectsHandle::default(),
+                    placement_handle: PlacementHandle::default(),
+                    meter: LevelMeter::default(),
                 }
             } else {
                 Self {
@@ -164,10 +471,30 @@ impl Clone for AudioCue {
                     name: self.name.clone(),
                     file_path: self.file_path.clone(),
                     sink: None,
+                    output: None,
                     start: self.start,
                     end: self.end,
+                    fade_in: self.fade_in,
+                    fade_out: self.fade_out,
                     volume: self.volume,
+                    trim: self.trim,
+                    effects: self.effects.clone(),
+                    placement: self.placement.clone(),
+                    enabled: self.enabled,
+                    armed: self.armed,
+                    hotkey: self.hotkey.clone(),
+                    color: self.color,
+                    flagged: self.flagged,
+                    pre_wait: self.pre_wait,
+                    continue_mode: self.continue_mode,
+                    notes: self.notes.clone(),
                     duration: self.duration,
+                    loading: self.loading,
+                    error: self.error.clone(),
+                    fade: FadeHandle::default(),
+                    effects_handle: EffectsHandle::default(),
+                    placement_handle: PlacementHandle::default(),
+                    meter: LevelMeter::default(),
                 }
             }
         })
@@ -193,7 +520,10 @@ impl Cue for AudioCue {
             debug!("Audio cue {} already initted!", self.id)
         }
         audio::AUDIO_MANAGER.with_borrow(|am| match am {
-            Some(am) => self.sink = Some(Box::new(Sink::connect_new(&am.stream.mixer()))),
+            Some(am) => {
+                self.sink = Some(Box::new(Sink::connect_new(&am.mixer)));
+                self.output = Some(am.mixer.clone());
+            }
             None => {
                 error!(
                     "Could not init audio cue {}, AudioManager not intialized!",
@@ -201,23 +531,13 @@ impl Cue for AudioCue {
                 )
             }
         });
-        // intialize duration, read from file
-        match self.init_duration() {
-            Err(err) => {
-                if let Some(_) = err.downcast_ref::<std::io::Error>() {
-                    warn!(
-                        "Tried to init audio cue {} with invalid audio file",
-                        self.id
-                    )
-                } else {
-                    error!(
-                        "Could not init audio cue {}, duration err: {}",
-                        self.id, err
-                    )
-                }
-            }
-            Ok(_) => {}
-        };
+        // intialize duration, once the file has been analyzed
+        if let Err(err) = self.init_duration() {
+            warn!(
+                "Tried to init audio cue {} with invalid audio file: {}",
+                self.id, err
+            )
+        }
     }
 
     fn get_id(&self) -> String {
@@ -238,8 +558,92 @@ impl Cue for AudioCue {
     fn type_str_short(&self) -> String {
         "Aud".to_string()
     }
+    fn get_color(&self) -> Option<[u8; 3]> {
+        self.color
+    }
+    fn set_color(&mut self, color: Option<[u8; 3]>) -> () {
+        self.color = color;
+    }
+    fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+    fn set_flagged(&mut self, to: bool) -> () {
+        self.flagged = to;
+    }
+    fn get_notes(&self) -> String {
+        self.notes.clone()
+    }
+    fn set_notes(&mut self, notes: &str) -> () {
+        self.notes = notes.to_string();
+    }
+    fn get_pre_wait(&self) -> CueTime {
+        self.pre_wait
+    }
+    fn set_pre_wait(&mut self, pre_wait: CueTime) -> () {
+        self.pre_wait = pre_wait.max(CueTime::ZERO);
+    }
+    fn get_continue(&self) -> ContinueMode {
+        self.continue_mode
+    }
+    fn set_continue(&mut self, mode: ContinueMode) -> () {
+        self.continue_mode = mode;
+    }
+
+    fn get_attributes(&self) -> CueTypeAttributes {
+        CueTypeAttributes {
+            runnable: true,
+            timed: true,
+            timed_bounded: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_media(&self) -> Vec<&String> {
+        vec![&self.file_path]
+    }
+    fn get_media_mut(&mut self) -> Vec<&mut String> {
+        vec![&mut self.file_path]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, to: bool) -> () {
+        self.enabled = to;
+    }
+    fn is_armed(&self) -> bool {
+        self.armed
+    }
+    fn set_armed(&mut self, to: bool) -> () {
+        self.armed = to;
+    }
+    fn get_hotkey(&self) -> Option<&String> {
+        self.hotkey.as_ref()
+    }
+    fn set_hotkey(&mut self, key: Option<String>) -> () {
+        self.hotkey = key;
+    }
+
+    // a missing or undecodable file can't be played
+    fn is_errored(&self) -> bool {
+        self.error.is_some()
+    }
+    fn is_loading(&self) -> bool {
+        self.loading
+    }
+    fn update_media(&mut self) -> () {
+        if self.loading {
+            if let Err(err) = self.init_duration() {
+                warn!("Could not analyze audio for cue {}: {}", self.id, err);
+            }
+        }
+    }
 
     fn go(&mut self) -> () {
+        if !self.can_fire() {
+            debug!("Audio cue {} cannot fire", self.id);
+            return;
+        }
         if let Err(err) = self.play_audio() {
             error!("Error playing audio cue {}: {}", self.id, err);
         }
@@ -260,8 +664,15 @@ impl Cue for AudioCue {
     }
 
     fn stop(&mut self) -> () {
-        if let Some(sink) = &self.sink {
-            sink.clear();
+        // swapping in a new sink doesn't wait on the output to get around to
+        // the old one, as clearing it would
+        match &self.output {
+            Some(mixer) => self.sink = Some(Box::new(Sink::connect_new(mixer))),
+            None => {
+                if let Some(sink) = &self.sink {
+                    sink.clear();
+                }
+            }
         }
     }
 
@@ -275,6 +686,29 @@ impl Cue for AudioCue {
         }
     }
 
+    fn fade_out(&mut self, length: CueTime) -> () {
+        match self.running() {
+            CueRunning::Running => self.fade.fade_to(0., length, true),
+            // a paused cue would never get through its fade
+            CueRunning::Paused => self.stop(),
+            CueRunning::Stopped => {}
+        }
+    }
+
+    fn levels(&self, frame: u64) -> Option<Vec<MeterReading>> {
+        match self.running() {
+            CueRunning::Stopped => None,
+            // the sink applies the volume after the meter
+            _ => Some(
+                self.meter
+                    .read(frame)
+                    .into_iter()
+                    .map(|r| r.scaled(self.gain()))
+                    .collect(),
+            ),
+        }
+    }
+
     fn length(&self) -> Option<CueTime> {
         self.duration
     }
@@ -282,7 +716,7 @@ impl Cue for AudioCue {
     fn elapsed(&self) -> Option<CueTime> {
         if let Some(sink) = &self.sink {
             if !sink.empty() {
-                Some(sink.get_pos().as_secs_f32())
+                Some(sink.get_pos().into())
             } else {
                 None
             }
@@ -294,7 +728,7 @@ impl Cue for AudioCue {
     fn remaining(&self) -> Option<CueTime> {
         if let Some(sink) = &self.sink {
             if let Some(dur) = self.duration {
-                Some(dur - sink.get_pos().as_secs_f32())
+                Some(dur - CueTime::from(sink.get_pos()))
             } else {
                 None
             }
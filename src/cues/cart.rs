@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Cue, CueRunning, MultitypeCue};
+
+// a cue sitting in one cell of a cart. the cue's own hotkey presses the
+// cell, starting or stopping it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CartCell {
+    pub row: usize,
+    pub column: usize,
+    pub cue: MultitypeCue,
+}
+
+// a grid of cues that are fired on demand rather than in order, for sound
+// effect boards and the like
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cart {
+    pub name: String,
+    pub rows: usize,
+    pub columns: usize,
+    cells: Vec<CartCell>,
+}
+
+impl Cart {
+    pub fn new(name: impl Into<String>, rows: usize, columns: usize) -> Self {
+        Self {
+            name: name.into(),
+            rows,
+            columns,
+            cells: vec![],
+        }
+    }
+
+    pub fn init_cues(&mut self) {
+        for cell in &mut self.cells {
+            cell.cue.init();
+        }
+    }
+
+    pub fn cells(&self) -> std::slice::Iter<'_, CartCell> {
+        self.cells.iter()
+    }
+
+    pub fn cells_mut(&mut self) -> std::slice::IterMut<'_, CartCell> {
+        self.cells.iter_mut()
+    }
+
+    // index of the cell at a grid position, if one has been filled in
+    pub fn cell_index(&self, row: usize, column: usize) -> Option<usize> {
+        self.cells
+            .iter()
+            .position(|cell| cell.row == row && cell.column == column)
+    }
+
+    pub fn cell(&self, index: usize) -> &CartCell {
+        &self.cells[index]
+    }
+
+    pub fn cell_mut(&mut self, index: usize) -> &mut CartCell {
+        &mut self.cells[index]
+    }
+
+    // put a cue in a cell, replacing whatever was there, and return the
+    // cell's index
+    pub fn set_cell(&mut self, row: usize, column: usize, mut cue: MultitypeCue) -> usize {
+        cue.init();
+        match self.cell_index(row, column) {
+            Some(i) => {
+                self.cells[i].cue.stop();
+                self.cells[i].cue = cue;
+                i
+            }
+            None => {
+                self.cells.push(CartCell { row, column, cue });
+                self.cells.len() - 1
+            }
+        }
+    }
+
+    pub fn remove_cell(&mut self, index: usize) {
+        let mut cell = self.cells.remove(index);
+        cell.cue.stop();
+    }
+
+    pub fn get_new_cue_id(&self) -> u64 {
+        self.cells
+            .iter()
+            .filter_map(|cell| cell.cue.get_id_num())
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    // first grid position without a cue, reading across then down
+    pub fn first_empty_cell(&self) -> Option<(usize, usize)> {
+        (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (row, column)))
+            .find(|&(row, column)| self.cell_index(row, column).is_none())
+    }
+
+    // cells that fall outside the grid after it has been shrunk
+    pub fn hidden_cells(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| cell.row >= self.rows || cell.column >= self.columns)
+            .count()
+    }
+
+    // pressing a cell starts its cue, or stops it if it is already going.
+    // returns true if the cue was started
+    pub fn toggle(&mut self, index: usize) -> bool {
+        let cue = &mut self.cells[index].cue;
+        if cue.running() == CueRunning::Stopped {
+            cue.go();
+            true
+        } else {
+            cue.stop();
+            false
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for cell in &mut self.cells {
+            cell.cue.stop();
+        }
+    }
+}
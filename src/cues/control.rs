@@ -0,0 +1,182 @@
+use log::debug;
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    add_common_lua_fields, add_common_lua_methods, ContinueMode, Cue, CueTime, CueTypeAttributes,
+};
+
+// what a control cue does to its target
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ControlAction {
+    Start,
+    Stop,
+    Pause,
+    Resume,
+}
+
+impl ControlAction {
+    pub const ITER: [ControlAction; 4] = [
+        ControlAction::Start,
+        ControlAction::Stop,
+        ControlAction::Pause,
+        ControlAction::Resume,
+    ];
+}
+
+impl std::fmt::Display for ControlAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// a cue that starts, stops, pauses or resumes another cue. the target is
+// either a cue ID in the same list, or `list name:cue ID` for a cue in
+// another list
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ControlCue {
+    pub id: String,
+    pub name: String,
+    enabled: bool,
+    armed: bool,
+    #[serde(default)]
+    hotkey: Option<String>,
+    #[serde(default)]
+    color: Option<[u8; 3]>,
+    #[serde(default)]
+    flagged: bool,
+    #[serde(default)]
+    pre_wait: CueTime,
+    #[serde(default)]
+    continue_mode: ContinueMode,
+    #[serde(default)]
+    notes: String,
+    pub action: ControlAction,
+    pub target: String,
+}
+
+impl ControlCue {
+    pub fn with_id(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: "New control cue".to_string(),
+            enabled: true,
+            armed: true,
+            hotkey: None,
+            color: None,
+            flagged: false,
+            pre_wait: CueTime::ZERO,
+            continue_mode: ContinueMode::DoNotContinue,
+            notes: String::new(),
+            action: ControlAction::Start,
+            target: "".to_string(),
+        }
+    }
+}
+
+#[typetag::serde]
+impl Cue for ControlCue {
+    fn init(&mut self) -> () {}
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+    fn set_id(&mut self, new_id: &str) -> () {
+        self.id = new_id.to_string();
+    }
+    fn set_name(&mut self, new_name: &str) -> () {
+        self.name = new_name.to_string();
+    }
+    fn type_str_full(&self) -> String {
+        "Control".to_string()
+    }
+    fn type_str_short(&self) -> String {
+        "Ctrl".to_string()
+    }
+    fn get_color(&self) -> Option<[u8; 3]> {
+        self.color
+    }
+    fn set_color(&mut self, color: Option<[u8; 3]>) -> () {
+        self.color = color;
+    }
+    fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+    fn set_flagged(&mut self, to: bool) -> () {
+        self.flagged = to;
+    }
+    fn get_notes(&self) -> String {
+        self.notes.clone()
+    }
+    fn set_notes(&mut self, notes: &str) -> () {
+        self.notes = notes.to_string();
+    }
+    fn get_pre_wait(&self) -> CueTime {
+        self.pre_wait
+    }
+    fn set_pre_wait(&mut self, pre_wait: CueTime) -> () {
+        self.pre_wait = pre_wait.max(CueTime::ZERO);
+    }
+    fn get_continue(&self) -> ContinueMode {
+        self.continue_mode
+    }
+    fn set_continue(&mut self, mode: ContinueMode) -> () {
+        self.continue_mode = mode;
+    }
+    fn get_attributes(&self) -> CueTypeAttributes {
+        CueTypeAttributes {
+            runnable: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_referents(&self) -> Vec<&String> {
+        vec![&self.target]
+    }
+    fn get_control(&self) -> Option<(ControlAction, &String)> {
+        Some((self.action, &self.target))
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, to: bool) -> () {
+        self.enabled = to;
+    }
+    fn is_armed(&self) -> bool {
+        self.armed
+    }
+    fn set_armed(&mut self, to: bool) -> () {
+        self.armed = to;
+    }
+    fn get_hotkey(&self) -> Option<&String> {
+        self.hotkey.as_ref()
+    }
+    fn set_hotkey(&mut self, key: Option<String>) -> () {
+        self.hotkey = key;
+    }
+
+    // the action itself is carried out by the project, which can see the
+    // target
+    fn go(&mut self) -> () {
+        debug!("Control cue {}: {} {}", self.id, self.action, self.target)
+    }
+}
+
+impl LuaUserData for ControlCue {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        add_common_lua_fields(fields);
+        fields.add_field_method_get("target", |_, this| Ok(this.target.clone()));
+        fields.add_field_method_set("target", |_, this, new_target: String| {
+            Ok(this.target = new_target)
+        });
+        fields.add_field_method_get("action", |_, this| Ok(this.action.to_string()));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        add_common_lua_methods(methods)
+    }
+}
@@ -2,12 +2,22 @@ use log::{debug, info};
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use super::{add_common_lua_fields, add_common_lua_methods, Cue, CueTypeAttributes};
+use super::{
+    add_common_lua_fields, add_common_lua_methods, ContinueMode, Cue, CueTime, CueTypeAttributes,
+};
 
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct RemarkCue {
     pub id: String,
     pub name: String,
+    #[serde(default)]
+    color: Option<[u8; 3]>,
+    #[serde(default)]
+    flagged: bool,
+    #[serde(default)]
+    pre_wait: CueTime,
+    #[serde(default)]
+    continue_mode: ContinueMode,
     pub notes: String,
 }
 
@@ -16,6 +26,10 @@ impl RemarkCue {
         Self {
             id: id.into(),
             name: "New remark cue".to_string(),
+            color: None,
+            flagged: false,
+            pre_wait: CueTime::ZERO,
+            continue_mode: ContinueMode::DoNotContinue,
             notes: "".to_string(),
         }
     }
@@ -43,6 +57,36 @@ impl Cue for RemarkCue {
     fn type_str_short(&self) -> String {
         "Rmk".to_string()
     }
+    fn get_color(&self) -> Option<[u8; 3]> {
+        self.color
+    }
+    fn set_color(&mut self, color: Option<[u8; 3]>) -> () {
+        self.color = color;
+    }
+    fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+    fn set_flagged(&mut self, to: bool) -> () {
+        self.flagged = to;
+    }
+    fn get_notes(&self) -> String {
+        self.notes.clone()
+    }
+    fn set_notes(&mut self, notes: &str) -> () {
+        self.notes = notes.to_string();
+    }
+    fn get_pre_wait(&self) -> CueTime {
+        self.pre_wait
+    }
+    fn set_pre_wait(&mut self, pre_wait: CueTime) -> () {
+        self.pre_wait = pre_wait.max(CueTime::ZERO);
+    }
+    fn get_continue(&self) -> ContinueMode {
+        self.continue_mode
+    }
+    fn set_continue(&mut self, mode: ContinueMode) -> () {
+        self.continue_mode = mode;
+    }
     fn go(&mut self) -> () {
         debug!("Remark {}", self.name)
     }
@@ -51,10 +95,6 @@ impl Cue for RemarkCue {
 impl LuaUserData for RemarkCue {
     fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
         add_common_lua_fields(fields);
-        fields.add_field_method_get("notes", |_, this| Ok(this.notes.clone()));
-        fields.add_field_method_set("notes", |_, this, new_notes: String| {
-            Ok(this.notes = new_notes)
-        });
     }
 
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
@@ -62,12 +102,24 @@ impl LuaUserData for RemarkCue {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct BonkCue {
     id: String,
     name: String,
     enabled: bool,
     armed: bool,
+    #[serde(default)]
+    hotkey: Option<String>,
+    #[serde(default)]
+    color: Option<[u8; 3]>,
+    #[serde(default)]
+    flagged: bool,
+    #[serde(default)]
+    pre_wait: CueTime,
+    #[serde(default)]
+    continue_mode: ContinueMode,
+    #[serde(default)]
+    notes: String,
     pub ctr: u64,
 }
 
@@ -79,6 +131,12 @@ impl BonkCue {
             ctr: 0,
             enabled: true,
             armed: true,
+            hotkey: None,
+            color: None,
+            flagged: false,
+            pre_wait: CueTime::ZERO,
+            continue_mode: ContinueMode::DoNotContinue,
+            notes: String::new(),
         }
     }
 }
@@ -105,6 +163,36 @@ impl Cue for BonkCue {
     fn type_str_short(&self) -> String {
         "Bonk".to_string()
     }
+    fn get_color(&self) -> Option<[u8; 3]> {
+        self.color
+    }
+    fn set_color(&mut self, color: Option<[u8; 3]>) -> () {
+        self.color = color;
+    }
+    fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+    fn set_flagged(&mut self, to: bool) -> () {
+        self.flagged = to;
+    }
+    fn get_notes(&self) -> String {
+        self.notes.clone()
+    }
+    fn set_notes(&mut self, notes: &str) -> () {
+        self.notes = notes.to_string();
+    }
+    fn get_pre_wait(&self) -> CueTime {
+        self.pre_wait
+    }
+    fn set_pre_wait(&mut self, pre_wait: CueTime) -> () {
+        self.pre_wait = pre_wait.max(CueTime::ZERO);
+    }
+    fn get_continue(&self) -> ContinueMode {
+        self.continue_mode
+    }
+    fn set_continue(&mut self, mode: ContinueMode) -> () {
+        self.continue_mode = mode;
+    }
     fn get_attributes(&self) -> CueTypeAttributes {
         let mut a = CueTypeAttributes::default();
         a.runnable = true;
@@ -127,6 +215,12 @@ impl Cue for BonkCue {
     fn set_armed(&mut self, to: bool) -> () {
         self.armed = to;
     }
+    fn get_hotkey(&self) -> Option<&String> {
+        self.hotkey.as_ref()
+    }
+    fn set_hotkey(&mut self, key: Option<String>) -> () {
+        self.hotkey = key;
+    }
     fn is_errored(&self) -> bool {
         false
     }
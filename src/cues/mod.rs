@@ -1,12 +1,20 @@
 mod audio;
+mod cart;
+mod control;
 mod cues;
+mod time;
 
 pub use audio::AudioCue;
+pub use cart::{Cart, CartCell};
+pub use control::{ControlAction, ControlCue};
 pub use cues::{BonkCue, RemarkCue};
+pub use time::{CueTime, TimeFormat};
+
+use crate::audio::MeterReading;
 
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::{cmp::max, path::PathBuf};
+use std::cmp::max;
 
 /*******************************************************************************
 * 0. NOTE TO FUTURE MAINTAINERS:                                               *
@@ -67,6 +75,7 @@ macro_rules! call_cue_enum_inner_matchblock {
             MultitypeCue::Remark(c) => c.$method($($x,)*),
             MultitypeCue::Bonk(c)   => c.$method($($x,)*),
             MultitypeCue::Audio(c)   => c.$method($($x,)*),
+            MultitypeCue::Control(c) => c.$method($($x,)*),
         }
     }
 }
@@ -88,9 +97,46 @@ pub trait Cue {
         CueTypeAttributes::default()
     }
 
+    // color the cue is marked with in the list, as 8-bit sRGB
+    fn get_color(&self) -> Option<[u8; 3]> {
+        None
+    }
+    fn set_color(&mut self, _color: Option<[u8; 3]>) -> () {}
+    // flagged cues are ones to come back to, e.g. after tech
+    fn is_flagged(&self) -> bool {
+        false
+    }
+    fn set_flagged(&mut self, _to: bool) -> () {}
+    fn get_notes(&self) -> String {
+        String::new()
+    }
+    fn set_notes(&mut self, _notes: &str) -> () {}
+
+    // time between the cue being triggered and it actually firing
+    fn get_pre_wait(&self) -> CueTime {
+        CueTime::ZERO
+    }
+    fn set_pre_wait(&mut self, _pre_wait: CueTime) -> () {}
+    fn get_continue(&self) -> ContinueMode {
+        ContinueMode::DoNotContinue
+    }
+    fn set_continue(&mut self, _mode: ContinueMode) -> () {}
+
     fn get_referents(&self) -> Vec<&String> {
         Vec::new()
     }
+    // paths of every media file this cue plays
+    fn get_media(&self) -> Vec<&String> {
+        Vec::new()
+    }
+    fn get_media_mut(&mut self) -> Vec<&mut String> {
+        Vec::new()
+    }
+    // action this cue takes on the cue it refers to, carried out by the
+    // project when the cue fires
+    fn get_control(&self) -> Option<(ControlAction, &String)> {
+        None
+    }
 
     fn is_enabled(&self) -> bool {
         false
@@ -100,9 +146,20 @@ pub trait Cue {
         false
     }
     fn set_armed(&mut self, _to: bool) -> () {}
+    // name of the key that fires this cue on its own, as understood by egui
+    fn get_hotkey(&self) -> Option<&String> {
+        None
+    }
+    fn set_hotkey(&mut self, _key: Option<String>) -> () {}
     fn is_errored(&self) -> bool {
         false
     }
+    // media is still being analyzed in the background
+    fn is_loading(&self) -> bool {
+        false
+    }
+    // pick up the results of media analysis that has finished since
+    fn update_media(&mut self) -> () {}
     fn can_fire(&self) -> bool {
         self.is_enabled() && self.is_armed() && !self.is_errored()
     }
@@ -113,6 +170,16 @@ pub trait Cue {
     }
     fn stop(&mut self) -> () {}
     fn set_paused(&mut self, _pu: bool) -> () {}
+    // fade to silence over `length` seconds and then stop. cues that can't
+    // fade just stop
+    fn fade_out(&mut self, _length: CueTime) -> () {
+        self.stop()
+    }
+    // level of each channel this cue is playing since the last frame, or
+    // None if it isn't playing anything
+    fn levels(&self, _frame: u64) -> Option<Vec<MeterReading>> {
+        None
+    }
 
     fn length(&self) -> Option<CueTime> {
         None
@@ -135,12 +202,13 @@ pub trait Cue {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum MultitypeCue {
     // modify this when adding/deleting cues
     Remark(RemarkCue),
     Bonk(BonkCue),
     Audio(AudioCue),
+    Control(ControlCue),
 }
 
 #[typetag::serde]
@@ -173,9 +241,48 @@ impl Cue for MultitypeCue {
     call_cue_enum_inner!(
         fn get_attributes(&self) -> CueTypeAttributes;
     );
+    call_cue_enum_inner!(
+        fn get_color(&self) -> Option<[u8; 3]>;
+    );
+    call_cue_enum_inner!(
+        fn set_color(&mut self, color: Option<[u8; 3]>) -> ();
+    );
+    call_cue_enum_inner!(
+        fn is_flagged(&self) -> bool;
+    );
+    call_cue_enum_inner!(
+        fn set_flagged(&mut self, to: bool) -> ();
+    );
+    call_cue_enum_inner!(
+        fn get_notes(&self) -> String;
+    );
+    call_cue_enum_inner!(
+        fn set_notes(&mut self, notes: &str) -> ();
+    );
+    call_cue_enum_inner!(
+        fn get_pre_wait(&self) -> CueTime;
+    );
+    call_cue_enum_inner!(
+        fn set_pre_wait(&mut self, pre_wait: CueTime) -> ();
+    );
+    call_cue_enum_inner!(
+        fn get_continue(&self) -> ContinueMode;
+    );
+    call_cue_enum_inner!(
+        fn set_continue(&mut self, mode: ContinueMode) -> ();
+    );
     call_cue_enum_inner!(
         fn get_referents(&self) -> Vec<&String>;
     );
+    call_cue_enum_inner!(
+        fn get_media(&self) -> Vec<&String>;
+    );
+    call_cue_enum_inner!(
+        fn get_media_mut(&mut self) -> Vec<&mut String>;
+    );
+    call_cue_enum_inner!(
+        fn get_control(&self) -> Option<(ControlAction, &String)>;
+    );
     call_cue_enum_inner!(
         fn is_enabled(&self) -> bool;
     );
@@ -188,9 +295,21 @@ impl Cue for MultitypeCue {
     call_cue_enum_inner!(
         fn set_armed(&mut self, _to: bool) -> ();
     );
+    call_cue_enum_inner!(
+        fn get_hotkey(&self) -> Option<&String>;
+    );
+    call_cue_enum_inner!(
+        fn set_hotkey(&mut self, key: Option<String>) -> ();
+    );
     call_cue_enum_inner!(
         fn is_errored(&self) -> bool;
     );
+    call_cue_enum_inner!(
+        fn is_loading(&self) -> bool;
+    );
+    call_cue_enum_inner!(
+        fn update_media(&mut self) -> ();
+    );
     call_cue_enum_inner!(
         fn can_fire(&self) -> bool;
     );
@@ -206,6 +325,12 @@ impl Cue for MultitypeCue {
     call_cue_enum_inner!(
         fn set_paused(&mut self, _pu: bool) -> ();
     );
+    call_cue_enum_inner!(
+        fn fade_out(&mut self, length: CueTime) -> ();
+    );
+    call_cue_enum_inner!(
+        fn levels(&self, frame: u64) -> Option<Vec<MeterReading>>;
+    );
     call_cue_enum_inner!(
         fn length(&self) -> Option<CueTime>;
     );
@@ -230,31 +355,27 @@ impl IntoLua for MultitypeCue {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct Project {
+pub struct CueList {
     pub name: String,
-    pub path: Option<PathBuf>,
-
-    pub cues: CueList,
-}
+    // name of the key that fires this list, as understood by egui
+    #[serde(default)]
+    pub go_key: Option<String>,
 
-impl Default for Project {
-    fn default() -> Self {
-        Self {
-            name: String::from("Untitled"),
-            path: None,
-            cues: CueList::new(),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct CueList {
     list: Vec<MultitypeCue>,
+
+    // every list has its own playhead, so they can run side by side
+    #[serde(skip)]
+    pub playhead: Option<usize>,
 }
 
 impl CueList {
-    pub fn new() -> Self {
-        Self { list: vec![] }
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            go_key: None,
+            list: vec![],
+            playhead: None,
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -306,6 +427,47 @@ impl CueList {
         None
     }
 
+    pub fn get(&self, index: usize) -> Option<&MultitypeCue> {
+        self.list.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut MultitypeCue> {
+        self.list.get_mut(index)
+    }
+
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.list.iter().position(|cue| cue.get_id() == id)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, MultitypeCue> {
+        self.list.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, MultitypeCue> {
+        self.list.iter_mut()
+    }
+
+    // move the playhead, keeping it inside the list
+    pub fn set_playhead(&mut self, index: Option<usize>) {
+        self.playhead = index.filter(|&i| i < self.list.len());
+    }
+
+    // indices of every cue that is currently running or paused
+    pub fn active_cues(&self) -> Vec<usize> {
+        self.list
+            .iter()
+            .enumerate()
+            .filter(|(_, cue)| cue.running() != CueRunning::Stopped)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn stop_all(&mut self) {
+        for cue in &mut self.list {
+            cue.stop();
+        }
+    }
+
     pub fn move_cue(&mut self, mve: usize, to: usize) -> () {
         // move "mve" cue to "to" cue
         if mve < to {
@@ -385,9 +547,25 @@ impl Default for CueTypeAttributes {
         }
     }
 }
-// Possibly change time representation later.
-// For now this is a float of seconds.
-pub type CueTime = f32;
+// what a cue does to the one after it in the list once it has been
+// triggered
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ContinueMode {
+    #[default]
+    DoNotContinue,
+    // trigger the next cue as soon as this one fires
+    AutoContinue,
+    // trigger the next cue once this one has finished
+    AutoFollow,
+}
+
+impl ContinueMode {
+    pub const ITER: [(ContinueMode, &'static str); 3] = [
+        (ContinueMode::DoNotContinue, "Do not continue"),
+        (ContinueMode::AutoContinue, "Auto-continue"),
+        (ContinueMode::AutoFollow, "Auto-follow"),
+    ];
+}
 
 impl LuaUserData for Box<dyn Cue> {
     fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
@@ -409,11 +587,24 @@ impl LuaUserData for Box<dyn Cue> {
         fields.add_field_method_get("errored", |_, this| Ok(this.is_errored()));
         fields.add_field_method_get("can_fire", |_, this| Ok(this.can_fire()));
         fields.add_field_method_get("running", |_, this| Ok(this.running()));
+        fields.add_field_method_get("flagged", |_, this| Ok(this.is_flagged()));
+        fields.add_field_method_set("flagged", |_, this, flagged: bool| {
+            Ok(this.set_flagged(flagged))
+        });
+        fields.add_field_method_get("notes", |_, this| Ok(this.get_notes()));
+        fields.add_field_method_set("notes", |_, this, notes: String| Ok(this.set_notes(&notes)));
+        fields.add_field_method_get("pre_wait", |_, this| Ok(this.get_pre_wait()));
+        fields.add_field_method_set("pre_wait", |_, this, pre_wait: CueTime| {
+            Ok(this.set_pre_wait(pre_wait))
+        });
     }
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
         methods.add_method_mut("go", |_, this, ()| Ok(this.go()));
         methods.add_method_mut("stop", |_, this, ()| Ok(this.stop()));
         methods.add_method_mut("set_paused", |_, this, x: bool| Ok(this.set_paused(x)));
+        methods.add_method_mut("fade_out", |_, this, length: CueTime| {
+            Ok(this.fade_out(length))
+        });
     }
 }
 
@@ -436,9 +627,22 @@ pub fn add_common_lua_fields<Q: Cue, F: LuaUserDataFields<Q>>(fields: &mut F) {
     fields.add_field_method_get("errored", |_, this| Ok(this.is_errored()));
     fields.add_field_method_get("can_fire", |_, this| Ok(this.can_fire()));
     fields.add_field_method_get("running", |_, this| Ok(this.running()));
+    fields.add_field_method_get("flagged", |_, this| Ok(this.is_flagged()));
+    fields.add_field_method_set("flagged", |_, this, flagged: bool| {
+        Ok(this.set_flagged(flagged))
+    });
+    fields.add_field_method_get("notes", |_, this| Ok(this.get_notes()));
+    fields.add_field_method_set("notes", |_, this, notes: String| Ok(this.set_notes(&notes)));
+    fields.add_field_method_get("pre_wait", |_, this| Ok(this.get_pre_wait()));
+    fields.add_field_method_set("pre_wait", |_, this, pre_wait: CueTime| {
+        Ok(this.set_pre_wait(pre_wait))
+    });
 }
 pub fn add_common_lua_methods<Q: Cue, M: LuaUserDataMethods<Q>>(methods: &mut M) {
     methods.add_method_mut("go", |_, this, ()| Ok(this.go()));
     methods.add_method_mut("stop", |_, this, ()| Ok(this.stop()));
     methods.add_method_mut("set_paused", |_, this, x: bool| Ok(this.set_paused(x)));
+    methods.add_method_mut("fade_out", |_, this, length: CueTime| {
+        Ok(this.fade_out(length))
+    });
 }
@@ -0,0 +1,310 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    time::Duration,
+};
+
+use mlua::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+// subdivisions of a beat when showing beats
+const TICKS_PER_BEAT: i64 = 960;
+
+// a time in a show, to the nanosecond. kept as a whole number so that start,
+// end and length math doesn't drift the way float seconds do, and so it is
+// exact to the sample at any rate. negative times are allowed, for
+// differences
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CueTime(i64);
+
+// how times are shown and typed in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimeFormat {
+    // minutes and seconds to the millisecond, as mm:ss.mmm, with hours in
+    // front once there are any
+    #[default]
+    Clock,
+    // SMPTE timecode, as hh:mm:ss:ff at a whole number of frames a second
+    Smpte {
+        fps: u32,
+    },
+    // bars, beats and ticks counted from zero at a tempo, as bars.beats.ticks
+    // with 960 ticks to the beat
+    Beats {
+        bpm: f64,
+        per_bar: u32,
+    },
+}
+
+impl TimeFormat {
+    // one of each kind, as the format starts out when switched to it
+    pub fn kinds() -> [TimeFormat; 3] {
+        [
+            TimeFormat::Clock,
+            TimeFormat::Smpte { fps: 30 },
+            TimeFormat::Beats {
+                bpm: 120.,
+                per_bar: 4,
+            },
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeFormat::Clock => "Minutes & Seconds",
+            TimeFormat::Smpte { .. } => "SMPTE",
+            TimeFormat::Beats { .. } => "Beats",
+        }
+    }
+}
+
+impl CueTime {
+    pub const ZERO: CueTime = CueTime(0);
+
+    pub const fn from_nanos(nanos: i64) -> Self {
+        Self(nanos)
+    }
+    pub const fn as_nanos(self) -> i64 {
+        self.0
+    }
+    pub const fn from_millis(millis: i64) -> Self {
+        Self(millis * 1_000_000)
+    }
+
+    // to the nearest nanosecond. NaN is taken as zero and infinities as the
+    // furthest time there is
+    pub fn from_secs(secs: f64) -> Self {
+        Self((secs * NANOS_PER_SEC as f64).round() as i64)
+    }
+    pub fn from_secs_f32(secs: f32) -> Self {
+        Self::from_secs(secs as f64)
+    }
+    pub fn as_secs(self) -> f64 {
+        self.0 as f64 / NANOS_PER_SEC as f64
+    }
+    pub fn as_secs_f32(self) -> f32 {
+        self.as_secs() as f32
+    }
+
+    // the start of frame `frames` at `rate` frames a second
+    pub fn from_frames(frames: i64, rate: u32) -> Self {
+        Self(div_round(frames as i128 * NANOS_PER_SEC as i128, rate.max(1) as i128) as i64)
+    }
+    // the frame nearest to this time at `rate` frames a second
+    pub fn to_frames(self, rate: u32) -> i64 {
+        div_round(self.0 as i128 * rate as i128, NANOS_PER_SEC as i128) as i64
+    }
+
+    // negative times are cut off at zero
+    pub fn to_duration(self) -> Duration {
+        Duration::from_nanos(self.0.max(0) as u64)
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    pub fn mul_f64(self, factor: f64) -> Self {
+        Self((self.0 as f64 * factor).round() as i64)
+    }
+
+    // like `format`, but clock times keep the microseconds when there are
+    // any, so editing a time doesn't round it to the millisecond
+    pub fn format_precise(self, format: TimeFormat) -> String {
+        let nanos = self.0.unsigned_abs() as i128;
+        if !matches!(format, TimeFormat::Clock) || div_round(nanos, 1000) % 1000 == 0 {
+            return self.format(format);
+        }
+        let sign = if self.is_negative() { "-" } else { "" };
+        clock(sign, nanos, 6)
+    }
+
+    pub fn format(self, format: TimeFormat) -> String {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let nanos = self.0.unsigned_abs() as i128;
+        match format {
+            TimeFormat::Clock => clock(sign, nanos, 3),
+            TimeFormat::Smpte { fps } => {
+                let fps = fps.max(1) as i128;
+                let frames = nanos * fps / NANOS_PER_SEC as i128;
+                let (secs, frames) = (frames / fps, frames % fps);
+                format!(
+                    "{}{:02}:{:02}:{:02}:{:02}",
+                    sign,
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    frames
+                )
+            }
+            TimeFormat::Beats { bpm, per_bar } => {
+                let ticks = (self.abs().as_secs() * bpm / 60. * TICKS_PER_BEAT as f64).round();
+                let ticks = ticks as i64;
+                let beats = ticks / TICKS_PER_BEAT;
+                let per_bar = per_bar.max(1) as i64;
+                format!(
+                    "{}{}.{}.{:03}",
+                    sign,
+                    beats / per_bar,
+                    beats % per_bar,
+                    ticks % TICKS_PER_BEAT
+                )
+            }
+        }
+    }
+
+    // read a time as `format` shows it. plain seconds are always understood
+    pub fn parse(text: &str, format: TimeFormat) -> Option<Self> {
+        let text = text.trim();
+        let (sign, text) = match text.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, text),
+        };
+        let time = match format {
+            TimeFormat::Smpte { fps } if text.matches(':').count() == 3 => {
+                let mut fields = text.split(':').map(|f| f.parse::<u32>().ok());
+                let mut next = || fields.next().flatten();
+                let (h, m, s, f) = (next()?, next()?, next()?, next()?);
+                let fps = fps.max(1);
+                if m >= 60 || s >= 60 || f >= fps {
+                    return None;
+                }
+                let secs = (h as i64 * 60 + m as i64) * 60 + s as i64;
+                Self(secs.checked_mul(NANOS_PER_SEC)?) + Self::from_frames(f as i64, fps)
+            }
+            TimeFormat::Beats { bpm, per_bar } if text.matches('.').count() == 2 => {
+                let mut fields = text.split('.').map(|f| f.parse::<u32>().ok());
+                let mut next = || fields.next().flatten();
+                let (bars, beats, ticks) = (next()?, next()?, next()?);
+                let beats = (bars as f64 * per_bar as f64)
+                    + beats as f64
+                    + ticks as f64 / TICKS_PER_BEAT as f64;
+                Self::from_secs(beats * 60. / bpm)
+            }
+            _ => {
+                // [[h:]m:]s, with the seconds allowed a fraction
+                let mut secs = 0.;
+                for (i, field) in text.rsplit(':').enumerate() {
+                    let value: f64 = field.parse().ok().filter(|v: &f64| *v >= 0.)?;
+                    secs += value * 60f64.powi(i as i32);
+                    if i > 2 {
+                        return None;
+                    }
+                }
+                Self::from_secs(secs)
+            }
+        };
+        Some(Self(time.0 * sign))
+    }
+}
+
+// a / b to the nearest whole number, for positive b
+fn div_round(a: i128, b: i128) -> i128 {
+    (a + b / 2).div_euclid(b)
+}
+
+impl fmt::Display for CueTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.format(TimeFormat::Clock))
+    }
+}
+
+impl From<Duration> for CueTime {
+    fn from(duration: Duration) -> Self {
+        Self(i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX))
+    }
+}
+
+impl Add for CueTime {
+    type Output = CueTime;
+    fn add(self, rhs: CueTime) -> CueTime {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for CueTime {
+    type Output = CueTime;
+    fn sub(self, rhs: CueTime) -> CueTime {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl AddAssign for CueTime {
+    fn add_assign(&mut self, rhs: CueTime) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for CueTime {
+    fn sub_assign(&mut self, rhs: CueTime) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for CueTime {
+    type Output = CueTime;
+    fn neg(self) -> CueTime {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl Sum for CueTime {
+    fn sum<I: Iterator<Item = CueTime>>(iter: I) -> CueTime {
+        iter.fold(CueTime::ZERO, Add::add)
+    }
+}
+
+// written as seconds, which f64 holds to the nanosecond for far longer than
+// any show, so project files stay readable and open in older versions. old
+// files stored f32 seconds, which read the same way
+impl Serialize for CueTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_secs())
+    }
+}
+
+impl<'de> Deserialize<'de> for CueTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(CueTime::from_secs)
+    }
+}
+
+// seconds in Lua, as before
+impl IntoLua for CueTime {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        self.as_secs().into_lua(lua)
+    }
+}
+
+impl FromLua for CueTime {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        f64::from_lua(value, lua).map(CueTime::from_secs)
+    }
+}
+
+// [h:]mm:ss with `digits` places of seconds
+fn clock(sign: &str, nanos: i128, digits: u32) -> String {
+    let unit = 10i128.pow(digits);
+    let ticks = div_round(nanos, NANOS_PER_SEC as i128 / unit);
+    let (secs, fraction) = (ticks / unit, ticks % unit);
+    let (mins, secs) = (secs / 60, secs % 60);
+    let digits = digits as usize;
+    if mins >= 60 {
+        format!(
+            "{}{}:{:02}:{:02}.{:0digits$}",
+            sign,
+            mins / 60,
+            mins % 60,
+            secs,
+            fraction
+        )
+    } else {
+        format!("{}{:02}:{:02}.{:0digits$}", sign, mins, secs, fraction)
+    }
+}
@@ -1,7 +1,10 @@
 pub mod app;
+pub mod args;
 pub mod audio;
 pub mod cli;
 pub mod cues;
+pub mod project;
 
-// these types are in the cues module, but we want to display them as public
-pub use cues::{Cue, CueList, MultitypeCue, Project};
+// these types are in submodules, but we want to display them as public
+pub use cues::{Cue, CueList, MultitypeCue};
+pub use project::Project;
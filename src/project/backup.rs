@@ -0,0 +1,102 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::anyhow;
+use log::{debug, warn};
+
+// write to a temporary file next to `path` and rename it into place, so a
+// crash mid-write can never leave a half-written file behind
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), anyhow::Error> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid save path {}", path.display()))?;
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// folder that backups of the project at `path` are kept in
+pub fn backup_dir(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{} Backups", stem))
+}
+
+// copy the current contents of `path` into the backup folder before it gets
+// overwritten, keeping only the newest `keep` backups
+pub fn rotate_backups(path: &Path, keep: usize) -> Result<(), anyhow::Error> {
+    if keep == 0 || !path.is_file() {
+        return Ok(());
+    }
+
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or_default().to_string_lossy();
+    // timestamps sort the same way as strings, which pruning relies on. two
+    // saves in the same millisecond get a counter, which sorts after the first
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H-%M-%S%.3f");
+    let mut backup = dir.join(format!("{} {}.{}", stem, timestamp, ext));
+    let mut count = 1;
+    while backup.exists() {
+        backup = dir.join(format!("{} {}_{:03}.{}", stem, timestamp, count, ext));
+        count += 1;
+    }
+    fs::copy(path, &backup)?;
+    debug!("Backed up {} to {}", path.display(), backup.display());
+
+    let prefix = format!("{} ", stem);
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with(&prefix))
+        })
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for old in &backups[..excess] {
+        if let Err(err) = fs::remove_file(old) {
+            warn!("Could not remove old backup {}: {}", old.display(), err);
+        }
+    }
+
+    Ok(())
+}
+
+// where autosaves of the project at `path` go. untitled projects are
+// autosaved into cueball's storage folder instead
+pub fn recovery_path(path: Option<&Path>) -> Option<PathBuf> {
+    match path {
+        Some(path) => {
+            let file_name = path.file_name()?.to_string_lossy();
+            Some(path.with_file_name(format!(".{}.recovery", file_name)))
+        }
+        None => eframe::storage_dir("cueball").map(|d| d.join("Untitled.cueball.recovery")),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// whether there is a recovery file for the project at `path` holding changes
+// made after it was last saved
+pub fn recovery_available(path: Option<&Path>) -> Option<PathBuf> {
+    let recovery = recovery_path(path)?;
+    let recovered_at = modified(&recovery)?;
+    match path.and_then(modified) {
+        Some(saved_at) if saved_at >= recovered_at => None,
+        _ => Some(recovery),
+    }
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashSet, fmt::Display, path::Path};
+
+use super::Project;
+use crate::{audio, cues::CueTime, Cue, MultitypeCue};
+
+// a problem with a project file that would keep the show from running cleanly
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProjectProblem {
+    MissingMedia {
+        cue: String,
+        path: String,
+    },
+    UndecodableMedia {
+        cue: String,
+        path: String,
+        error: String,
+    },
+    TrimTooLong {
+        cue: String,
+        trimmed: CueTime,
+        file_length: CueTime,
+    },
+    BrokenReferent {
+        cue: String,
+        referent: String,
+    },
+    DuplicateId {
+        cue: String,
+    },
+    Disabled {
+        cue: String,
+    },
+    Disarmed {
+        cue: String,
+    },
+    OutputUnavailable {
+        cue: String,
+    },
+}
+
+// how far a check that leaves decoding to the analysis workers has got
+#[derive(Clone, Debug, Default)]
+pub struct CheckProgress {
+    pub problems: Vec<ProjectProblem>,
+    // audio files the check needs the length of, and how many of those are
+    // still being analyzed
+    pub media: usize,
+    pub waiting: usize,
+}
+
+impl CheckProgress {
+    pub fn is_done(&self) -> bool {
+        self.waiting == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum ProblemSeverity {
+    // might be intentional, but worth a look before the show
+    Warning,
+    // the show will not run as written
+    Error,
+}
+
+impl ProjectProblem {
+    pub fn severity(&self) -> ProblemSeverity {
+        match self {
+            ProjectProblem::Disabled { .. } | ProjectProblem::Disarmed { .. } => {
+                ProblemSeverity::Warning
+            }
+            _ => ProblemSeverity::Error,
+        }
+    }
+
+    // ID of the cue this problem belongs to, as `list name:cue ID` when the
+    // project has more than one list or any carts
+    pub fn cue(&self) -> &str {
+        match self {
+            ProjectProblem::MissingMedia { cue, .. }
+            | ProjectProblem::UndecodableMedia { cue, .. }
+            | ProjectProblem::TrimTooLong { cue, .. }
+            | ProjectProblem::BrokenReferent { cue, .. }
+            | ProjectProblem::DuplicateId { cue }
+            | ProjectProblem::Disabled { cue }
+            | ProjectProblem::Disarmed { cue }
+            | ProjectProblem::OutputUnavailable { cue } => cue,
+        }
+    }
+}
+
+impl Display for ProjectProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectProblem::MissingMedia { cue, path } if path.is_empty() => {
+                write!(f, "Cue {} has no media file", cue)
+            }
+            ProjectProblem::MissingMedia { cue, path } => {
+                write!(f, "Cue {} is missing media file {}", cue, path)
+            }
+            ProjectProblem::UndecodableMedia { cue, path, error } => {
+                write!(f, "Cue {} could not decode {}: {}", cue, path, error)
+            }
+            ProjectProblem::TrimTooLong {
+                cue,
+                trimmed,
+                file_length,
+            } => write!(
+                f,
+                "Cue {} trims {} from a file only {} long",
+                cue, trimmed, file_length
+            ),
+            ProjectProblem::BrokenReferent { cue, referent } => {
+                write!(f, "Cue {} refers to nonexistent cue {}", cue, referent)
+            }
+            ProjectProblem::DuplicateId { cue } => {
+                write!(f, "More than one cue has ID {}", cue)
+            }
+            ProjectProblem::Disabled { cue } => write!(f, "Cue {} is disabled", cue),
+            ProjectProblem::Disarmed { cue } => write!(f, "Cue {} is disarmed", cue),
+            ProjectProblem::OutputUnavailable { cue } => {
+                write!(f, "Cue {} has no audio output available", cue)
+            }
+        }
+    }
+}
+
+impl Project {
+    // report every problem that would keep this project from running cleanly.
+    // this decodes every media file, so it can take a while on big shows
+    pub fn check(&self) -> Vec<ProjectProblem> {
+        self.run_check(true).problems
+    }
+
+    // the same check without waiting on media, which is queued for the
+    // analysis workers instead. call again until it is done
+    pub fn check_progress(&self) -> CheckProgress {
+        self.run_check(false)
+    }
+
+    fn run_check(&self, wait_for_media: bool) -> CheckProgress {
+        let mut progress = CheckProgress::default();
+        let output_available = audio::AUDIO_MANAGER.with_borrow(|am| am.is_some());
+
+        for (l, list) in self.cue_lists.iter().enumerate() {
+            // cues are named by ID alone until there's more than one list to
+            // tell them apart
+            let qualify = |id: String| {
+                if self.cue_lists.len() > 1 || !self.carts.is_empty() {
+                    format!("{}:{}", list.name, id)
+                } else {
+                    id
+                }
+            };
+
+            for id in duplicate_ids(list.iter()) {
+                progress
+                    .problems
+                    .push(ProjectProblem::DuplicateId { cue: qualify(id) });
+            }
+            for cue in list.iter() {
+                let id = qualify(cue.get_id());
+                self.check_cue(l, cue, id, output_available, wait_for_media, &mut progress);
+            }
+        }
+
+        // control cues in carts refer to the first list, like when they fire
+        for cart in &self.carts {
+            let cues = || cart.cells().map(|cell| &cell.cue);
+            for id in duplicate_ids(cues()) {
+                progress.problems.push(ProjectProblem::DuplicateId {
+                    cue: format!("{}:{}", cart.name, id),
+                });
+            }
+            for cue in cues() {
+                let id = format!("{}:{}", cart.name, cue.get_id());
+                self.check_cue(0, cue, id, output_available, wait_for_media, &mut progress);
+            }
+        }
+
+        progress
+    }
+
+    fn check_cue(
+        &self,
+        from_list: usize,
+        cue: &MultitypeCue,
+        id: String,
+        output_available: bool,
+        wait_for_media: bool,
+        progress: &mut CheckProgress,
+    ) {
+        let problems = &mut progress.problems;
+        let mut media_ok = true;
+        for path in cue.get_media() {
+            if path.is_empty() || !Path::new(path).is_file() {
+                media_ok = false;
+                problems.push(ProjectProblem::MissingMedia {
+                    cue: id.clone(),
+                    path: path.clone(),
+                });
+            }
+        }
+
+        for referent in cue.get_referents() {
+            if self.resolve_ref(from_list, referent).is_none() {
+                problems.push(ProjectProblem::BrokenReferent {
+                    cue: id.clone(),
+                    referent: referent.clone(),
+                });
+            }
+        }
+
+        if cue.get_attributes().runnable {
+            if !cue.is_enabled() {
+                problems.push(ProjectProblem::Disabled { cue: id.clone() });
+            }
+            if !cue.is_armed() {
+                problems.push(ProjectProblem::Disarmed { cue: id.clone() });
+            }
+        }
+
+        if let MultitypeCue::Audio(audio_cue) = cue {
+            if !output_available {
+                problems.push(ProjectProblem::OutputUnavailable { cue: id.clone() });
+            }
+
+            if media_ok {
+                progress.media += 1;
+                let file_length = if wait_for_media {
+                    audio_cue.file_length()
+                } else {
+                    match audio_cue.analyzed_file_length() {
+                        Some(result) => result,
+                        None => {
+                            progress.waiting += 1;
+                            return;
+                        }
+                    }
+                };
+                let problems = &mut progress.problems;
+                match file_length {
+                    Err(err) => problems.push(ProjectProblem::UndecodableMedia {
+                        cue: id.clone(),
+                        path: audio_cue.file_path.clone(),
+                        error: err.to_string(),
+                    }),
+                    Ok(file_length) => {
+                        let trimmed = audio_cue.start + audio_cue.end;
+                        if trimmed > file_length {
+                            problems.push(ProjectProblem::TrimTooLong {
+                                cue: id,
+                                trimmed,
+                                file_length,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// IDs used by more than one of the given cues
+fn duplicate_ids<'a>(cues: impl Iterator<Item = &'a MultitypeCue>) -> Vec<String> {
+    let mut seen_ids = HashSet::new();
+    let mut duplicates = Vec::new();
+    for cue in cues {
+        let id = cue.get_id();
+        if !seen_ids.insert(id.clone()) && !duplicates.contains(&id) {
+            duplicates.push(id);
+        }
+    }
+    duplicates
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Project;
+
+// version of the project file format written by this build. bump this and
+// add a step to MIGRATIONS whenever a change to the file format would keep
+// older files from deserializing.
+pub const FORMAT_VERSION: u64 = 3;
+
+// each migration upgrades a document from version n to version n + 1, where
+// n is its index in this array
+type Migration = fn(&mut Value) -> Result<(), anyhow::Error>;
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [v0_to_v1, v1_to_v2, v2_to_v3];
+
+#[derive(Debug)]
+pub struct FormatTooNewError {
+    pub found: u64,
+}
+
+impl std::fmt::Display for FormatTooNewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Project file is format version {}, but this version of cueball only supports up to {}. Please update cueball to open it.",
+            self.found, FORMAT_VERSION
+        )
+    }
+}
+
+impl std::error::Error for FormatTooNewError {}
+
+// what gets written to disk: the project, with the format version alongside it
+#[derive(Serialize)]
+pub(super) struct VersionedProject<'a> {
+    version: u64,
+    #[serde(flatten)]
+    project: &'a Project,
+}
+
+impl<'a> VersionedProject<'a> {
+    pub(super) fn new(project: &'a Project) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            project,
+        }
+    }
+}
+
+// files from before versioning have no version field
+pub fn document_version(doc: &Value) -> Result<u64, anyhow::Error> {
+    match doc.get("version") {
+        None => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Invalid project format version {}", v)),
+    }
+}
+
+// upgrade a project document in place to the current format version
+pub fn migrate(doc: &mut Value) -> Result<(), anyhow::Error> {
+    let version = document_version(doc)?;
+    if version > FORMAT_VERSION {
+        return Err(FormatTooNewError { found: version }.into());
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(doc)?;
+        log::debug!("Migrated project from format {} to {}", from, from + 1);
+    }
+
+    if let Some(obj) = doc.as_object_mut() {
+        obj.insert("version".into(), FORMAT_VERSION.into());
+    }
+
+    Ok(())
+}
+
+// iterate over the inner object of every cue of the given type in a document
+fn cues_of_type<'a>(
+    doc: &'a mut Value,
+    list_path: &str,
+    cue_type: &'a str,
+) -> impl Iterator<Item = &'a mut serde_json::Map<String, Value>> {
+    doc.pointer_mut(list_path)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(move |cue| cue.get_mut(cue_type))
+        .filter_map(Value::as_object_mut)
+}
+
+// v0 files relied on serde defaults for fields added after the first release
+fn v0_to_v1(doc: &mut Value) -> Result<(), anyhow::Error> {
+    for cue in cues_of_type(doc, "/cues/list", "Audio") {
+        cue.entry("volume").or_insert(0.5.into());
+        cue.entry("enabled").or_insert(true.into());
+        cue.entry("armed").or_insert(true.into());
+    }
+    Ok(())
+}
+
+// v1 projects had a single cue list, which becomes the main list
+fn v1_to_v2(doc: &mut Value) -> Result<(), anyhow::Error> {
+    let obj = doc
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("Project file is not an object"))?;
+    let mut cues = obj
+        .remove("cues")
+        .ok_or_else(|| anyhow::anyhow!("Project file has no cues"))?;
+    if let Some(list) = cues.as_object_mut() {
+        list.insert("name".into(), "Main".into());
+        list.insert("go_key".into(), "Space".into());
+    }
+    obj.insert("cue_lists".into(), Value::Array(vec![cues]));
+    Ok(())
+}
+
+// v2 cart cells had a hotkey of their own, which is now their cue's. a cue
+// that already has one keeps it
+fn v2_to_v3(doc: &mut Value) -> Result<(), anyhow::Error> {
+    let carts = doc
+        .get_mut("carts")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten();
+    let cells = carts
+        .filter_map(|cart| cart.get_mut("cells"))
+        .filter_map(Value::as_array_mut)
+        .flatten()
+        .filter_map(Value::as_object_mut);
+    for cell in cells {
+        let Some(hotkey) = cell.remove("hotkey").filter(|key| !key.is_null()) else {
+            continue;
+        };
+        // the cue is tagged with its type, e.g. {"Audio": {...}}
+        let cue = cell
+            .get_mut("cue")
+            .and_then(Value::as_object_mut)
+            .and_then(|cue| cue.values_mut().next())
+            .and_then(Value::as_object_mut);
+        if let Some(cue) = cue {
+            let slot = cue.entry("hotkey").or_insert(Value::Null);
+            if slot.is_null() {
+                *slot = hotkey;
+            }
+        }
+    }
+    Ok(())
+}
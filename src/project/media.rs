@@ -0,0 +1,278 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use log::{debug, info, warn};
+
+use super::Project;
+use crate::{Cue, MultitypeCue};
+
+// name of the folder media gets copied into when collecting a project
+pub const MEDIA_DIR_NAME: &str = "media";
+
+#[derive(Debug, Default)]
+pub struct RelinkReport {
+    // (old path, new path) of every media file that was found
+    pub relinked: Vec<(String, String)>,
+    pub still_missing: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct NormalizeReport {
+    // (cue ID, new trim in dB) of every audio cue that was normalized
+    pub normalized: Vec<(String, f32)>,
+    // (cue ID, why) of every audio cue that couldn't be
+    pub skipped: Vec<(String, String)>,
+}
+
+fn is_missing(path: &str) -> bool {
+    !path.is_empty() && !Path::new(path).is_file()
+}
+
+impl Project {
+    // give cues the results of media analysis that has finished in the
+    // background. returns whether any are still loading
+    pub fn update_media(&mut self) -> bool {
+        let mut loading = false;
+        for cue in self.cues_mut() {
+            cue.update_media();
+            loading |= cue.is_loading();
+        }
+        loading
+    }
+
+    // trim every audio cue so that it comes out at the project's loudness
+    // target. cues whose files haven't been analyzed yet are skipped
+    pub fn normalize_loudness(&mut self) -> NormalizeReport {
+        let target = self.loudness_target;
+        let mut report = NormalizeReport::default();
+        for cue in self.cues_mut() {
+            if let MultitypeCue::Audio(cue) = cue {
+                match cue.normalize(target) {
+                    Ok(trim) => report.normalized.push((cue.id.clone(), trim)),
+                    Err(err) => report.skipped.push((cue.id.clone(), err.to_string())),
+                }
+            }
+        }
+        info!(
+            "Normalized {} audio cues to {} LUFS, skipped {}",
+            report.normalized.len(),
+            target,
+            report.skipped.len()
+        );
+        report
+    }
+
+    // directory that relative media paths are stored against
+    pub fn base_dir(&self) -> Option<&Path> {
+        self.path.as_deref().and_then(Path::parent)
+    }
+
+    // rewrite media paths inside `base` to be relative to it, so that the
+    // show folder can be moved between machines
+    pub(super) fn relativize_media(&mut self, base: &Path) {
+        for cue in self.cues_mut() {
+            for path in cue.get_media_mut() {
+                if let Ok(rel) = Path::new(path.as_str()).strip_prefix(base) {
+                    if let Some(rel) = rel.to_str() {
+                        *path = rel.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    // turn relative media paths back into absolute ones
+    pub(super) fn absolutize_media(&mut self, base: &Path) {
+        for cue in self.cues_mut() {
+            for path in cue.get_media_mut() {
+                if !path.is_empty() && Path::new(path.as_str()).is_relative() {
+                    if let Some(abs) = base.join(path.as_str()).to_str() {
+                        *path = abs.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    // every media path that doesn't point to a file, without duplicates
+    pub fn missing_media(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        for cue in self.cues() {
+            for path in cue.get_media() {
+                if is_missing(path) && !missing.contains(path) {
+                    missing.push(path.clone());
+                }
+            }
+        }
+        missing
+    }
+
+    // search `dir` and its subdirectories for missing media files by file
+    // name, and point cues at any that are found
+    pub fn relink_media(&mut self, dir: &Path) -> Result<RelinkReport, anyhow::Error> {
+        let mut wanted: HashMap<String, Option<PathBuf>> = HashMap::new();
+        for path in self.missing_media() {
+            if let Some(name) = Path::new(&path).file_name().and_then(|n| n.to_str()) {
+                wanted.insert(name.to_string(), None);
+            }
+        }
+
+        let mut to_search = vec![dir.to_path_buf()];
+        while let Some(d) = to_search.pop() {
+            for entry in fs::read_dir(&d)? {
+                let entry_path = entry?.path();
+                if entry_path.is_dir() {
+                    to_search.push(entry_path);
+                } else if let Some(name) = entry_path.file_name().and_then(|n| n.to_str()) {
+                    if let Some(found @ None) = wanted.get_mut(name) {
+                        *found = Some(entry_path.clone());
+                    }
+                }
+            }
+        }
+
+        let mut report = RelinkReport::default();
+        for cue in self.cues_mut() {
+            let mut changed = false;
+            for path in cue.get_media_mut() {
+                if !is_missing(path) {
+                    continue;
+                }
+                let found = Path::new(path.as_str())
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| wanted.get(n).cloned().flatten())
+                    .and_then(|p| p.to_str().map(String::from));
+                match found {
+                    Some(new_path) => {
+                        debug!("Relinked {} to {}", path, new_path);
+                        report.relinked.push((path.clone(), new_path.clone()));
+                        *path = new_path;
+                        changed = true;
+                    }
+                    None => {
+                        if !report.still_missing.contains(path) {
+                            report.still_missing.push(path.clone());
+                        }
+                    }
+                }
+            }
+            // pick up the new media
+            if changed {
+                cue.init();
+            }
+        }
+
+        info!(
+            "Relinked {} media files, {} still missing",
+            report.relinked.len(),
+            report.still_missing.len()
+        );
+        Ok(report)
+    }
+
+    // the project's name made safe to use as a file or folder name, with
+    // path separators and characters some systems reserve swapped out
+    pub fn file_stem(&self) -> String {
+        let stem: String = self
+            .name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect();
+        // a name of only dots would mean this folder or the one above it
+        let stem = stem.trim_matches(|c: char| c == '.' || c.is_whitespace());
+        if stem.is_empty() {
+            "Untitled".to_string()
+        } else {
+            stem.to_string()
+        }
+    }
+
+    // copy every referenced media file into `bundle_dir`, point the cues at
+    // the copies, and save the project alongside them. returns the path of
+    // the saved project file
+    pub fn collect(&mut self, bundle_dir: &Path) -> Result<PathBuf, anyhow::Error> {
+        let media_dir = bundle_dir.join(MEDIA_DIR_NAME);
+        fs::create_dir_all(&media_dir)?;
+
+        // map from original path to its copy, so that media shared between
+        // cues is only copied once
+        let mut copied: HashMap<String, String> = HashMap::new();
+        for cue in self.cues_mut() {
+            for path in cue.get_media_mut() {
+                if path.is_empty() {
+                    continue;
+                }
+                if let Some(copy) = copied.get(path.as_str()) {
+                    *path = copy.clone();
+                    continue;
+                }
+                if is_missing(path) {
+                    warn!("Not collecting missing media file {}", path);
+                    continue;
+                }
+                // already collected into this bundle
+                if Path::new(path.as_str()).starts_with(&media_dir) {
+                    continue;
+                }
+
+                let dest = unique_destination(&media_dir, Path::new(path.as_str()))?;
+                fs::copy(path.as_str(), &dest)?;
+                let dest = dest
+                    .to_str()
+                    .ok_or_else(|| anyhow!("Invalid media path {}", dest.display()))?
+                    .to_string();
+                debug!("Collected {} to {}", path, dest);
+
+                copied.insert(path.clone(), dest.clone());
+                *path = dest;
+            }
+        }
+
+        let project_path = bundle_dir.join(format!("{}.cueball", self.file_stem()));
+        self.save_to(&project_path)?;
+        self.path = Some(project_path.clone());
+
+        info!(
+            "Collected {} media files into {}",
+            copied.len(),
+            bundle_dir.display()
+        );
+        Ok(project_path)
+    }
+}
+
+// pick a path in `dir` for a copy of `src`, without clobbering files that
+// happen to share its name
+fn unique_destination(dir: &Path, src: &Path) -> Result<PathBuf, anyhow::Error> {
+    let name = src
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid media path {}", src.display()))?;
+    let mut dest = dir.join(name);
+
+    let stem = src
+        .file_stem()
+        .unwrap_or(name)
+        .to_string_lossy()
+        .to_string();
+    let ext = src.extension().map(|e| e.to_string_lossy().to_string());
+    let mut n = 1;
+    while dest.exists() {
+        let new_name = match &ext {
+            Some(ext) => format!("{} {}.{}", stem, n, ext),
+            None => format!("{} {}", stem, n),
+        };
+        dest = dir.join(new_name);
+        n += 1;
+    }
+
+    Ok(dest)
+}
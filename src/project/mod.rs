@@ -0,0 +1,321 @@
+pub mod backup;
+mod check;
+mod format;
+mod media;
+mod render;
+mod sequence;
+
+pub use check::{CheckProgress, ProblemSeverity, ProjectProblem};
+pub use format::{FormatTooNewError, FORMAT_VERSION};
+pub use media::{NormalizeReport, RelinkReport, MEDIA_DIR_NAME};
+pub use render::{RenderOptions, RenderReport};
+pub use sequence::{SequenceEntry, Sequencer};
+
+use crate::{
+    audio::{self, EffectChain, Speaker},
+    cues::{Cart, ControlAction, CueList, TimeFormat},
+    Cue, MultitypeCue,
+};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    hash::{DefaultHasher, Hasher},
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Project {
+    pub name: String,
+    pub path: Option<PathBuf>,
+
+    pub cue_lists: Vec<CueList>,
+    #[serde(default)]
+    pub carts: Vec<Cart>,
+
+    // integrated loudness audio cues are normalized to, in LUFS
+    #[serde(default = "default_loudness_target")]
+    pub loudness_target: f32,
+    // what the whole output goes through
+    #[serde(default)]
+    pub output_effects: EffectChain,
+    // where the output's speakers are on the stage plot, one for each
+    // channel. empty until they are placed
+    #[serde(default)]
+    pub speakers: Vec<Speaker>,
+    // how times are shown and typed in
+    #[serde(default)]
+    pub time_format: TimeFormat,
+
+    #[serde(skip)]
+    sequencer: Sequencer,
+}
+
+// the EBU R128 broadcast target
+fn default_loudness_target() -> f32 {
+    -23.
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        let mut main = CueList::new("Main");
+        main.go_key = Some("Space".to_string());
+        Self {
+            name: String::from("Untitled"),
+            path: None,
+            cue_lists: vec![main],
+            carts: vec![],
+            loudness_target: default_loudness_target(),
+            output_effects: EffectChain::default(),
+            speakers: vec![],
+            time_format: TimeFormat::default(),
+            sequencer: Sequencer::default(),
+        }
+    }
+}
+
+impl Project {
+    // read a project file from disk without initializing any cues
+    pub fn read(path: impl Into<PathBuf>) -> Result<Project, anyhow::Error> {
+        let path = path.into();
+        let file = File::open(&path)?;
+        let reader = BufReader::new(file);
+
+        // upgrade older files before handing them to serde
+        let mut doc: serde_json::Value = serde_json::from_reader(reader)?;
+        format::migrate(&mut doc)?;
+
+        let mut project: Project = serde_json::from_value(doc)?;
+        project.path = Some(path);
+
+        // media paths are stored relative to the project file where possible
+        if let Some(base) = project.base_dir().map(Path::to_path_buf) {
+            project.absolutize_media(&base);
+        }
+
+        Ok(project)
+    }
+
+    // read a project file from disk and initialize all of its cues
+    pub fn open(path: impl Into<PathBuf>) -> Result<Project, anyhow::Error> {
+        let mut project = Project::read(path)?;
+
+        for list in &mut project.cue_lists {
+            list.init_cues();
+            list.set_playhead(Some(0));
+        }
+        for cart in &mut project.carts {
+            cart.init_cues();
+        }
+        project.apply_output_settings();
+
+        debug!("Loaded project {}", project.name);
+
+        Ok(project)
+    }
+
+    // send the project's output effects and speaker layout to the output,
+    // if there is one
+    pub fn apply_output_settings(&self) {
+        audio::set_output_effects(&self.output_effects);
+        audio::set_speakers(&self.speakers);
+    }
+
+    // the speakers as placed, or where the output's are assumed to be
+    pub fn speaker_layout(&self) -> Vec<Speaker> {
+        if !self.speakers.is_empty() {
+            return self.speakers.clone();
+        }
+        audio::speakers().unwrap_or_else(|| audio::default_speakers(2))
+    }
+
+    pub fn save_to(&mut self, path: &Path) -> Result<(), anyhow::Error> {
+        // write media paths relative to the project file, then put them back
+        // so the running show is unaffected
+        let base = path.parent().map(Path::to_path_buf);
+        if let Some(base) = &base {
+            self.relativize_media(base);
+        }
+        let result = self.write_to(path);
+        if let Some(base) = &base {
+            self.absolutize_media(base);
+        }
+        result
+    }
+
+    fn write_to(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let contents = serde_json::to_vec(&format::VersionedProject::new(self))?;
+        backup::write_atomic(path, &contents)?;
+        debug!("Saved project {} to {}", self.name, path.display());
+        Ok(())
+    }
+
+    // serialized form of the project, for telling whether it has changed
+    pub fn to_json(&self) -> Result<String, anyhow::Error> {
+        Ok(serde_json::to_string(&format::VersionedProject::new(self))?)
+    }
+
+    // hash of the serialized project, worked out without building the whole
+    // string, for cheaply telling whether it has changed
+    pub fn fingerprint(&self) -> Result<u64, anyhow::Error> {
+        struct HashWriter(DefaultHasher);
+        impl Write for HashWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut writer = HashWriter(DefaultHasher::new());
+        serde_json::to_writer(&mut writer, &format::VersionedProject::new(self))?;
+        Ok(writer.0.finish())
+    }
+
+    // every cue in every list and cart
+    pub fn cues(&self) -> impl Iterator<Item = &MultitypeCue> {
+        self.cue_lists.iter().flat_map(|list| list.iter()).chain(
+            self.carts
+                .iter()
+                .flat_map(|cart| cart.cells().map(|c| &c.cue)),
+        )
+    }
+
+    pub fn cues_mut(&mut self) -> impl Iterator<Item = &mut MultitypeCue> {
+        self.cue_lists
+            .iter_mut()
+            .flat_map(|list| list.iter_mut())
+            .chain(
+                self.carts
+                    .iter_mut()
+                    .flat_map(|cart| cart.cells_mut().map(|c| &mut c.cue)),
+            )
+    }
+
+    pub fn list_index(&self, name: &str) -> Option<usize> {
+        self.cue_lists.iter().position(|list| list.name == name)
+    }
+
+    // whether `list` could be called `name`. references name their list, so
+    // names have to be unique and can't contain the `:` that ends them
+    pub fn check_list_name(&self, list: usize, name: &str) -> Result<(), anyhow::Error> {
+        if name.trim().is_empty() {
+            return Err(anyhow::anyhow!("A list needs a name"));
+        }
+        if name.contains(':') {
+            return Err(anyhow::anyhow!("List names can't contain ':'"));
+        }
+        match self.list_index(name) {
+            Some(other) if other != list => {
+                Err(anyhow::anyhow!("There is already a list called {}", name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // rename a list, pointing every reference to its cues at the new name
+    pub fn rename_list(&mut self, list: usize, name: &str) -> Result<(), anyhow::Error> {
+        self.check_list_name(list, name)?;
+        let old = std::mem::replace(&mut self.cue_lists[list].name, name.to_string());
+        let prefix = format!("{}:", old);
+        for cue in self.cues_mut() {
+            if let MultitypeCue::Control(cue) = cue {
+                if let Some(id) = cue.target.strip_prefix(&prefix) {
+                    cue.target = format!("{}:{}", name, id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // delete a list, stopping its cues and cancelling anything it had
+    // pending. the last list can't be removed
+    pub fn remove_list(&mut self, list: usize) -> Option<CueList> {
+        if list >= self.cue_lists.len() || self.cue_lists.len() == 1 {
+            return None;
+        }
+        let mut removed = self.cue_lists.remove(list);
+        removed.stop_all();
+        self.sequencer.remove_list(list);
+        Some(removed)
+    }
+
+    // find the cue a reference points to, as (list index, cue index). a
+    // reference is either a cue ID in `from_list`, or `list name:cue ID`
+    pub fn resolve_ref(&self, from_list: usize, reference: &str) -> Option<(usize, usize)> {
+        if let Some((list_name, id)) = reference.split_once(':') {
+            if let Some(l) = self.list_index(list_name) {
+                return self.cue_lists[l].index_of(id).map(|i| (l, i));
+            }
+        }
+        self.cue_lists
+            .get(from_list)?
+            .index_of(reference)
+            .map(|i| (from_list, i))
+    }
+
+    // trigger a cue now, carrying out its control action once it fires and
+    // continuing into the cues after it. returns the index the list's
+    // playhead should move to afterwards
+    pub fn go_cue(&mut self, list: usize, index: usize) -> usize {
+        self.go_cue_at(list, index, Instant::now())
+    }
+
+    // press a cart cell, starting or stopping its cue. control cues in carts
+    // refer to cues in the first list unless they name another one
+    pub fn toggle_cart_cell(&mut self, cart: usize, index: usize) {
+        let cart = &mut self.carts[cart];
+        if cart.toggle(index) {
+            let control = control_of(&cart.cell(index).cue);
+            self.run_control(0, control);
+        }
+    }
+
+    fn run_control(&mut self, from_list: usize, control: Option<(ControlAction, String)>) {
+        let Some((action, target)) = control else {
+            return;
+        };
+        match self.resolve_ref(from_list, &target) {
+            Some((l, i)) => {
+                let target = &mut self.cue_lists[l][i];
+                match action {
+                    ControlAction::Start => target.go(),
+                    ControlAction::Stop => target.stop(),
+                    ControlAction::Pause => target.set_paused(true),
+                    ControlAction::Resume => target.set_paused(false),
+                }
+            }
+            None => warn!("Control cue target {} does not exist", target),
+        }
+    }
+
+    // fire the cue at a list's playhead and advance it
+    pub fn go(&mut self, list: usize) {
+        if let Some(index) = self.cue_lists[list].playhead {
+            let next = self.go_cue(list, index);
+            self.cue_lists[list].set_playhead(Some(next));
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.cancel_pending();
+        for list in &mut self.cue_lists {
+            list.stop_all();
+        }
+        for cart in &mut self.carts {
+            cart.stop_all();
+        }
+    }
+}
+
+// the control action a cue that just fired should carry out, if any
+fn control_of(cue: &MultitypeCue) -> Option<(ControlAction, String)> {
+    match cue.get_control() {
+        Some((action, target)) if cue.can_fire() => Some((action, target.clone())),
+        _ => None,
+    }
+}
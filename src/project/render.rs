@@ -0,0 +1,177 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use log::{info, warn};
+use rodio::{source::Zero, Source};
+
+use super::Project;
+use crate::{
+    audio::{default_speakers, EffectsHandle, Processed, WavWriter},
+    cues::CueTime,
+    Cue, MultitypeCue,
+};
+
+// samples pulled from the mix at a time
+const CHUNK: usize = 4096;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions {
+    pub channels: u16,
+    pub rate: u32,
+    // silence kept after the last cue ends, for effect tails
+    pub tail: CueTime,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            channels: 2,
+            rate: 48000,
+            tail: CueTime::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RenderReport {
+    pub frames: u64,
+    // (cue ID, when it fired) of every audio cue in the render
+    pub rendered: Vec<(String, CueTime)>,
+    // (cue ID, why) of every audio cue that couldn't be
+    pub skipped: Vec<(String, String)>,
+}
+
+impl Project {
+    // when each cue from `from` to `to` of a list fires, as if GO was pressed
+    // on `from` and again on the next sequence as soon as everything in the
+    // last one had finished
+    pub fn render_schedule(&self, list: usize, from: usize, to: usize) -> Vec<(usize, CueTime)> {
+        let cues = &self.cue_lists[list];
+        let mut schedule = Vec::new();
+        let (mut go, mut i) = (CueTime::ZERO, from);
+        while i <= to && i < cues.len() {
+            let sequence = self.sequence(list, i);
+            let mut end = go;
+            for entry in sequence.iter().filter(|e| e.index <= to) {
+                if cues[entry.index].can_fire() {
+                    schedule.push((entry.index, go + entry.fired));
+                }
+                end = end.max(go + entry.fired + entry.length.unwrap_or_default());
+            }
+            let last = sequence.last().map_or(i, |entry| entry.index);
+            i = last + cues[last].next_offset();
+            go = end;
+        }
+        schedule
+    }
+
+    // default options for this project, with a channel for each of its
+    // speakers when it has a layout of its own
+    pub fn render_options(&self) -> RenderOptions {
+        let mut options = RenderOptions::default();
+        if !self.speakers.is_empty() {
+            options.channels = self.speakers.len() as u16;
+        }
+        options
+    }
+
+    // mix cues `from` to `to` of a list faster than real time, through the
+    // same cue and output processing as live playback, handing the
+    // interleaved samples to `out` a chunk at a time
+    pub fn render(
+        &mut self,
+        list: usize,
+        from: usize,
+        to: usize,
+        options: RenderOptions,
+        mut out: impl FnMut(&[f32]) -> Result<(), anyhow::Error>,
+    ) -> Result<RenderReport, anyhow::Error> {
+        if options.channels == 0 || options.rate == 0 {
+            return Err(anyhow!("Cannot render with no channels"));
+        }
+        let len = self.cue_lists[list].len();
+        if from >= len {
+            return Err(anyhow!(
+                "Nothing to render, cue list {} has no cue {}",
+                self.cue_lists[list].name,
+                from + 1
+            ));
+        }
+        let mut report = RenderReport::default();
+
+        // lengths decide when auto-follows happen, so they have to be known
+        for i in from..=to.min(len - 1) {
+            if let MultitypeCue::Audio(cue) = &mut self.cue_lists[list][i] {
+                if let Err(err) = cue.load_now() {
+                    warn!("Could not load audio cue {} to render: {}", cue.id, err);
+                }
+            }
+        }
+
+        let speakers = match self.speaker_layout() {
+            layout if layout.len() == options.channels as usize => layout,
+            _ => default_speakers(options.channels as usize),
+        };
+        let (mixer, mix) = rodio::mixer::mixer(options.channels, options.rate);
+        let mut length = CueTime::ZERO;
+        for (i, at) in self.render_schedule(list, from, to) {
+            let MultitypeCue::Audio(cue) = &self.cue_lists[list][i] else {
+                continue;
+            };
+            // a copy, so a cue that is playing live keeps its handles
+            let mut cue = cue.clone();
+            match cue.source(Some(speakers.clone())) {
+                Ok(source) => {
+                    length = length.max(at + cue.duration.unwrap_or_default());
+                    let source = source.amplify(cue.gain());
+                    mixer.add(source.delay(at.to_duration()));
+                    report.rendered.push((cue.id.clone(), at));
+                }
+                Err(err) => report.skipped.push((cue.id.clone(), err.to_string())),
+            }
+        }
+
+        // silence for the whole length keeps the mix going through gaps
+        let length = length + options.tail.max(CueTime::ZERO);
+        let frames = length.to_frames(options.rate).max(0) as u64;
+        let total = frames as usize * options.channels as usize;
+        mixer.add(Zero::new(options.channels, options.rate).take_duration(length.to_duration()));
+        let mut mix = Processed::new(mix, &self.output_effects, EffectsHandle::default());
+
+        let mut chunk = Vec::with_capacity(CHUNK);
+        let mut written = 0;
+        while written < total {
+            chunk.clear();
+            let want = CHUNK.min(total - written);
+            chunk.extend(mix.by_ref().take(want));
+            // the mixer can stop a sample or two early
+            chunk.resize(want, 0.);
+            out(&chunk)?;
+            written += want;
+        }
+
+        report.frames = frames;
+        Ok(report)
+    }
+
+    // render cues `from` to `to` of a list to a 32 bit float WAV file
+    pub fn render_to(
+        &mut self,
+        list: usize,
+        from: usize,
+        to: usize,
+        options: RenderOptions,
+        path: &Path,
+    ) -> Result<RenderReport, anyhow::Error> {
+        let mut wav = WavWriter::create(path, options.channels, options.rate)?;
+        let report = self.render(list, from, to, options, |samples| wav.write(samples))?;
+        wav.finish()?;
+        info!(
+            "Rendered {} cues to {} ({})",
+            report.rendered.len(),
+            path.display(),
+            CueTime::from_frames(report.frames as i64, options.rate)
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,232 @@
+use std::time::Instant;
+
+use super::{control_of, Project};
+use crate::{
+    cues::{ContinueMode, CueRunning, CueTime},
+    Cue,
+};
+
+// cues that have been triggered but haven't fired yet, either because they
+// are counting down their pre-wait or because the cue before them is set to
+// auto-follow. cues are kept by ID rather than index, so editing the list
+// while something is pending can't fire the wrong cue, and cues that have
+// been deleted are dropped
+#[derive(Debug, Default)]
+pub struct Sequencer {
+    // (list, cue ID, when it fires)
+    waiting: Vec<(usize, String, Instant)>,
+    // (list, cue ID) of cues that trigger the next cue once they have finished
+    following: Vec<(usize, String)>,
+    // (list, cue ID, when) of the last cue triggered with GO, so the timeline
+    // knows where its sequence started
+    last_go: Option<(usize, String, Instant)>,
+}
+
+impl Sequencer {
+    pub fn is_idle(&self) -> bool {
+        self.waiting.is_empty() && self.following.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.waiting.clear();
+        self.following.clear();
+    }
+
+    // forget anything pending in list `removed`, and point the lists after it
+    // at where they have moved to
+    pub(super) fn remove_list(&mut self, removed: usize) {
+        let shift = |list: &mut usize| {
+            if *list > removed {
+                *list -= 1;
+            }
+        };
+        self.waiting.retain(|(l, _, _)| *l != removed);
+        self.waiting.iter_mut().for_each(|(l, _, _)| shift(l));
+        self.following.retain(|(l, _)| *l != removed);
+        self.following.iter_mut().for_each(|(l, _)| shift(l));
+        match &mut self.last_go {
+            Some((l, _, _)) if *l == removed => self.last_go = None,
+            Some((l, _, _)) => shift(l),
+            None => {}
+        }
+    }
+}
+
+// where a cue sits in a sequence, from the sequence being started
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SequenceEntry {
+    pub index: usize,
+    // when the cue is triggered, which is where its pre-wait starts
+    pub triggered: CueTime,
+    // when the pre-wait is over and the cue fires
+    pub fired: CueTime,
+    pub length: Option<CueTime>,
+}
+
+impl Project {
+    // trigger a cue at `now`, firing it once its pre-wait is over. cues after
+    // it that it continues into are triggered in turn. returns the index the
+    // list's playhead should move to, past the end of the sequence
+    pub fn go_cue_at(&mut self, list: usize, index: usize, now: Instant) -> usize {
+        let Some(cue) = self.cue_lists.get(list).and_then(|l| l.get(index)) else {
+            return index;
+        };
+        self.sequencer.last_go = Some((list, cue.get_id(), now));
+        self.trigger(list, index, now);
+
+        let sequence = self.sequence(list, index);
+        let last = sequence.last().map_or(index, |entry| entry.index);
+        last + self.cue_lists[list][last].next_offset()
+    }
+
+    fn trigger(&mut self, list: usize, index: usize, now: Instant) {
+        let Some(cue) = self.cue_lists.get(list).and_then(|l| l.get(index)) else {
+            return;
+        };
+        let pre_wait = cue.get_pre_wait();
+        if pre_wait > CueTime::ZERO {
+            let at = now + pre_wait.to_duration();
+            self.sequencer.waiting.push((list, cue.get_id(), at));
+        } else {
+            self.fire(list, index, now);
+        }
+    }
+
+    // where a pending cue is now, if it still exists
+    fn pending_index(&self, list: usize, id: &str) -> Option<usize> {
+        self.cue_lists.get(list)?.index_of(id)
+    }
+
+    // the pre-wait is over, so actually fire the cue
+    fn fire(&mut self, list: usize, index: usize, now: Instant) {
+        let Some(cue) = self.cue_lists.get_mut(list).and_then(|l| l.get_mut(index)) else {
+            return;
+        };
+        cue.go();
+        let next = index + cue.next_offset();
+        let mode = cue.get_continue();
+
+        let control = control_of(cue);
+        self.run_control(list, control);
+
+        if next >= self.cue_lists[list].len() {
+            return;
+        }
+        match mode {
+            ContinueMode::DoNotContinue => {}
+            ContinueMode::AutoContinue => self.trigger(list, next, now),
+            ContinueMode::AutoFollow => {
+                let id = self.cue_lists[list][index].get_id();
+                self.sequencer.following.push((list, id));
+            }
+        }
+    }
+
+    // fire every cue whose pre-wait is over and follow on from every cue that
+    // has finished. call this regularly while anything is pending
+    pub fn update(&mut self, now: Instant) {
+        // firing a cue can trigger more, which may already be due
+        loop {
+            let due = self
+                .sequencer
+                .waiting
+                .iter()
+                .position(|(_, _, at)| *at <= now);
+            let Some(i) = due else {
+                break;
+            };
+            let (list, id, at) = self.sequencer.waiting.remove(i);
+            // later cues in the sequence count from when this one was due,
+            // not from when we got around to it
+            if let Some(index) = self.pending_index(list, &id) {
+                self.fire(list, index, at);
+            }
+        }
+
+        let following = std::mem::take(&mut self.sequencer.following);
+        for (list, id) in following {
+            // a cue deleted while playing has nothing left to follow on from
+            let Some(index) = self.pending_index(list, &id) else {
+                continue;
+            };
+            let cue = &self.cue_lists[list][index];
+            if cue.running() == CueRunning::Stopped {
+                let next = index + cue.next_offset();
+                self.trigger(list, next, now);
+            } else {
+                self.sequencer.following.push((list, id));
+            }
+        }
+    }
+
+    // anything still to come in a sequence
+    pub fn sequence_pending(&self) -> bool {
+        !self.sequencer.is_idle()
+    }
+
+    // forget every cue waiting to fire, without stopping anything that has
+    pub fn cancel_pending(&mut self) {
+        self.sequencer.clear();
+    }
+
+    // time until a cue fires, if it is counting down its pre-wait
+    pub fn pre_wait_remaining(&self, list: usize, index: usize, now: Instant) -> Option<CueTime> {
+        let id = self.cue_lists.get(list)?.get(index)?.get_id();
+        self.sequencer
+            .waiting
+            .iter()
+            .find(|(l, i, _)| *l == list && *i == id)
+            .map(|(_, _, at)| at.saturating_duration_since(now).into())
+    }
+
+    // the cue in `list` that the last GO started from, and how long ago
+    pub fn last_go(&self, list: usize, now: Instant) -> Option<(usize, CueTime)> {
+        match &self.sequencer.last_go {
+            Some((l, id, at)) if *l == list => {
+                let index = self.pending_index(list, id)?;
+                Some((index, now.saturating_duration_since(*at).into()))
+            }
+            _ => None,
+        }
+    }
+
+    // the first cue of the sequence `index` is part of, found by walking back
+    // through cues that continue into the next one
+    pub fn sequence_start(&self, list: usize, index: usize) -> usize {
+        let cues = &self.cue_lists[list];
+        let mut start = index.min(cues.len().saturating_sub(1));
+        while start > 0 && cues[start - 1].get_continue() != ContinueMode::DoNotContinue {
+            start -= 1;
+        }
+        start
+    }
+
+    // when each cue in the sequence starting at `index` is triggered and
+    // fires, relative to `index` being triggered. cues of unknown length are
+    // followed on from straight away
+    pub fn sequence(&self, list: usize, index: usize) -> Vec<SequenceEntry> {
+        let cues = &self.cue_lists[list];
+        let mut entries = Vec::new();
+        let mut i = index;
+        let mut triggered = CueTime::ZERO;
+        while i < cues.len() {
+            let cue = &cues[i];
+            let fired = triggered + cue.get_pre_wait();
+            let length = cue.length();
+            entries.push(SequenceEntry {
+                index: i,
+                triggered,
+                fired,
+                length,
+            });
+
+            triggered = match cue.get_continue() {
+                ContinueMode::DoNotContinue => break,
+                ContinueMode::AutoContinue => fired,
+                ContinueMode::AutoFollow => fired + length.unwrap_or_default(),
+            };
+            i += cue.next_offset();
+        }
+        entries
+    }
+}
@@ -0,0 +1,61 @@
+pub mod common;
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use common::{dc, scratch_dir, write_wav, RATE};
+use cueball::{
+    audio::{self, Backend, Placement},
+    cli::Show,
+    cues::{AudioCue, CueRunning, CueTime},
+    Cue, MultitypeCue, Project,
+};
+
+#[test]
+fn fires_pre_waited_cues_with_their_placement() {
+    let dir = scratch_dir("cli");
+    let wav = dir.join("dc.wav");
+    write_wav(&wav, dc);
+    audio::init(Backend::Virtual {
+        channels: 2,
+        rate: RATE,
+    })
+    .unwrap();
+
+    // hard left, after a tenth of a second
+    let mut project = Project::default();
+    let mut cue = AudioCue::with_id("1".to_string());
+    cue.file_path = wav.to_str().unwrap().to_string();
+    cue.set_placement(Placement::Pan(-1.));
+    let mut cue = MultitypeCue::Audio(cue);
+    cue.set_pre_wait(CueTime::from_millis(100));
+    project.cue_lists[0].add(cue).unwrap();
+    if let MultitypeCue::Audio(cue) = &mut project.cue_lists[0][0] {
+        cue.load_now().unwrap();
+    }
+    project.cue_lists[0].set_playhead(Some(0));
+
+    // the way the CLI runs it, ticking on the output's clock
+    let mut show = Show::new(Some(project));
+    let start = Instant::now();
+    show.run_line("go");
+    let mut played = Vec::new();
+    while audio::clock().unwrap() < Duration::from_millis(300) {
+        audio::advance(Duration::from_millis(5)).unwrap();
+        played.extend(audio::take_recording().unwrap());
+        show.tick(start + audio::clock().unwrap());
+    }
+
+    let project = show.project.as_ref().unwrap();
+    assert_eq!(project.cue_lists[0][0].running(), CueRunning::Running);
+    // it waited, then played only on the left
+    assert!(played[..RATE as usize / 10].iter().all(|s| *s == 0.));
+    let frames: Vec<_> = played.chunks(2).skip(RATE as usize / 4).collect();
+    assert!(frames
+        .iter()
+        .all(|f| (f[0] - 0.5).abs() < 0.01 && f[1] == 0.));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,66 @@
+// helpers for the integration tests. each binary declares this as `pub mod
+// common`, so the helpers it happens not to use aren't taken for dead code
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Once,
+};
+
+use cueball::audio::set_analysis_cache_dir;
+
+pub const RATE: u32 = 8000;
+
+// scratch directory for a single test. analysis results go to a cache of
+// their own rather than the real one, which runs can share since entries are
+// keyed by each file's path and modification time
+pub fn scratch_dir(name: &str) -> PathBuf {
+    static CACHE: Once = Once::new();
+    let temp = std::env::temp_dir();
+    CACHE.call_once(|| set_analysis_cache_dir(temp.join("cueball-test-cache")));
+    let dir = temp.join(format!("cueball-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// half a second of 16 bit stereo at `RATE`, with `sample` giving each
+// frame's value
+pub fn write_wav(path: &Path, sample: impl Fn(u32) -> i16) {
+    let (channels, frames) = (2u16, RATE / 2);
+    let data_len = frames * channels as u32 * 2;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&RATE.to_le_bytes());
+    bytes.extend_from_slice(&(RATE * channels as u32 * 2).to_le_bytes());
+    bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for frame in 0..frames {
+        let sample = sample(frame);
+        for _ in 0..channels {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    fs::write(path, bytes).unwrap();
+}
+
+// half scale DC
+pub fn dc(_: u32) -> i16 {
+    16384
+}
+
+// a square wave at half scale, 200 Hz at `RATE`
+pub fn square(frame: u32) -> i16 {
+    if frame % 40 < 20 {
+        16384
+    } else {
+        -16384
+    }
+}
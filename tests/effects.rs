@@ -0,0 +1,112 @@
+use cueball::audio::{ChainProcessor, Effect, EffectChain, EffectSlot, EffectsHandle, Processed};
+use cueball::cues::CueTime;
+use rodio::buffer::SamplesBuffer;
+
+const RATE: u32 = 1000;
+
+// stereo samples, the same on both channels, through `chain`
+fn process(chain: &EffectChain, mono: &[f32]) -> Vec<f32> {
+    let samples = mono.iter().flat_map(|s| [*s, *s]).collect::<Vec<_>>();
+    let source = SamplesBuffer::new(2, RATE, samples);
+    Processed::new(source, chain, EffectsHandle::default()).collect()
+}
+
+#[test]
+fn delays_and_bypasses() {
+    let impulse = [1., 0., 0., 0., 0., 0.];
+    let mut chain = EffectChain(vec![EffectSlot::new(Effect::Delay {
+        time: CueTime::from_millis(3),
+    })]);
+    assert_eq!(
+        process(&chain, &impulse),
+        vec![0., 0., 0., 0., 0., 0., 1., 1., 0., 0., 0., 0.]
+    );
+
+    chain.0[0].bypass = true;
+    assert_eq!(
+        process(&chain, &impulse),
+        vec![1., 1., 0., 0., 0., 0., 0., 0., 0., 0., 0., 0.]
+    );
+}
+
+#[test]
+fn filters_and_limits() {
+    // a high pass takes out DC
+    let dc = vec![1.; RATE as usize];
+    let chain = EffectChain(vec![EffectSlot::new(Effect::HighPass {
+        freq: 20.,
+        q: 0.707,
+    })]);
+    assert!(process(&chain, &dc).last().unwrap().abs() < 0.01);
+
+    // an instant limiter never lets a sample over its ceiling
+    let mut limiter = ChainProcessor::new(
+        &EffectChain(vec![EffectSlot::new(Effect::Limiter {
+            ceiling: -6.,
+            release: 0.05,
+        })]),
+        1,
+        RATE,
+    );
+    for sample in [0.1, 1., -0.9, 0.7, 0.2] {
+        assert!(limiter.process(0, sample).abs() <= 0.502);
+    }
+}
+
+#[test]
+fn edits_while_playing() {
+    // a second of DC, with a delay added partway through
+    let source = SamplesBuffer::new(2, RATE, vec![1.; 2 * RATE as usize]);
+    let handle = EffectsHandle::default();
+    let mut processed = Processed::new(source, &EffectChain::default(), handle.clone());
+    assert!(processed.by_ref().take(600).all(|s| s == 1.));
+
+    handle.set(&EffectChain(vec![EffectSlot::new(Effect::Delay {
+        time: CueTime::from_millis(100),
+    })]));
+    let rest = processed.collect::<Vec<_>>();
+    // the new delay line starts out silent, then catches up with the input
+    assert!(rest.contains(&0.));
+    assert_eq!(*rest.last().unwrap(), 1.);
+}
+
+#[test]
+fn ramps_while_playing() {
+    let compressor = |makeup| {
+        EffectChain(vec![EffectSlot::new(Effect::Compressor {
+            threshold: 0.,
+            ratio: 4.,
+            attack: 0.,
+            release: 0.,
+            makeup,
+        })])
+    };
+    // DC well under the threshold, so only the makeup gain touches it
+    let source = SamplesBuffer::new(2, RATE, vec![0.1; 4 * RATE as usize]);
+    let handle = EffectsHandle::default();
+    let mut processed = Processed::new(source, &compressor(0.), handle.clone());
+    assert!(processed.by_ref().take(600).all(|s| (s - 0.1).abs() < 1e-6));
+
+    // 20 dB up over a second, evenly in dB
+    handle.ramp_to(&compressor(20.), CueTime::from_millis(1000));
+    let rest = processed.collect::<Vec<_>>();
+    assert!(rest.windows(2).all(|w| w[1] >= w[0] - 1e-6));
+    // the edit is taken on between checks, so the ramp starts a little later
+    let start = rest.iter().position(|s| *s > 0.1 + 1e-6).unwrap();
+    assert!(start < 256);
+    let halfway = rest[start + RATE as usize];
+    assert!((halfway - 0.316).abs() < 0.02, "halfway at {}", halfway);
+    assert!((rest.last().unwrap() - 1.).abs() < 1e-3);
+}
+
+#[test]
+fn chain_round_trips() {
+    let chain = EffectChain(
+        Effect::ITER
+            .into_iter()
+            .map(EffectSlot::new)
+            .collect::<Vec<_>>(),
+    );
+    let json = serde_json::to_string(&chain).unwrap();
+    assert_eq!(serde_json::from_str::<EffectChain>(&json).unwrap(), chain);
+}
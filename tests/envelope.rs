@@ -0,0 +1,49 @@
+use cueball::audio::{Enveloped, Fade, FadeCurve};
+use cueball::cues::CueTime;
+use rodio::buffer::SamplesBuffer;
+
+// a second of full scale stereo at a low rate, through an envelope
+fn envelope(fade_in: Fade, fade_out: Fade) -> Vec<f32> {
+    let rate = 1000;
+    let source = SamplesBuffer::new(2, rate, vec![1.; 2 * rate as usize]);
+    Enveloped::new(source, fade_in, fade_out, CueTime::from_millis(1000)).collect()
+}
+
+#[test]
+fn fades_both_ends() {
+    let samples = envelope(
+        Fade {
+            length: CueTime::from_millis(100),
+            curve: FadeCurve::Linear,
+        },
+        Fade {
+            length: CueTime::from_millis(200),
+            curve: FadeCurve::EqualPower,
+        },
+    );
+    assert_eq!(samples.len(), 2000);
+
+    // both channels of a frame get the same gain
+    assert_eq!(samples[0], 0.);
+    assert_eq!(samples[100], samples[101]);
+    assert!((samples[100] - 0.5).abs() < 0.001);
+    assert_eq!(samples[1000], 1.);
+
+    // equal power is at -3 dB halfway through the fade out
+    let halfway = samples[2 * 900];
+    assert!((halfway - 0.5f32.sqrt()).abs() < 0.001, "{}", halfway);
+    assert!(samples[1999] < 0.01);
+}
+
+#[test]
+fn curves_meet_at_the_ends() {
+    for (curve, _) in FadeCurve::ITER {
+        assert_eq!(curve.gain(0.), 0.);
+        assert!((curve.gain(1.) - 1.).abs() < 1e-6);
+        assert!(curve.gain(0.25) < curve.gain(0.75));
+    }
+    // without fades the source is left alone
+    assert!(envelope(Fade::default(), Fade::default())
+        .iter()
+        .all(|s| *s == 1.));
+}
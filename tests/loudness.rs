@@ -0,0 +1,87 @@
+pub mod common;
+
+use std::f32::consts::PI;
+
+use common::{scratch_dir, square, write_wav, RATE};
+use cueball::{
+    audio::{analyze_now, gain_to_db, LoudnessMeter},
+    cues::{AudioCue, CueTime},
+    MultitypeCue, Project,
+};
+
+// interleaved stereo sine, the same on both channels
+fn measure(rate: u32, seconds: f32, freq: f32, amplitude: f32, phase: f32) -> LoudnessMeter {
+    let mut meter = LoudnessMeter::new(2, rate);
+    for n in 0..(rate as f32 * seconds) as usize {
+        let sample = amplitude * (2. * PI * freq * n as f32 / rate as f32 + phase).sin();
+        meter.push(sample);
+        meter.push(sample);
+    }
+    meter
+}
+
+#[test]
+fn measures_reference_tone() {
+    // a 1 kHz stereo sine at -23 dBFS reads -23 LUFS, by the EBU's own test
+    let meter = measure(48000, 5., 1000., 10f32.powf(-23. / 20.), 0.);
+    let loudness = meter.integrated().unwrap();
+    assert!((loudness + 23.).abs() < 0.1, "measured {} LUFS", loudness);
+
+    // silence can't be measured at all
+    let meter = measure(48000, 1., 1000., 0., 0.);
+    assert_eq!(meter.integrated(), None);
+}
+
+#[test]
+fn finds_peaks_between_samples() {
+    // a quarter of the sample rate, sampled at 45 degrees, never lands on
+    // its peaks
+    let meter = measure(48000, 1., 12000., 1., PI / 4.);
+    let true_peak = gain_to_db(meter.true_peak());
+    assert!(true_peak.abs() < 0.5, "true peak {} dBTP", true_peak);
+}
+
+#[test]
+fn normalizes_the_part_that_plays() {
+    let dir = scratch_dir("normalize");
+    let wav = dir.join("quiet-then-loud.wav");
+    // a quarter second 24 dB down, then a quarter second at half scale
+    write_wav(&wav, |frame| match frame < RATE / 4 {
+        true => square(frame) / 16,
+        false => square(frame),
+    });
+    analyze_now(&wav).unwrap();
+
+    let mut cue = AudioCue::with_id("1".to_string());
+    cue.file_path = wav.to_str().unwrap().to_string();
+    let whole = cue.normalize(-23.).unwrap();
+    // trimming off the loud end leaves only the quiet start to bring up
+    cue.end = CueTime::from_millis(250);
+    let quiet = cue.normalize(-23.).unwrap();
+    assert!(quiet - whole > 20., "trims {} and {}", whole, quiet);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn trim_round_trips() {
+    let mut project = Project::default();
+    project.loudness_target = -16.;
+    let mut cue = AudioCue::with_id("1".to_string());
+    cue.set_trim(-6.5);
+    project.cue_lists[0].add(MultitypeCue::Audio(cue)).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("cueball-loudness-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("loudness.cueball");
+    project.save_to(&path).unwrap();
+
+    let reloaded = Project::read(&path).unwrap();
+    assert_eq!(reloaded.loudness_target, -16.);
+    match &reloaded.cue_lists[0][0] {
+        MultitypeCue::Audio(cue) => assert_eq!(cue.get_trim(), -6.5),
+        other => panic!("expected audio cue, got {:?}", other),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
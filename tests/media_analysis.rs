@@ -0,0 +1,125 @@
+pub mod common;
+
+use std::{
+    fs,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{scratch_dir, square, write_wav};
+use cueball::{
+    audio::{analyze, analyze_now, MediaStatus},
+    cues::{AudioCue, CueTime},
+    project::ProjectProblem,
+    Cue, MultitypeCue, Project,
+};
+
+#[test]
+fn analyzes_in_background() {
+    let dir = scratch_dir("analysis");
+    let wav = dir.join("square.wav");
+    write_wav(&wav, square);
+
+    let analysis = analyze_now(&wav).unwrap();
+    assert_eq!(analysis.info.channels, 2);
+    assert_eq!(analysis.info.rate, 8000);
+    assert_eq!(analysis.info.frames, 4000);
+    assert!((analysis.info.length() - 0.5).abs() < 0.001);
+    assert!((analysis.info.peak - 0.5).abs() < 0.01);
+    assert!((analysis.info.rms - 0.5).abs() < 0.01);
+
+    // working it out on the spot leaves it for everyone else
+    match analyze(&wav) {
+        MediaStatus::Ready(ready) => assert!(Arc::ptr_eq(&ready, &analysis)),
+        other => panic!("analysis wasn't kept: {:?}", other),
+    }
+
+    // a cue picks up the duration once the worker is done with its file
+    let mut project = Project::default();
+    let mut cue = AudioCue::with_id("1".to_string());
+    cue.file_path = wav.to_str().unwrap().to_string();
+    cue.start = CueTime::from_millis(100);
+    project.cue_lists[0].add(MultitypeCue::Audio(cue)).unwrap();
+
+    let started = Instant::now();
+    while project.update_media() {
+        assert!(started.elapsed() < Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(10));
+    }
+    let length = project.cue_lists[0][0].length().unwrap();
+    assert_eq!(length, CueTime::from_millis(400));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reports_undecodable_media() {
+    let dir = scratch_dir("undecodable");
+    let bad = dir.join("noise.wav");
+    fs::write(&bad, b"not audio").unwrap();
+
+    let started = Instant::now();
+    loop {
+        match analyze(&bad) {
+            MediaStatus::Failed(_) => break,
+            MediaStatus::Ready(_) => panic!("garbage decoded as audio"),
+            _ => {
+                assert!(started.elapsed() < Duration::from_secs(10));
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    // cues on a file that isn't there or isn't audio show as errored, and
+    // won't fire
+    let mut project = Project::default();
+    for (id, path) in [("1", bad.clone()), ("2", dir.join("missing.wav"))] {
+        let mut cue = AudioCue::with_id(id.to_string());
+        cue.file_path = path.to_str().unwrap().to_string();
+        project.cue_lists[0].add(MultitypeCue::Audio(cue)).unwrap();
+    }
+    let started = Instant::now();
+    while project.update_media() {
+        assert!(started.elapsed() < Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(10));
+    }
+    for cue in project.cue_lists[0].iter() {
+        assert!(cue.is_errored() && !cue.can_fire(), "cue {}", cue.get_id());
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn checks_readiness_in_background() {
+    let dir = scratch_dir("readiness");
+    let wav = dir.join("square.wav");
+    write_wav(&wav, square);
+
+    // trimmed by more than the half second there is
+    let mut project = Project::default();
+    let mut cue = AudioCue::with_id("1".to_string());
+    cue.file_path = wav.to_str().unwrap().to_string();
+    cue.start = CueTime::from_millis(400);
+    cue.end = CueTime::from_millis(400);
+    project.cue_lists[0].add(MultitypeCue::Audio(cue)).unwrap();
+
+    let started = Instant::now();
+    let progress = loop {
+        let progress = project.check_progress();
+        assert_eq!(progress.media, 1);
+        if progress.is_done() {
+            break progress;
+        }
+        assert!(started.elapsed() < Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(10));
+    };
+    assert!(progress
+        .problems
+        .iter()
+        .any(|p| matches!(p, ProjectProblem::TrimTooLong { .. })));
+    assert_eq!(progress.problems, project.check());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,97 @@
+use cueball::audio::{
+    self, dbap_gains, default_speakers, placement_gains, position_at, Backend, Keyframe, Panned,
+    Placement, PlacementHandle,
+};
+use cueball::cues::CueTime;
+use rodio::{buffer::SamplesBuffer, Source};
+
+#[test]
+fn pans_between_speakers() {
+    let stereo = default_speakers(2);
+
+    // a mono source in the middle is 3 dB down in each speaker
+    let gains = placement_gains(&Placement::Pan(0.), 1, &stereo, CueTime::ZERO);
+    assert!((gains[0] - 0.5f32.sqrt()).abs() < 1e-6);
+    assert!((gains[1] - 0.5f32.sqrt()).abs() < 1e-6);
+
+    // a stereo source panned hard right keeps only its right channel
+    let gains = placement_gains(&Placement::Pan(1.), 2, &stereo, CueTime::ZERO);
+    assert!(gains[0].abs() < 1e-6 && gains[1] == 0.);
+    assert_eq!((gains[2], gains[3]), (0., 1.));
+
+    // distance based panning keeps the power the same, and is loudest in
+    // the nearest speaker
+    let ring = default_speakers(4);
+    for (x, y) in [(0., 0.), (0.7, 0.7), (-1., 0.2)] {
+        let gains = dbap_gains(&ring, x, y);
+        let power: f32 = gains.iter().map(|g| g * g).sum();
+        assert!((power - 1.).abs() < 1e-5);
+    }
+    let gains = dbap_gains(&ring, ring[2].x, ring[2].y);
+    assert!(gains.iter().all(|g| *g <= gains[2]));
+}
+
+#[test]
+fn moves_through_keyframes() {
+    let path = [
+        Keyframe {
+            time: CueTime::from_millis(1000),
+            x: -1.,
+            y: 0.,
+        },
+        Keyframe {
+            time: CueTime::from_millis(3000),
+            x: 1.,
+            y: 0.5,
+        },
+    ];
+    assert_eq!(position_at(&path, CueTime::ZERO), Some((-1., 0.)));
+    assert_eq!(
+        position_at(&path, CueTime::from_millis(2000)),
+        Some((0., 0.25))
+    );
+    assert_eq!(
+        position_at(&path, CueTime::from_millis(10000)),
+        Some((1., 0.5))
+    );
+    assert_eq!(position_at(&[], CueTime::from_millis(1000)), None);
+}
+
+#[test]
+fn mixes_to_every_speaker() {
+    let speakers = default_speakers(4);
+    let mono = SamplesBuffer::new(1, 1000, vec![1.; 100]);
+    let panned = Panned::new(
+        mono,
+        &Placement::Object(vec![Keyframe {
+            time: CueTime::ZERO,
+            x: 0.,
+            y: 0.,
+        }]),
+        speakers,
+        PlacementHandle::default(),
+    );
+    assert_eq!(panned.channels(), 4);
+    let samples: Vec<f32> = panned.collect();
+    assert_eq!(samples.len(), 400);
+    // in the middle of a ring, every speaker gets the same
+    assert!(samples.iter().all(|s| (s - 0.5).abs() < 1e-5));
+}
+
+#[test]
+fn keeps_one_speaker_per_output_channel() {
+    audio::init(Backend::Virtual {
+        channels: 2,
+        rate: 48000,
+    })
+    .unwrap();
+
+    // a layout for a different output falls back to the default one
+    audio::set_speakers(&default_speakers(6));
+    assert_eq!(audio::speakers().unwrap(), default_speakers(2));
+
+    let mut moved = default_speakers(2);
+    moved[0].x = -0.5;
+    audio::set_speakers(&moved);
+    assert_eq!(audio::speakers().unwrap(), moved);
+}
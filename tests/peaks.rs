@@ -0,0 +1,43 @@
+use cueball::audio::Peaks;
+
+// a second of stereo at a low rate: a ramp on the left, silence on the right
+// apart from one spike
+fn stereo_ramp() -> Peaks {
+    let rate = 1000;
+    let mut samples = Vec::new();
+    for frame in 0..rate {
+        samples.push(frame as f32 / rate as f32 * 2. - 1.);
+        samples.push(if frame == 700 { 0.5 } else { 0. });
+    }
+    Peaks::from_samples(2, rate as u32, &samples)
+}
+
+#[test]
+fn envelope_at_any_zoom() {
+    let peaks = stereo_ramp();
+    assert_eq!(peaks.channels(), 2);
+    assert_eq!(peaks.frames(), 1000);
+    assert_eq!(peaks.length(), 1.);
+
+    // the whole file, read from the coarsest level
+    let (min, max) = peaks.envelope(0, 0, 1000);
+    assert!(min < -0.99 && max > 0.99);
+    let (min, max) = peaks.envelope(1, 0, 1000);
+    assert!(min.abs() < 0.001 && (max - 0.5).abs() < 0.001);
+
+    // a few frames, read from the finest level
+    let (min, max) = peaks.envelope(1, 0, 10);
+    assert_eq!((min, max), (0., 0.));
+    let (min, max) = peaks.envelope(0, 500, 501);
+    assert!(min > -0.05 && max < 0.05);
+}
+
+#[test]
+fn peak_files_round_trip() {
+    let peaks = stereo_ramp();
+    let bytes = peaks.to_bytes();
+    assert_eq!(Peaks::from_bytes(&bytes).unwrap(), peaks);
+
+    assert!(Peaks::from_bytes(&bytes[..bytes.len() - 3]).is_err());
+    assert!(Peaks::from_bytes(b"not a peak file").is_err());
+}
@@ -0,0 +1,174 @@
+pub mod common;
+
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use common::{dc, scratch_dir, write_wav, RATE};
+use cueball::{
+    audio::{self, Backend},
+    cues::{AudioCue, ContinueMode, CueRunning, CueTime},
+    Cue, MultitypeCue, Project,
+};
+
+// sinks pick up pauses and stops every 5 ms of audio
+const CONTROL_FRAMES: usize = RATE as usize / 200;
+
+// a project on a virtual output, with an audio cue of `wav` for each of
+// `timing`'s (pre-wait, continue mode)
+fn playback_project(wav: &Path, timing: &[(f32, ContinueMode)]) -> Project {
+    audio::init(Backend::Virtual {
+        channels: 2,
+        rate: RATE,
+    })
+    .unwrap();
+    let mut project = Project::default();
+    for (i, (pre_wait, mode)) in timing.iter().enumerate() {
+        let mut cue = AudioCue::with_id((i + 1).to_string());
+        cue.file_path = wav.to_str().unwrap().to_string();
+        let mut cue = MultitypeCue::Audio(cue);
+        cue.set_pre_wait(CueTime::from_secs_f32(*pre_wait));
+        cue.set_continue(*mode);
+        let i = project.cue_lists[0].add(cue).unwrap();
+        // rather than waiting on the analysis worker
+        if let MultitypeCue::Audio(cue) = &mut project.cue_lists[0][i] {
+            cue.load_now().unwrap();
+        }
+    }
+    project
+}
+
+// advance the output, returning the left channel of what it played
+fn play(length: f32) -> Vec<f32> {
+    audio::advance(Duration::from_secs_f32(length)).unwrap();
+    audio::take_recording()
+        .unwrap()
+        .into_iter()
+        .step_by(2)
+        .collect()
+}
+
+// what was played once a pause or stop had been picked up
+fn settled(samples: Vec<f32>) -> Vec<f32> {
+    samples[CONTROL_FRAMES..].to_vec()
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 0.01
+}
+
+#[test]
+fn plays_pauses_and_stops() {
+    let dir = scratch_dir("playback");
+    let wav = dir.join("dc.wav");
+    write_wav(&wav, dc);
+    let mut project = playback_project(&wav, &[(0., ContinueMode::DoNotContinue)]);
+    let cue = &mut project.cue_lists[0][0];
+
+    // nothing plays until the clock moves
+    cue.go();
+    assert_eq!(cue.running(), CueRunning::Running);
+    assert!(play(0.2).iter().all(|s| close(*s, 0.5)));
+    // rodio's position runs a span ahead, so only how it moves is checked
+    let elapsed = cue.elapsed().unwrap().as_secs_f32();
+    assert!((0.2..0.25).contains(&elapsed), "{}", elapsed);
+
+    // paused cues keep their place and play silence
+    cue.set_paused(true);
+    assert!(settled(play(0.1)).iter().all(|s| *s == 0.));
+    assert_eq!(cue.running(), CueRunning::Paused);
+    let paused_at = cue.elapsed().unwrap().as_secs_f32();
+    assert!(close(paused_at, elapsed));
+    cue.set_paused(false);
+    assert!(settled(play(0.1)).iter().all(|s| close(*s, 0.5)));
+    assert!(close(cue.elapsed().unwrap().as_secs_f32(), paused_at + 0.1));
+
+    // stopping is immediate, without waiting on the output
+    cue.stop();
+    assert_eq!(cue.running(), CueRunning::Stopped);
+    assert_eq!(cue.elapsed(), None);
+    assert!(settled(play(0.1)).iter().all(|s| *s == 0.));
+
+    // and a cue left alone stops at its end
+    cue.go();
+    play(0.6);
+    assert_eq!(cue.running(), CueRunning::Stopped);
+    assert!(close(audio::clock().unwrap().as_secs_f32(), 1.1));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fades_out() {
+    let dir = scratch_dir("playback-fade");
+    let wav = dir.join("dc.wav");
+    write_wav(&wav, dc);
+    let mut project = playback_project(&wav, &[(0., ContinueMode::DoNotContinue)]);
+    let cue = &mut project.cue_lists[0][0];
+
+    cue.go();
+    play(0.1);
+    cue.fade_out(CueTime::from_millis(200));
+    let fade = play(0.1);
+    assert!(fade.windows(2).all(|w| w[1] <= w[0]));
+    assert!(fade[fade.len() - 1] < 0.5 && fade[fade.len() - 1] > 0.);
+    assert_eq!(cue.running(), CueRunning::Running);
+
+    // the cue stops once the fade is over, before the file runs out
+    play(0.15);
+    assert_eq!(cue.running(), CueRunning::Stopped);
+    assert!(play(0.05).iter().all(|s| *s == 0.));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn continues_on_the_output_clock() {
+    let dir = scratch_dir("playback-continue");
+    let wav = dir.join("dc.wav");
+    write_wav(&wav, dc);
+    // 1 auto-continues into 2 after a tenth of a second, which auto-follows
+    // into 3 after another
+    let mut project = playback_project(
+        &wav,
+        &[
+            (0., ContinueMode::AutoContinue),
+            (0.1, ContinueMode::AutoFollow),
+            (0.1, ContinueMode::DoNotContinue),
+        ],
+    );
+
+    // run the sequencer off the output's clock rather than the wall clock
+    let start = Instant::now();
+    project.go_cue_at(0, 0, start);
+    let mut played = Vec::new();
+    let mut fired = [None; 3];
+    while audio::clock().unwrap() < Duration::from_secs_f32(1.5) {
+        played.extend(play(0.005));
+        let now = audio::clock().unwrap();
+        project.update(start + now);
+        for (i, fired) in fired.iter_mut().enumerate() {
+            if fired.is_none() && project.cue_lists[0][i].running() != CueRunning::Stopped {
+                *fired = Some(now.as_secs_f32());
+            }
+        }
+    }
+
+    assert_eq!(fired[0], Some(0.005));
+    assert!(close(fired[1].unwrap(), 0.1), "{:?}", fired);
+    assert!(close(fired[2].unwrap(), 0.7), "{:?}", fired);
+    assert!(!project.sequence_pending());
+
+    // 1 and 2 overlap, then there's a gap while 3 waits out its pre-wait
+    let at = |time: f32| played[(time * RATE as f32) as usize];
+    assert!(close(at(0.05), 0.5));
+    assert!(close(at(0.3), 1.));
+    assert!(close(at(0.55), 0.5));
+    assert_eq!(at(0.65), 0.);
+    assert!(close(at(0.9), 0.5));
+    assert_eq!(at(1.3), 0.);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,251 @@
+use std::path::PathBuf;
+
+use cueball::{
+    cues::{BonkCue, Cart, CueTime, RemarkCue},
+    project::{FormatTooNewError, ProjectProblem, FORMAT_VERSION},
+    Cue, MultitypeCue, Project,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn audio_cue(project: &Project, id: &str) -> cueball::cues::AudioCue {
+    match project.cue_lists[0].get_cue(id.to_string()) {
+        Some(MultitypeCue::Audio(cue)) => cue.clone(),
+        other => panic!("expected audio cue {}, got {:?}", id, other),
+    }
+}
+
+#[test]
+fn loads_v0() {
+    let project = Project::read(fixture("project_v0.cueball")).unwrap();
+
+    assert_eq!(project.name, "Fixture v0");
+    assert_eq!(project.cue_lists[0].len(), 3);
+    assert_eq!(project.cue_lists[0][0].get_name(), "House to half");
+    assert!(!project.cue_lists[0][1].is_armed());
+
+    // v0 files predate the volume, enabled and armed fields
+    let audio = audio_cue(&project, "3");
+    assert_eq!(audio.get_volume(), 0.5);
+    assert!(audio.is_enabled());
+    assert!(audio.is_armed());
+    assert_eq!(audio.start, CueTime::from_millis(1500));
+    assert_eq!(audio.end, CueTime::from_millis(250));
+}
+
+#[test]
+fn loads_v1() {
+    let project = Project::read(fixture("project_v1.cueball")).unwrap();
+
+    assert_eq!(project.name, "Fixture v1");
+    assert_eq!(project.cue_lists[0].len(), 3);
+
+    let audio = audio_cue(&project, "3");
+    assert_eq!(audio.get_volume(), 0.8);
+    assert!(!audio.is_enabled());
+    assert_eq!(audio.file_path, "/home/stage/media/preshow.wav");
+}
+
+#[test]
+fn v1_cues_become_main_list() {
+    let project = Project::read(fixture("project_v1.cueball")).unwrap();
+
+    assert_eq!(project.cue_lists.len(), 1);
+    assert_eq!(project.cue_lists[0].name, "Main");
+    assert_eq!(project.cue_lists[0].go_key.as_deref(), Some("Space"));
+}
+
+#[test]
+fn loads_v2() {
+    let project = Project::read(fixture("project_v2.cueball")).unwrap();
+
+    assert_eq!(project.cue_lists.len(), 2);
+    assert_eq!(project.cue_lists[1].name, "Sound FX");
+    assert_eq!(project.cue_lists[1].go_key.as_deref(), Some("Enter"));
+
+    // control cues can reach into other lists with `list:id`
+    assert_eq!(project.resolve_ref(0, "2"), Some((0, 1)));
+    assert_eq!(project.resolve_ref(0, "Sound FX:1"), Some((1, 0)));
+    assert_eq!(project.resolve_ref(1, "1"), Some((1, 0)));
+    assert_eq!(project.resolve_ref(0, "Nowhere:1"), None);
+    assert!(project
+        .check()
+        .iter()
+        .all(|p| !matches!(p, cueball::project::ProjectProblem::BrokenReferent { .. })));
+}
+
+#[test]
+fn rejects_newer_files() {
+    let err = Project::read(fixture("project_future.cueball")).unwrap_err();
+    let too_new = err.downcast_ref::<FormatTooNewError>().unwrap();
+    assert_eq!(too_new.found, 999);
+}
+
+#[test]
+fn renaming_a_list_keeps_references() {
+    let mut project = Project::read(fixture("project_v2.cueball")).unwrap();
+    let broken = |project: &Project| {
+        project
+            .check()
+            .into_iter()
+            .filter(|p| matches!(p, ProjectProblem::BrokenReferent { .. }))
+            .count()
+    };
+
+    project.rename_list(1, "SFX").unwrap();
+    assert_eq!(project.resolve_ref(0, "SFX:1"), Some((1, 0)));
+    match &project.cue_lists[0][0] {
+        MultitypeCue::Control(cue) => assert_eq!(cue.target, "SFX:1"),
+        other => panic!("expected control cue, got {:?}", other),
+    }
+    assert_eq!(broken(&project), 0);
+
+    // names have to stay unique and usable in a reference
+    assert!(project.rename_list(1, "Main").is_err());
+    assert!(project.rename_list(1, "Sound:FX").is_err());
+
+    // a reference to a list that's gone is a problem
+    project.cue_lists[1].name = "Elsewhere".to_string();
+    assert_eq!(broken(&project), 1);
+}
+
+#[test]
+fn saves_current_version() {
+    let mut project = Project::read(fixture("project_v0.cueball")).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("cueball-format-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("saved.cueball");
+    project.save_to(&path).unwrap();
+
+    let doc: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(doc["version"], FORMAT_VERSION);
+
+    // cue equality only looks at ids, names and files, so check the fields
+    // migrated from v0 by hand
+    let mut reloaded = Project::read(&path).unwrap();
+    assert_eq!(reloaded.cue_lists[0].len(), project.cue_lists[0].len());
+    let audio = audio_cue(&reloaded, "3");
+    assert_eq!(audio, audio_cue(&project, "3"));
+    assert_eq!(audio.get_volume(), 0.5);
+    assert!(audio.is_enabled());
+    assert!(audio.is_armed());
+    assert_eq!(audio.start, CueTime::from_millis(1500));
+    assert_eq!(audio.end, CueTime::from_millis(250));
+
+    // and that nothing else changed, by saving again and comparing documents
+    let resaved = dir.join("resaved.cueball");
+    reloaded.save_to(&resaved).unwrap();
+    let read_doc = |path: &PathBuf| {
+        let mut doc: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        doc.as_object_mut().unwrap().remove("path");
+        doc
+    };
+    assert_eq!(read_doc(&resaved), read_doc(&path));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn carts_round_trip() {
+    // files from before carts existed have none
+    let mut project = Project::read(fixture("project_v1.cueball")).unwrap();
+    assert!(project.carts.is_empty());
+
+    let mut cart = Cart::new("SFX", 2, 3);
+    cart.set_cell(1, 2, MultitypeCue::Remark(RemarkCue::with_id("1")));
+    let i = cart.set_cell(0, 0, MultitypeCue::Bonk(BonkCue::with_id("2")));
+    cart.cell_mut(i).cue.set_hotkey(Some("F1".to_string()));
+    project.carts.push(cart);
+
+    let dir = std::env::temp_dir().join(format!("cueball-carts-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("carts.cueball");
+    project.save_to(&path).unwrap();
+
+    let reloaded = Project::read(&path).unwrap();
+    assert_eq!(reloaded.carts, project.carts);
+    let cart = &reloaded.carts[0];
+    assert_eq!(
+        cart.cell(cart.cell_index(0, 0).unwrap())
+            .cue
+            .get_hotkey()
+            .map(String::as_str),
+        Some("F1")
+    );
+    assert_eq!(cart.cell_index(0, 1), None);
+    assert_eq!(cart.first_empty_cell(), Some((0, 1)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn v2_cart_hotkeys_move_to_their_cues() {
+    let project = Project::read(fixture("project_v2.cueball")).unwrap();
+    let hotkeys: Vec<_> = project.carts[0]
+        .cells()
+        .map(|cell| cell.cue.get_hotkey().cloned())
+        .collect();
+    // a cue's own hotkey wins over its cell's
+    assert_eq!(hotkeys, vec![Some("F1".to_string()), Some("P".to_string())]);
+}
+
+#[test]
+fn cue_hotkeys_round_trip() {
+    // files from before cue hotkeys existed have none
+    let mut project = Project::read(fixture("project_v1.cueball")).unwrap();
+    assert!(project.cues().all(|cue| cue.get_hotkey().is_none()));
+
+    let mut cue = MultitypeCue::Bonk(BonkCue::with_id("99"));
+    cue.set_hotkey(Some("F5".to_string()));
+    project.cue_lists[0].add(cue).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("cueball-hotkeys-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("hotkeys.cueball");
+    project.save_to(&path).unwrap();
+
+    let reloaded = Project::read(&path).unwrap();
+    let cue = reloaded.cue_lists[0].get_cue("99".to_string()).unwrap();
+    assert_eq!(cue.get_hotkey().map(String::as_str), Some("F5"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cue_markings_round_trip() {
+    let mut project = Project::read(fixture("project_v1.cueball")).unwrap();
+    // files from before every cue had markings have none
+    assert!(project
+        .cues()
+        .all(|cue| cue.get_color().is_none() && !cue.is_flagged()));
+
+    let cue = &mut project.cue_lists[0][0];
+    cue.set_color(Some([10, 20, 30]));
+    cue.set_flagged(true);
+    cue.set_notes("check level in tech");
+    let mut remark = MultitypeCue::Remark(RemarkCue::with_id("99"));
+    remark.set_notes("house open");
+    project.cue_lists[0].add(remark).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("cueball-markings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("markings.cueball");
+    project.save_to(&path).unwrap();
+
+    let reloaded = Project::read(&path).unwrap();
+    let cue = &reloaded.cue_lists[0][0];
+    assert_eq!(cue.get_color(), Some([10, 20, 30]));
+    assert!(cue.is_flagged());
+    assert_eq!(cue.get_notes(), "check level in tech");
+    let remark = reloaded.cue_lists[0].get_cue("99".to_string()).unwrap();
+    assert_eq!(remark.get_notes(), "house open");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
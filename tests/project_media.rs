@@ -0,0 +1,127 @@
+pub mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::scratch_dir;
+use cueball::{project::backup, Cue, Project};
+
+fn write_project(dir: &Path, media_path: &str) -> PathBuf {
+    let path = dir.join("show.cueball");
+    let doc = serde_json::json!({
+        "version": 1,
+        "name": "Media",
+        "path": null,
+        "cues": {"list": [{"Audio": {
+            "id": "1",
+            "name": "Thunder",
+            "file_path": media_path,
+            "start": 0.0,
+            "end": 0.0,
+            "volume": 1.0,
+            "enabled": true,
+            "armed": true,
+        }}]},
+    });
+    fs::write(&path, doc.to_string()).unwrap();
+    path
+}
+
+fn media_of(project: &Project) -> String {
+    project.cue_lists[0][0].get_media()[0].clone()
+}
+
+#[test]
+fn relative_paths_round_trip() {
+    let dir = scratch_dir("relative");
+    fs::create_dir_all(dir.join("media")).unwrap();
+    fs::write(dir.join("media/thunder.wav"), b"").unwrap();
+    let path = write_project(&dir, "media/thunder.wav");
+
+    let mut project = Project::read(&path).unwrap();
+    let absolute = dir.join("media/thunder.wav");
+    assert_eq!(media_of(&project), absolute.to_str().unwrap());
+
+    // saving writes the relative path, but leaves the loaded project alone
+    project.save_to(&path).unwrap();
+    assert_eq!(media_of(&project), absolute.to_str().unwrap());
+    let doc: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(
+        doc["cue_lists"][0]["list"][0]["Audio"]["file_path"],
+        "media/thunder.wav"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn relinks_missing_media_by_name() {
+    let dir = scratch_dir("relink");
+    fs::create_dir_all(dir.join("found/deeper")).unwrap();
+    fs::write(dir.join("found/deeper/thunder.wav"), b"").unwrap();
+    let path = write_project(&dir, "/nowhere/thunder.wav");
+
+    let mut project = Project::read(&path).unwrap();
+    assert_eq!(project.missing_media(), vec!["/nowhere/thunder.wav"]);
+
+    let report = project.relink_media(&dir.join("found")).unwrap();
+    assert_eq!(report.relinked.len(), 1);
+    assert!(report.still_missing.is_empty());
+    assert!(project.missing_media().is_empty());
+    assert_eq!(
+        media_of(&project),
+        dir.join("found/deeper/thunder.wav").to_str().unwrap()
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn collects_media_into_bundle() {
+    let dir = scratch_dir("collect");
+    fs::create_dir_all(dir.join("elsewhere")).unwrap();
+    fs::write(dir.join("elsewhere/thunder.wav"), b"rumble").unwrap();
+    let media = dir.join("elsewhere/thunder.wav");
+    let path = write_project(&dir, media.to_str().unwrap());
+
+    let mut project = Project::read(&path).unwrap();
+    let bundle = dir.join("bundle");
+    let saved = project.collect(&bundle).unwrap();
+
+    assert_eq!(saved, bundle.join("Media.cueball"));
+    assert_eq!(
+        fs::read(bundle.join("media/thunder.wav")).unwrap(),
+        b"rumble"
+    );
+
+    let reloaded = Project::read(&saved).unwrap();
+    assert_eq!(
+        media_of(&reloaded),
+        bundle.join("media/thunder.wav").to_str().unwrap()
+    );
+
+    // names that would reach outside the bundle are made safe
+    project.name = "../Act 1: Storm".to_string();
+    assert_eq!(project.file_stem(), "_Act 1_ Storm");
+    let saved = project.collect(&bundle).unwrap();
+    assert_eq!(saved, bundle.join("_Act 1_ Storm.cueball"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn keeps_backups_saved_in_quick_succession() {
+    let dir = scratch_dir("backups");
+    let path = write_project(&dir, "thunder.wav");
+
+    // saving several times a second still keeps each backup
+    for _ in 0..3 {
+        backup::rotate_backups(&path, 2).unwrap();
+    }
+    let backups = fs::read_dir(backup::backup_dir(&path)).unwrap().count();
+    assert_eq!(backups, 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
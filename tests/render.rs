@@ -0,0 +1,118 @@
+pub mod common;
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+use common::{dc, scratch_dir, write_wav};
+use cueball::{
+    audio::default_speakers,
+    cues::{AudioCue, ContinueMode, CueTime},
+    project::RenderOptions,
+    Cue, MultitypeCue, Project,
+};
+use rodio::{Decoder, Source};
+
+// cue 1 auto-follows into cue 2, which waits a quarter second first
+fn render_project(wav: &Path) -> Project {
+    let mut project = Project::default();
+    for (i, (pre_wait, mode)) in [
+        (0, ContinueMode::AutoFollow),
+        (250, ContinueMode::DoNotContinue),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let mut cue = AudioCue::with_id((i + 1).to_string());
+        cue.file_path = wav.to_str().unwrap().to_string();
+        let mut cue = MultitypeCue::Audio(cue);
+        cue.set_pre_wait(CueTime::from_millis(pre_wait));
+        cue.set_continue(mode);
+        project.cue_lists[0].add(cue).unwrap();
+    }
+    project
+}
+
+fn render(project: &mut Project, options: RenderOptions) -> Vec<f32> {
+    let mut samples = Vec::new();
+    project
+        .render(0, 0, 1, options, |chunk| {
+            samples.extend_from_slice(chunk);
+            Ok(())
+        })
+        .unwrap();
+    samples
+}
+
+#[test]
+fn renders_sequence_offline() {
+    let dir = scratch_dir("render");
+    let wav = dir.join("dc.wav");
+    write_wav(&wav, dc);
+    let mut project = render_project(&wav);
+
+    let options = RenderOptions {
+        channels: 2,
+        rate: 8000,
+        tail: CueTime::ZERO,
+    };
+    let samples = render(&mut project, options);
+    // rendering loads the cues, so their lengths place the auto-follow
+    assert_eq!(
+        project.render_schedule(0, 0, 1),
+        vec![(0, CueTime::ZERO), (1, CueTime::from_millis(750))]
+    );
+    assert_eq!(samples.len(), 2 * 10000);
+
+    // the pre-wait between the cues is silent, and both cues play at level
+    let at = |time: f32| samples[2 * (time * 8000.) as usize];
+    assert!((at(0.25) - 0.5).abs() < 0.01, "{}", at(0.25));
+    assert_eq!(at(0.6), 0.);
+    assert!((at(1.) - 0.5).abs() < 0.01, "{}", at(1.));
+
+    // and it comes out the same every time
+    assert_eq!(render(&mut project, options), samples);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn writes_multichannel_wav() {
+    let dir = scratch_dir("render-wav");
+    let wav = dir.join("dc.wav");
+    write_wav(&wav, dc);
+    let mut project = render_project(&wav);
+
+    let options = RenderOptions {
+        channels: 4,
+        rate: 16000,
+        tail: CueTime::from_millis(500),
+    };
+    let out = dir.join("render.wav");
+    let report = project.render_to(0, 0, 1, options, &out).unwrap();
+    assert_eq!(report.frames, 28000);
+    assert!(report.skipped.is_empty());
+
+    let decoder = Decoder::new(BufReader::new(File::open(&out).unwrap())).unwrap();
+    assert_eq!(decoder.channels(), 4);
+    assert_eq!(decoder.sample_rate(), 16000);
+    let decoded: Vec<f32> = decoder.collect();
+    assert_eq!(decoded, render(&mut project, options));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn refuses_empty_list() {
+    let mut project = Project::default();
+    assert!(project
+        .render(0, 0, 0, RenderOptions::default(), |_| Ok(()))
+        .is_err());
+
+    // the project's own layout decides how many channels to mix
+    assert_eq!(project.render_options().channels, 2);
+    project.speakers = default_speakers(6);
+    assert_eq!(project.render_options().channels, 6);
+}
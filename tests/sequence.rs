@@ -0,0 +1,167 @@
+use std::time::{Duration, Instant};
+
+use cueball::{
+    cues::{BonkCue, ContinueMode, CueList, CueTime},
+    project::SequenceEntry,
+    Cue, MultitypeCue, Project,
+};
+
+// four bonk cues: 1 auto-continues into 2 after a second of pre-wait, 2
+// auto-follows into 3, and 4 stands on its own
+fn sequenced_project() -> Project {
+    let mut project = Project::default();
+    let timing = [
+        (1000, ContinueMode::AutoContinue),
+        (500, ContinueMode::AutoFollow),
+        (2000, ContinueMode::DoNotContinue),
+        (0, ContinueMode::DoNotContinue),
+    ];
+    for (i, (pre_wait, mode)) in timing.into_iter().enumerate() {
+        let mut cue = MultitypeCue::Bonk(BonkCue::with_id((i + 1).to_string()));
+        cue.set_pre_wait(CueTime::from_millis(pre_wait));
+        cue.set_continue(mode);
+        project.cue_lists[0].add(cue).unwrap();
+    }
+    project
+}
+
+fn bonks(project: &Project) -> Vec<u64> {
+    project.cue_lists[0]
+        .iter()
+        .map(|cue| match cue {
+            MultitypeCue::Bonk(bonk) => bonk.ctr,
+            other => panic!("expected bonk cue, got {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn lays_out_sequence() {
+    let project = sequenced_project();
+
+    assert_eq!(project.sequence_start(0, 2), 0);
+    assert_eq!(project.sequence_start(0, 3), 3);
+    assert_eq!(
+        project.sequence(0, 0),
+        vec![
+            SequenceEntry {
+                index: 0,
+                triggered: CueTime::from_millis(0),
+                fired: CueTime::from_millis(1000),
+                length: None,
+            },
+            SequenceEntry {
+                index: 1,
+                triggered: CueTime::from_millis(1000),
+                fired: CueTime::from_millis(1500),
+                length: None,
+            },
+            SequenceEntry {
+                index: 2,
+                triggered: CueTime::from_millis(1500),
+                fired: CueTime::from_millis(3500),
+                length: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn fires_after_pre_wait() {
+    let mut project = sequenced_project();
+    let start = Instant::now();
+
+    // GO moves the playhead past the whole sequence
+    assert_eq!(project.go_cue_at(0, 0, start), 3);
+    assert_eq!(bonks(&project), vec![0, 0, 0, 0]);
+    assert_eq!(
+        project.pre_wait_remaining(0, 0, start),
+        Some(CueTime::from_millis(1000))
+    );
+
+    project.update(start + Duration::from_millis(1000));
+    assert_eq!(bonks(&project), vec![1, 0, 0, 0]);
+    assert_eq!(
+        project.pre_wait_remaining(0, 1, start + Duration::from_millis(1000)),
+        Some(CueTime::from_millis(500))
+    );
+
+    // bonks finish straight away, so 2 follows into 3 on the next update
+    project.update(start + Duration::from_millis(1500));
+    assert_eq!(bonks(&project), vec![1, 1, 0, 0]);
+    project.update(start + Duration::from_millis(1500));
+    project.update(start + Duration::from_millis(3500));
+    assert_eq!(bonks(&project), vec![1, 1, 1, 0]);
+    assert!(!project.sequence_pending());
+}
+
+#[test]
+fn stop_all_cancels_pre_wait() {
+    let mut project = sequenced_project();
+    let start = Instant::now();
+
+    project.go_cue_at(0, 0, start);
+    project.stop_all();
+    project.update(start + Duration::from_secs(10));
+    assert_eq!(bonks(&project), vec![0, 0, 0, 0]);
+}
+
+#[test]
+fn pending_cues_survive_edits() {
+    let mut project = sequenced_project();
+    let start = Instant::now();
+
+    // cue 1 is counting down when it is moved to the end of the list
+    project.go_cue_at(0, 0, start);
+    project.cue_lists[0].move_cue(0, 3);
+    assert_eq!(
+        project.pre_wait_remaining(0, 3, start),
+        Some(CueTime::from_millis(1000))
+    );
+    project.update(start + Duration::from_millis(1000));
+    assert_eq!(bonks(&project), vec![0, 0, 0, 1]);
+
+    // a list deleted while a pre-wait is pending is forgotten
+    project.go_cue_at(0, 0, start);
+    project.cue_lists.remove(0);
+    project.update(start + Duration::from_secs(10));
+    assert!(!project.sequence_pending());
+    assert_eq!(project.last_go(0, start), None);
+}
+
+#[test]
+fn removing_a_list_keeps_others_pending() {
+    let mut project = sequenced_project();
+    project.cue_lists.insert(0, CueList::new("Other"));
+    let start = Instant::now();
+
+    // the sequence carries on in its list once the list before it is gone
+    project.go_cue_at(1, 0, start);
+    assert!(project.remove_list(0).is_some());
+    assert!(project.sequence_pending());
+    assert_eq!(project.last_go(0, start), Some((0, CueTime::ZERO)));
+    project.update(start + Duration::from_millis(1000));
+    assert_eq!(bonks(&project), vec![1, 0, 0, 0]);
+
+    // and the last list stays
+    assert!(project.remove_list(0).is_none());
+}
+
+#[test]
+fn timing_round_trips() {
+    let mut project = sequenced_project();
+
+    let dir = std::env::temp_dir().join(format!("cueball-sequence-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("sequence.cueball");
+    project.save_to(&path).unwrap();
+
+    let reloaded = Project::read(&path).unwrap();
+    assert_eq!(reloaded.sequence(0, 0), project.sequence(0, 0));
+    assert_eq!(
+        reloaded.cue_lists[0][1].get_continue(),
+        ContinueMode::AutoFollow
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
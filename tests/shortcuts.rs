@@ -0,0 +1,95 @@
+use cueball::{
+    app::shortcuts::{Action, KeyBinding, KeyBindings, Shortcut, Shortcuts},
+    cues::AudioCue,
+    Cue, MultitypeCue, Project,
+};
+use egui::{Event, InputState, Key, Modifiers};
+
+fn press(key: Key) -> Event {
+    Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+fn input(events: Vec<Event>) -> InputState {
+    let mut inp = InputState::default();
+    inp.events = events;
+    inp
+}
+
+#[test]
+fn leaves_text_keys_to_text_fields() {
+    let mut shortcuts = Shortcuts::default();
+
+    // space types into a focused field rather than firing GO
+    let mut inp = input(vec![press(Key::Space), Event::Text(" ".to_string())]);
+    assert!(shortcuts.fired(&mut inp, true).is_empty());
+    assert_eq!(inp.events.len(), 2);
+    let mut inp = input(vec![press(Key::Space)]);
+    assert_eq!(shortcuts.fired(&mut inp, false), vec![Action::Go]);
+
+    // a function key doesn't type anything, so it fires anyway
+    shortcuts.set(Action::Go, Some(Shortcut::new(Key::F5)));
+    let mut inp = input(vec![press(Key::F5)]);
+    assert_eq!(shortcuts.fired(&mut inp, true), vec![Action::Go]);
+}
+
+#[test]
+fn fires_go_keys_while_typing() {
+    let mut project = Project::default();
+    project.cue_lists[0].go_key = Some("G".to_string());
+    for (id, hotkey) in [("1", "X"), ("2", "F6")] {
+        let mut cue = MultitypeCue::Audio(AudioCue::with_id(id.to_string()));
+        cue.set_hotkey(Some(hotkey.to_string()));
+        project.cue_lists[0].add(cue).unwrap();
+    }
+    let bindings = KeyBindings::new(&project, &[]);
+
+    // the list's GO key takes the character it would have typed
+    let mut inp = input(vec![
+        press(Key::G),
+        Event::Text("g".to_string()),
+        press(Key::X),
+        Event::Text("x".to_string()),
+        press(Key::F6),
+    ]);
+    assert_eq!(
+        bindings.pressed_hotkeys(&mut inp, true),
+        vec![KeyBinding::Go(0), KeyBinding::Cue(0, 1)]
+    );
+    assert_eq!(
+        inp.events,
+        vec![press(Key::X), Event::Text("x".to_string())]
+    );
+}
+
+#[test]
+fn fires_held_hotkeys_once() {
+    let mut project = Project::default();
+    let mut cue = MultitypeCue::Audio(AudioCue::with_id("1".to_string()));
+    cue.set_hotkey(Some("X".to_string()));
+    project.cue_lists[0].add(cue).unwrap();
+    let bindings = KeyBindings::new(&project, &[]);
+
+    let mut inp = input(vec![press(Key::X)]);
+    assert_eq!(
+        bindings.pressed_hotkeys(&mut inp, false),
+        vec![KeyBinding::Cue(0, 0)]
+    );
+
+    // the key repeating while it's held down doesn't fire the cue again
+    let held = Event::Key {
+        key: Key::X,
+        physical_key: None,
+        pressed: true,
+        repeat: true,
+        modifiers: Modifiers::NONE,
+    };
+    let mut inp = input(vec![held.clone()]);
+    assert!(bindings.pressed_hotkeys(&mut inp, false).is_empty());
+    assert_eq!(inp.events, vec![held]);
+}
@@ -0,0 +1,73 @@
+use cueball::cues::{AudioCue, CueTime, TimeFormat};
+
+#[test]
+fn formats_and_parses() {
+    let time = CueTime::from_millis(83_250);
+    assert_eq!(time.format(TimeFormat::Clock), "01:23.250");
+    assert_eq!(
+        CueTime::from_millis(3_723_004).format(TimeFormat::Clock),
+        "1:02:03.004"
+    );
+    assert_eq!((-time).format(TimeFormat::Clock), "-01:23.250");
+
+    let smpte = TimeFormat::Smpte { fps: 25 };
+    assert_eq!(time.format(smpte), "00:01:23:06");
+    assert_eq!(
+        CueTime::parse("00:01:23:06", smpte),
+        Some(CueTime::from_millis(83_240))
+    );
+    // fields past their range, and times too long to hold, aren't times
+    for bad in [
+        "00:01:23:25",
+        "00:60:00:00",
+        "00:00:60:00",
+        "4294967295:00:00:00",
+    ] {
+        assert_eq!(CueTime::parse(bad, smpte), None, "{}", bad);
+    }
+
+    // 2 beats a second, so 83.25 s is 166.5 beats
+    let beats = TimeFormat::Beats {
+        bpm: 120.,
+        per_bar: 4,
+    };
+    assert_eq!(time.format(beats), "41.2.480");
+    assert_eq!(CueTime::parse("41.2.480", beats), Some(time));
+
+    // plain seconds and clock times are understood whatever the format
+    for format in TimeFormat::kinds() {
+        assert_eq!(CueTime::parse("83.25", format), Some(time));
+        assert_eq!(CueTime::parse("1:23.25", format), Some(time));
+    }
+    assert_eq!(CueTime::parse("soon", TimeFormat::Clock), None);
+
+    // editing keeps microseconds, but doesn't pad whole milliseconds out
+    let fine = time + CueTime::from_nanos(12_000);
+    assert_eq!(fine.format_precise(TimeFormat::Clock), "01:23.250012");
+    assert_eq!(
+        CueTime::parse("01:23.250012", TimeFormat::Clock),
+        Some(fine)
+    );
+    assert_eq!(time.format_precise(TimeFormat::Clock), "01:23.250");
+}
+
+#[test]
+fn stays_sample_exact() {
+    // f32 seconds can't tell samples apart this far into a file
+    let rate = 48000;
+    let frames = 3 * 60 * 60 * rate as i64 + 1;
+    let time = CueTime::from_frames(frames, rate);
+    assert_eq!(time.to_frames(rate), frames);
+
+    // nudging a trim along doesn't drift the way float seconds do
+    let mut cue = AudioCue::with_id("1".to_string());
+    for _ in 0..10 {
+        cue.start += CueTime::from_secs(0.1);
+    }
+    assert_eq!(cue.start, CueTime::from_millis(1000));
+
+    // old project files stored f32 seconds
+    let old: CueTime = serde_json::from_str("1.5").unwrap();
+    assert_eq!(old, CueTime::from_millis(1500));
+    assert_eq!(serde_json::to_string(&old).unwrap(), "1.5");
+}